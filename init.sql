@@ -15,7 +15,7 @@ INSERT INTO t_revenue_data (platform_name, revenue, report_date) VALUES
 CREATE TABLE data_sources (
     id VARCHAR(50) PRIMARY KEY,       -- 唯一标识，如 "finance_db"
     db_type VARCHAR(20) NOT NULL,     -- postgres, mysql
-    connection_url TEXT NOT NULL,     -- 加密存储或直接存储连接串
+    connection_url TEXT NOT NULL,     -- AES-256-GCM 密文 (enc:v1:...)，仅在建连时解密
     display_name VARCHAR(100),
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
@@ -102,4 +102,222 @@ ALTER TABLE semantic_definitions RENAME COLUMN target_column TO sql_expression;
 ALTER TABLE ontology_nodes ADD COLUMN semantic_type VARCHAR(20) DEFAULT 'STRING';
 
 -- 2. 扩展定义表：增加格式化字符串（针对日期）
-ALTER TABLE semantic_definitions ADD COLUMN value_format VARCHAR(50) DEFAULT 'yyyy-MM-dd';
\ No newline at end of file
+ALTER TABLE semantic_definitions ADD COLUMN value_format VARCHAR(50) DEFAULT 'yyyy-MM-dd';
+-- 3. 指标展示格式：计量单位与数值格式（用于自然语言答案生成）
+-- 备注：number_format 采用 "#,##0.00" / "0.0%" / "0" 风格的模板
+ALTER TABLE semantic_definitions ADD COLUMN unit VARCHAR(20);
+ALTER TABLE semantic_definitions ADD COLUMN number_format VARCHAR(50);
+
+-- 4. 影子执行差异库：规则推理与 LLM 规划不一致的样本（用于微调）
+CREATE TABLE shadow_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    question TEXT NOT NULL,
+    provider VARCHAR(100) NOT NULL,
+    rule_plan JSONB NOT NULL,          -- 规范化后的规则计划
+    llm_plan JSONB,                    -- 规范化后的 LLM 计划（校验失败时为原始草案）
+    rule_hash VARCHAR(64) NOT NULL,
+    llm_hash VARCHAR(64),
+    llm_error TEXT,                    -- LLM 调用或校验失败原因
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- 5. 问答日志：每次对话落库，作为反馈与审计的锚点
+CREATE TABLE query_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    question TEXT NOT NULL,
+    plan JSONB,                        -- 推理得到的逻辑计划（推理失败时为空）
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- 6. 用户反馈：点赞/点踩与纠正，审核采纳后沉淀为别名或 A-Box 码值标签
+CREATE TABLE query_feedback (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    query_id UUID NOT NULL REFERENCES query_log(id) ON DELETE CASCADE,
+    rating VARCHAR(10) NOT NULL CHECK (rating IN ('UP', 'DOWN')),
+    comment TEXT,
+    corrected_metric VARCHAR(100),     -- 正确指标 node_key
+    corrected_dimension VARCHAR(100),  -- 正确维度 node_key
+    corrected_value VARCHAR(200),      -- 正确维度码值
+    phrase VARCHAR(200),               -- 用户口语表达
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'ACCEPTED', 'REJECTED')),
+    review_note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    reviewed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- 7. 问答审计：补齐会话、编译 SQL、数据源、耗时、行数与执行状态
+ALTER TABLE query_log ADD COLUMN session_id VARCHAR(100);
+ALTER TABLE query_log ADD COLUMN inference_mode VARCHAR(50);  -- rule / llm:<provider>
+ALTER TABLE query_log ADD COLUMN compiled_sql TEXT;
+ALTER TABLE query_log ADD COLUMN source_id VARCHAR(50);
+ALTER TABLE query_log ADD COLUMN latency_ms BIGINT;
+ALTER TABLE query_log ADD COLUMN row_count INT;
+ALTER TABLE query_log ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'success'; -- success / fail / error
+ALTER TABLE query_log ADD COLUMN error TEXT;
+ALTER TABLE query_log ADD COLUMN replay_of UUID REFERENCES query_log(id) ON DELETE SET NULL;
+CREATE INDEX idx_query_log_created_at ON query_log(created_at DESC);
+CREATE INDEX idx_query_log_session ON query_log(session_id);
+
+-- 8. 鉴权：API Key 仅保存 SHA-256 摘要，明文只在创建时返回一次
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    key_hash VARCHAR(64) UNIQUE NOT NULL,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('analyst', 'modeler', 'admin')),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- 问答审计：记录提问人
+ALTER TABLE query_log ADD COLUMN user_id VARCHAR(100);
+
+-- 9. 行列级安全：挂在节点或数据源上的按角色策略
+CREATE TABLE security_policies (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    role VARCHAR(20) CHECK (role IN ('analyst', 'modeler')), -- 为空表示所有非管理员角色
+    node_id UUID REFERENCES ontology_nodes(id) ON DELETE CASCADE,
+    source_id VARCHAR(50) REFERENCES data_sources(id) ON DELETE CASCADE,
+    effect VARCHAR(20) NOT NULL CHECK (effect IN ('ROW_FILTER', 'HIDE')),
+    predicate TEXT,                    -- 如 "platform_name IN user.platforms"
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CHECK ((node_id IS NULL) <> (source_id IS NULL)),
+    CHECK (effect <> 'ROW_FILTER' OR predicate IS NOT NULL)
+);
+
+-- 调用方属性（行级安全谓词中的 user.<属性>）
+ALTER TABLE api_keys ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+
+-- 10. 数据源凭据：连接串加密存储，另存脱敏串用于展示
+ALTER TABLE data_sources ADD COLUMN masked_url TEXT;
+
+-- 11. 数据源连接池参数（为空时使用默认值）
+ALTER TABLE data_sources ADD COLUMN max_connections INT;
+ALTER TABLE data_sources ADD COLUMN min_connections INT;
+ALTER TABLE data_sources ADD COLUMN acquire_timeout_secs INT;
+ALTER TABLE data_sources ADD COLUMN idle_timeout_secs INT;
+
+-- 12. 表间关联发现：外键与 *_id 命名启发式产出候选，建模人员确认后生效（驳回的候选保留，避免重复发现）
+ALTER TABLE ontology_relations ADD COLUMN source_id VARCHAR(50) REFERENCES data_sources(id) ON DELETE CASCADE;
+ALTER TABLE ontology_relations ADD COLUMN origin VARCHAR(20) NOT NULL DEFAULT 'MANUAL' CHECK (origin IN ('FK', 'HEURISTIC', 'MANUAL'));
+ALTER TABLE ontology_relations ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'ACCEPTED' CHECK (status IN ('PROPOSED', 'ACCEPTED', 'REJECTED'));
+ALTER TABLE ontology_relations ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+CREATE UNIQUE INDEX uq_ontology_relations ON ontology_relations (source_id, from_table, to_table, join_logic);
+
+-- 13. A-Box 同步：码值活跃标记、按维度调度与同步运行记录
+-- 备注：物理库中已消失的码值标记为 is_active = FALSE（保留反馈习得的标签，码值重现时自动恢复）
+ALTER TABLE dimension_values ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE dimension_values ADD COLUMN last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+CREATE TABLE dimension_sync_schedules (
+    dimension_node_id UUID PRIMARY KEY REFERENCES ontology_nodes(id) ON DELETE CASCADE,
+    interval_secs INT NOT NULL CHECK (interval_secs >= 60),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE sync_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    dimension_node_id UUID NOT NULL REFERENCES ontology_nodes(id) ON DELETE CASCADE,
+    trigger VARCHAR(20) NOT NULL CHECK (trigger IN ('MANUAL', 'SCHEDULED')),
+    status VARCHAR(20) NOT NULL CHECK (status IN ('RUNNING', 'SUCCESS', 'FAILED')),
+    scanned_count INT NOT NULL DEFAULT 0,      -- 物理库去重码值数
+    inserted_count INT NOT NULL DEFAULT 0,     -- 新增码值
+    reactivated_count INT NOT NULL DEFAULT 0,  -- 重新出现的失效码值
+    deactivated_count INT NOT NULL DEFAULT 0,  -- 本次标记失效的码值
+    error TEXT,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX idx_sync_runs_node ON sync_runs (dimension_node_id, started_at DESC);
+
+-- 14. A-Box 码值与标签分离：每个码值一行（展示标签可来自标签列或码表），口语同义词单独存放
+CREATE TABLE dimension_label_sources (
+    dimension_node_id UUID PRIMARY KEY REFERENCES ontology_nodes(id) ON DELETE CASCADE,
+    label_expression TEXT NOT NULL,     -- 标签列或表达式，如 "platform_name"
+    lookup_table VARCHAR(200),          -- 码表，如 "dim_platform"；为空表示标签列与维度位于同一张表
+    lookup_code_column VARCHAR(100),    -- 码表中的码值列
+    CHECK ((lookup_table IS NULL) = (lookup_code_column IS NULL))
+);
+
+-- 人工修改过的标签不再被同步覆盖
+ALTER TABLE dimension_values ADD COLUMN label_locked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE sync_runs ADD COLUMN relabeled_count INT NOT NULL DEFAULT 0; -- 本次更新标签的码值
+
+CREATE TABLE dimension_value_synonyms (
+    id SERIAL PRIMARY KEY,
+    value_id INT NOT NULL REFERENCES dimension_values(id) ON DELETE CASCADE,
+    synonym VARCHAR(200) NOT NULL,      -- 如 "阿里" -> 码值 "A公司"
+    origin VARCHAR(20) NOT NULL DEFAULT 'MANUAL' CHECK (origin IN ('MANUAL', 'FEEDBACK')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (value_id, synonym)
+);
+
+-- 迁移：历史上以 "口语 -> 码值" 形式写入的行转为同义词，码值本身补齐为一行
+INSERT INTO dimension_values (dimension_node_id, value_label, value_code)
+SELECT DISTINCT d.dimension_node_id, d.value_code, d.value_code FROM dimension_values d
+WHERE d.value_label <> d.value_code
+ON CONFLICT (dimension_node_id, value_label) DO NOTHING;
+
+INSERT INTO dimension_value_synonyms (value_id, synonym, origin)
+SELECT m.id, d.value_label, 'FEEDBACK' FROM dimension_values d
+JOIN dimension_values m ON m.dimension_node_id = d.dimension_node_id AND m.value_code = d.value_code AND m.value_label = m.value_code
+WHERE d.value_label <> d.value_code;
+
+DELETE FROM dimension_values d WHERE d.value_label <> d.value_code AND EXISTS (
+    SELECT 1 FROM dimension_values m
+    WHERE m.dimension_node_id = d.dimension_node_id AND m.value_code = d.value_code AND m.value_label = m.value_code
+);
+
+CREATE UNIQUE INDEX uq_dimension_values_code ON dimension_values (dimension_node_id, value_code);
+
+-- 15. 码值归一化索引：全角转半角、小写、剥离 "有限公司"/"公司" 等后缀后的形式，用于容错匹配
+-- 备注：归一化在应用层计算（写入码值/同义词时填充，启动时补齐空值）
+ALTER TABLE dimension_values ADD COLUMN normalized_label VARCHAR(200);
+ALTER TABLE dimension_value_synonyms ADD COLUMN normalized VARCHAR(200);
+CREATE INDEX idx_dimension_values_normalized ON dimension_values (normalized_label);
+CREATE INDEX idx_value_synonyms_normalized ON dimension_value_synonyms (normalized);
+
+-- 16. 问数按业务域限定：记录提问时指定的业务域，重放时沿用
+ALTER TABLE query_log ADD COLUMN domain_key VARCHAR(100);
+
+-- 17. 本体版本化：节点 / 定义 / T-Box 关联的每次变更记为一个修订（全局递增版本号）
+-- snapshot 为变更后的完整节点定义（删除时为空），diff 为与上一修订的字段级差异
+CREATE TABLE ontology_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    node_key VARCHAR(100) NOT NULL,
+    node_id UUID,                       -- 不设外键：节点删除后历史仍保留
+    action VARCHAR(10) NOT NULL CHECK (action IN ('CREATE', 'UPDATE', 'DELETE')),
+    snapshot JSONB,
+    diff JSONB NOT NULL DEFAULT '[]',
+    changed_by VARCHAR(200),
+    via VARCHAR(50),                    -- mapping / bootstrap / bundle / feedback / rollback:<版本> / discard / baseline
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_ontology_revisions_node ON ontology_revisions (node_key, id DESC);
+
+-- 发布快照：version 为发布水位，推理引擎只加载该版本及之前的最新修订，实时本体即草稿
+-- 生效发布为最新一条未撤回 (withdrawn_at 为空) 的发布；全部撤回时推理引擎回到实时本体
+CREATE TABLE ontology_publications (
+    id BIGSERIAL PRIMARY KEY,
+    version BIGINT NOT NULL,
+    note TEXT,
+    published_by VARCHAR(200),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    withdrawn_at TIMESTAMP WITH TIME ZONE
+);
+
+-- 18. A-Box 标签不再唯一：码值 (dimension_node_id, value_code) 是唯一键，标签重复不应导致码值丢失
+-- 同步时标签已被其他码值占用的码值以码值本身作标签，并计入 label_conflict_count
+ALTER TABLE dimension_values DROP CONSTRAINT dimension_values_dimension_node_id_value_label_key;
+CREATE INDEX idx_dimension_values_label ON dimension_values (dimension_node_id, value_label);
+ALTER TABLE sync_runs ADD COLUMN label_conflict_count INT NOT NULL DEFAULT 0;
+
+-- 19. 草稿删除：节点只打删除标记，发布后才物理删除
+-- 物理删除会级联清理安全策略、A-Box 码值与同义词、标签来源与同步调度，而发布快照仍在服务该节点；
+-- 因此删除标记保留到不再被任何未撤回的发布引用为止（发布 / 撤回时清理）
+ALTER TABLE ontology_nodes ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- 20. 关联纳入修订：ontology_relations 的变更与节点一样记为修订（node_key 为 relation:<id>，node_id 为空）
+ALTER TABLE ontology_revisions ADD COLUMN kind VARCHAR(10) NOT NULL DEFAULT 'NODE' CHECK (kind IN ('NODE', 'RELATION'));
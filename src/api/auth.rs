@@ -0,0 +1,221 @@
+use crate::ax_state::AppState;
+use crate::models::auth::{ApiKeyInfo, Claims, CreateApiKeyRequest, Principal, Role};
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use sha2::{Digest, Sha256};
+use sqlx::types::Json as SqlJson;
+use sqlx::{Postgres, Row};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// API Key 请求头
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// 鉴权配置（启动时从环境变量加载）
+/// - AUTH_ENABLED=false 时关闭鉴权（仅限本地开发）
+/// - JWT_SECRET：HS256 签名密钥，Bearer Token 在本地校验
+/// - SSE_ADMIN_API_KEY：引导用的管理员 Key，用于创建首批 API Key
+pub struct AuthConfig {
+    pub enabled: bool,
+    jwt_key: Option<DecodingKey>,
+    bootstrap_key_hash: Option<String>,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        let enabled = env::var("AUTH_ENABLED").map(|v| v != "false" && v != "0").unwrap_or(true);
+        if !enabled {
+            warn!("⚠️ 鉴权已关闭 (AUTH_ENABLED=false)，所有请求将以管理员身份处理");
+        }
+        Self {
+            enabled,
+            jwt_key: env::var("JWT_SECRET")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .map(|s| DecodingKey::from_secret(s.as_bytes())),
+            bootstrap_key_hash: env::var("SSE_ADMIN_API_KEY")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .map(|k| hash_key(&k)),
+        }
+    }
+}
+
+/// API Key 摘要 (SHA-256 hex)，数据库只保存摘要
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+// --- 1. 中间件 ---
+
+/// 鉴权中间件：解析 Bearer JWT 或 X-API-Key，将 Principal 注入请求扩展
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let principal = if state.auth.enabled {
+        match resolve_principal(&state, req.headers()).await {
+            Ok(p) => p,
+            Err(msg) => return (StatusCode::UNAUTHORIZED, msg).into_response(),
+        }
+    } else {
+        Principal {
+            subject: "anonymous".to_string(),
+            role: Role::Admin,
+            auth_method: "disabled".to_string(),
+            attributes: HashMap::new(),
+        }
+    };
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
+
+/// 路由级角色校验：建模师及以上
+pub async fn require_modeler(req: Request, next: Next) -> Response {
+    require_role(Role::Modeler, req, next).await
+}
+
+/// 路由级角色校验：管理员
+pub async fn require_admin(req: Request, next: Next) -> Response {
+    require_role(Role::Admin, req, next).await
+}
+
+async fn require_role(min: Role, req: Request, next: Next) -> Response {
+    match req.extensions().get::<Principal>() {
+        Some(p) if p.role >= min => next.run(req).await,
+        Some(p) => (
+            StatusCode::FORBIDDEN,
+            format!("角色 {} 无权访问该接口 (需要 {})", p.role.as_str(), min.as_str()),
+        )
+            .into_response(),
+        None => (StatusCode::UNAUTHORIZED, "未认证").into_response(),
+    }
+}
+
+async fn resolve_principal(state: &AppState, headers: &HeaderMap) -> Result<Principal, String> {
+    // A. Bearer JWT
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        let key = state.auth.jwt_key.as_ref().ok_or("服务端未配置 JWT_SECRET，无法校验 Bearer Token")?;
+        let data = decode::<Claims>(token.trim(), key, &Validation::new(Algorithm::HS256))
+            .map_err(|e| format!("无效的 Token: {}", e))?;
+        let role = Role::parse(&data.claims.role).ok_or_else(|| format!("未知角色: {}", data.claims.role))?;
+        return Ok(Principal {
+            subject: data.claims.sub,
+            role,
+            auth_method: "jwt".to_string(),
+            attributes: data.claims.attrs,
+        });
+    }
+
+    // B. API Key
+    if let Some(key) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        let digest = hash_key(key.trim());
+        if state.auth.bootstrap_key_hash.as_deref() == Some(digest.as_str()) {
+            return Ok(Principal {
+                subject: "bootstrap-admin".to_string(),
+                role: Role::Admin,
+                auth_method: "api_key".to_string(),
+                attributes: HashMap::new(),
+            });
+        }
+        let row = sqlx::query("SELECT name, role, attributes FROM api_keys WHERE key_hash = $1 AND enabled")
+            .bind(&digest)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("无效或已吊销的 API Key")?;
+        let role_str: String = row.get("role");
+        let SqlJson(attributes) = row.get::<SqlJson<HashMap<String, Vec<String>>>, _>("attributes");
+        return Ok(Principal {
+            subject: row.get("name"),
+            role: Role::parse(&role_str).ok_or_else(|| format!("未知角色: {}", role_str))?,
+            auth_method: "api_key".to_string(),
+            attributes,
+        });
+    }
+
+    Err("缺少认证信息：请提供 Authorization: Bearer <JWT> 或 X-API-Key".to_string())
+}
+
+// --- 2. 身份与 API Key 管理 ---
+
+/// 当前调用方身份
+pub async fn whoami(Extension(principal): Extension<Principal>) -> impl IntoResponse {
+    Json(principal)
+}
+
+/// 创建 API Key（明文仅在此处返回一次）
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    let Some(role) = Role::parse(&payload.role) else {
+        return (StatusCode::BAD_REQUEST, "role 仅支持 analyst / modeler / admin").into_response();
+    };
+    let plain = format!("sse_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let res = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO api_keys (name, key_hash, role, attributes) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(&payload.name)
+    .bind(hash_key(&plain))
+    .bind(role.as_str())
+    .bind(SqlJson(&payload.attributes))
+    .fetch_one(&state.db)
+    .await;
+    match res {
+        Ok(id) => {
+            info!("API Key 已创建: name={}, role={}", payload.name, role.as_str());
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({ "id": id, "name": payload.name, "role": role, "api_key": plain })),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn list_api_keys(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, ApiKeyInfo>(
+        "SELECT id, name, role, attributes, enabled, created_at FROM api_keys ORDER BY created_at",
+    )
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 吊销 API Key（保留记录以便审计）
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match sqlx::query("UPDATE api_keys SET enabled = FALSE WHERE id = $1")
+        .bind(id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "API Key not found").into_response(),
+        Ok(_) => {
+            info!("API Key 已吊销: id={}", id);
+            StatusCode::OK.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
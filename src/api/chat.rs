@@ -1,102 +1,121 @@
-use axum::{extract::State, response::IntoResponse, Json};
-use serde_json::json;
+use axum::{extract::State, response::IntoResponse, Extension, Json};
+use serde_json::{json, Value};
 use std::sync::Arc;
+use uuid::Uuid;
 
 // 导入项目内部组件
+use crate::api::domains::nodes_outside_domain;
 use crate::ax_state::AppState;
+use crate::models::auth::Principal;
 use crate::models::context::ChatRequest;
-use crate::models::schema::DataSource; // 保持导入
-use crate::infra::db_external::DynamicPool;
-use crate::infra::db_internal::{pg_row_to_json, mysql_row_to_json};
+use crate::models::schema::{DataSource, FullSemanticNode, QueryLogicalPlan}; // 保持导入
+use crate::core::inference::InferenceError;
+use crate::core::{llm_fallback, planner};
+use crate::infra::connector::SqlDialect;
+use crate::service::query_log::{self, QueryLogEntry};
+use crate::service::security::SecurityContext;
+use crate::service::shadow::ShadowJob;
+use crate::service::{answer, chart};
 use tracing::{info, warn, error, instrument};
 
 /// 语义问数对话核心接口
-#[instrument(skip(state, payload), fields(user_query = %payload.query))]
+#[instrument(skip(state, principal, payload), fields(user_query = %payload.query, user = %principal.subject))]
 pub async fn chat_query(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
     Json(payload): Json<ChatRequest>,
 ) -> impl IntoResponse {
     let query_text = payload.query.trim();
-
-    // 1. 获取推理引擎单例（已预装载自定义词典）
-    let engine = state.engine.read().await;
-
-    // 2. 执行深度语义推理
-    let inference = match engine.infer(state.clone(), query_text).await {
-        Ok(res) => res,
+    let mut entry = QueryLogEntry::new(query_text, payload.session_id.clone());
+    entry.user_id = Some(principal.subject.clone());
+    entry.domain_key = payload.domain.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(String::from);
+
+    let mut body = run_pipeline(&state, &principal, query_text, &mut entry).await;
+
+    // 审计：无论成败都落库，并把日志 ID 回传给前端用于反馈
+    let query_id = query_log::record(&state, &entry).await;
+    body["query_id"] = json!(query_id);
+    Json(body).into_response()
+}
+
+/// 问答流水线：推理 -> 逻辑计划 -> 安全策略 -> SQL 编译 -> 数据源路由 -> 执行 -> 答案与图表
+/// 过程信息写入 entry，由调用方统一落审计日志
+pub(crate) async fn run_pipeline(
+    state: &Arc<AppState>,
+    principal: &Principal,
+    query_text: &str,
+    entry: &mut QueryLogEntry,
+) -> serde_json::Value {
+    // 0. 加载调用方的行列级安全上下文
+    let security = match SecurityContext::load(state, principal).await {
+        Ok(s) => s,
         Err(e) => {
-            warn!("语义推理未命中: {}", e);
-            return Json(json!({
-                "status": "fail",
-                "answer": format!("抱歉，我理解不了这个提问：{}", e)
-            }))
-            .into_response();
+            error!("安全策略加载失败: {}", e);
+            entry.fail("error", format!("安全策略加载失败: {}", e));
+            return json!({"status": "error", "message": "安全策略加载失败"});
         }
     };
 
-    let metric = inference.metric;
-    let filters = inference.filters;
-
-    // 3. 确定聚合逻辑
-    let agg = if query_text.contains("平均") {
-        "AVG"
-    } else if metric.default_agg == "NONE" {
-        "NONE"
-    } else {
-        &metric.default_agg
-    };
+    // 0.1 业务域限定：域外节点与无权节点一样不参与匹配（未归属任何业务域的节点视为公共节点）
+    let mut excluded = security.hidden_nodes.clone();
+    if let Some(domain) = entry.domain_key.clone() {
+        match nodes_outside_domain(&state.db, &domain).await {
+            Ok(Some(outside)) => excluded.extend(outside),
+            Ok(None) => {
+                entry.fail("fail", format!("未知业务域: {}", domain));
+                return json!({"status": "fail", "answer": format!("抱歉，业务域 {} 不存在", domain)});
+            }
+            Err(e) => {
+                error!("业务域范围加载失败: {}", e);
+                entry.fail("error", format!("业务域范围加载失败: {}", e));
+                return json!({"status": "error", "message": "业务域范围加载失败"});
+            }
+        }
+    }
 
-    // 4. 构造 SELECT 子句
-    let metric_item = if agg == "NONE" {
-        format!("{} as \"{}\"", metric.sql_expression, metric.label)
-    } else {
-        format!("{}({}) as \"{}\"", agg, metric.sql_expression, metric.label)
+    // 1. 获取推理引擎单例（已预装载自定义词典），推理完成即释放读锁
+    // LLM 兜底是外部调用，持锁等待会阻塞引擎重载，进而（公平锁）阻塞其他问数
+    let inferred = {
+        let engine = state.engine.read().await;
+        engine.infer(state.clone(), query_text, &excluded).await
     };
 
-    // 5. 组装 SQL 片段
-    let mut select_items = vec![metric_item];
-    let mut where_conds = vec!["1=1".to_string()];
-    let mut group_by_items = Vec::new();
-
-    for (dim_node, val_code) in &filters {
-        where_conds.push(format!("{} = '{}'", dim_node.sql_expression, val_code));
-        select_items.insert(
-            0,
-            format!("{} as \"{}\"", dim_node.sql_expression, dim_node.label),
-        );
-        if agg != "NONE" {
-            group_by_items.push(dim_node.sql_expression.clone());
+    // 2. 执行深度语义推理（未识别到指标锚点时转交 LLM 兜底，产出结构化计划）
+    let planned = match inferred {
+        Ok(res) => Ok((planner::build_plan(query_text, res), "rule".to_string())),
+        Err(e) => {
+            let no_anchor = matches!(e.downcast_ref::<InferenceError>(), Some(InferenceError::NoMetricAnchor));
+            match state.llm.as_ref().filter(|_| no_anchor) {
+                Some(llm) => match llm_fallback::plan_with_llm(state, llm.as_ref(), query_text, &excluded).await {
+                    Ok(plan) => Ok((plan, format!("llm:{}", llm.name()))),
+                    Err(le) => {
+                        warn!("LLM 兜底规划失败: {}", le);
+                        Err(e)
+                    }
+                },
+                None => {
+                    warn!("语义推理未命中: {}", e);
+                    Err(e)
+                }
+            }
         }
-    }
+    };
 
-    // 6. 注入业务隐含约束
-    for c in &metric.default_constraints.0 {
-        where_conds.push(format!("{} {} '{}'", c.column, c.operator, c.value));
-    }
-    for (dim_node, _) in &filters {
-        for c in &dim_node.default_constraints.0 {
-            where_conds.push(format!("{} {} '{}'", c.column, c.operator, c.value));
+    let (mut plan, inference_mode) = match planned {
+        Ok(p) => p,
+        Err(e) => {
+            entry.fail("fail", e.to_string());
+            return json!({
+                "status": "fail",
+                "answer": format!("抱歉，我理解不了这个提问：{}", e)
+            });
         }
-    }
-
-    // 7. 拼装物理 SQL
-    let select_clause = select_items.join(", ");
-    let where_clause = where_conds.join(" AND ");
-    let mut sql = format!(
-        "SELECT {} FROM {} WHERE {}",
-        select_clause, metric.target_table, where_clause
-    );
-
-    if !group_by_items.is_empty() {
-        sql.push_str(&format!(" GROUP BY {}", group_by_items.join(", ")));
-    }
-
-    info!("🚀 语义推理完成，生成 SQL: {}", sql);
+    };
 
-    // 8. 动态路由数据源 (修复点：直接使用导入的 DataSource 类型)
+    // 3. 动态路由数据源（行级谓词需按其方言转义）
     let source_res: Result<DataSource, _> =
         sqlx::query_as("SELECT * FROM data_sources WHERE id = $1")
-            .bind(&metric.source_id)
+            .bind(&plan.metric.source_id)
             .fetch_one(&state.db)
             .await;
 
@@ -104,66 +123,120 @@ pub async fn chat_query(
         Ok(s) => s,
         Err(_) => {
             error!("无法找到该指标对应的数据源配置");
-            return Json(json!({"status": "error", "message": "无法找到该指标对应的数据源配置"}))
-                .into_response()
+            entry.fail("error", "无法找到该指标对应的数据源配置".to_string());
+            return json!({"status": "error", "message": "无法找到该指标对应的数据源配置"});
         }
     };
+    let Some(dialect) = SqlDialect::from_db_type(&source.db_type) else {
+        entry.fail("error", format!("不支持的数据源类型: {}", source.db_type));
+        return json!({"status": "error", "message": format!("不支持的数据源类型: {}", source.db_type)});
+    };
+
+    // 强制注入行级安全谓词（与业务隐含约束一同编译）
+    if let Err(e) = security.apply(&mut plan, dialect) {
+        warn!("安全策略拒绝: {}", e);
+        entry.fail("fail", e.clone());
+        return json!({"status": "fail", "answer": format!("抱歉，{}", e)});
+    }
+    entry.plan = Some(plan.clone());
+    entry.inference_mode = Some(inference_mode.clone());
+
+    let metric = &plan.metric;
+    let filters = &plan.dimensions;
+    let agg = plan.final_agg.as_str();
+    entry.source_id = Some(metric.source_id.clone());
+
+    // 规则推理的结果异步投递至影子执行流水线，不影响本次响应
+    if inference_mode == "rule" {
+        if let Some(shadow) = &state.shadow {
+            shadow.submit(ShadowJob {
+                question: query_text.to_string(),
+                plan: plan.clone(),
+            });
+        }
+    }
 
-    let pool = match state.pool_manager.get_or_create_pool(&source).await {
+    let connector = match state.pool_manager.get_or_create_pool(&source).await {
         Ok(p) => p,
         Err(e) => {
             error!("无法建立数据库连接");
-            return Json(json!({"status": "error", "message": format!("无法建立数据库连接: {}", e)}))
-                .into_response()
+            entry.fail("error", format!("无法建立数据库连接: {}", e));
+            return json!({"status": "error", "message": format!("无法建立数据库连接: {}", e)});
         }
     };
 
     let start_time = std::time::Instant::now();
 
-    // 9. 执行查询
-    match &*pool {
-        DynamicPool::Postgres(p) => {
-            let rows_result = sqlx::query(&sql).fetch_all(p).await;
-            match rows_result {
-                Ok(rows) => {
-                    let data: Vec<serde_json::Value> = rows.iter().map(pg_row_to_json).collect();
-                    info!(
-                        "✅ 查询成功 - 耗时: {:?}, 返回 {} 行",
-                        start_time.elapsed(),
-                        rows.len()
-                    );
-                    Json(json!({
-                        "status": "success",
-                        "sql": sql,
-                        "logic": format!("指标: {}, 关联维度: {}, 聚合: {}", metric.label, filters.len(), agg),
-                        "data": data
-                    })).into_response()
-                }
-                Err(e) => {
-                    error!("SQL执行失败: {}", e);
-                    Json(json!({"status": "error", "message": format!("物理库执行失败: {}", e)}))
-                        .into_response()
-                }
+    // 4. 按数据源方言编译逻辑计划并执行
+    let (executed_sql, rows_result) = connector.execute_plan(&plan).await;
+    info!("🚀 语义推理完成，生成 SQL: {}", executed_sql);
+    entry.compiled_sql = Some(executed_sql.clone());
+
+    match rows_result {
+        Ok(data) => {
+            info!(
+                "✅ 查询成功 - 耗时: {:?}, 返回 {} 行",
+                start_time.elapsed(),
+                data.len()
+            );
+            entry.row_count = Some(data.len() as i32);
+            entry.status = "success".to_string();
+            let labels = load_value_labels(state, &plan, &data).await;
+            let answer = answer::summarize(query_text, metric, filters, agg, &data, &labels);
+            let chart = chart::recommend(query_text, &plan, data.len());
+            json!({
+                "status": "success",
+                "answer": answer,
+                "chart": chart,
+                "inference_mode": inference_mode,
+                "sql": executed_sql,
+                "logic": format!("指标: {}, 关联维度: {}, 聚合: {}", metric.label, filters.len(), agg),
+                "data": data
+            })
+        }
+        Err(e) => {
+            error!("SQL执行失败: {}", e);
+            entry.fail("error", format!("物理库执行失败: {}", e));
+            json!({"status": "error", "message": format!("物理库执行失败: {}", e)})
+        }
+    }
+}
+
+/// 答案中出现的维度码值（过滤值与分组结果）对应的业务标签；查询失败时退回播报码值
+async fn load_value_labels(state: &AppState, plan: &QueryLogicalPlan, rows: &[Value]) -> answer::ValueLabels {
+    let dims: Vec<&FullSemanticNode> = plan.dimensions.iter().map(|(d, _)| d).chain(plan.group_by.iter()).collect();
+    if dims.is_empty() {
+        return answer::ValueLabels::new();
+    }
+    let mut codes: Vec<String> = plan.dimensions.iter().map(|(_, v)| v.clone()).collect();
+    for row in rows {
+        for d in &dims {
+            match row.get(&d.label) {
+                Some(Value::String(s)) => codes.push(s.clone()),
+                Some(Value::Null) | None => {}
+                Some(other) => codes.push(other.to_string()),
             }
         }
-        DynamicPool::MySql(p) => {
-            let sql_mysql = sql.replace("$1", "?"); 
-            let rows_result = sqlx::query(&sql_mysql).fetch_all(p).await;
-            match rows_result {
-                Ok(rows) => {
-                    let data: Vec<serde_json::Value> = rows.iter().map(mysql_row_to_json).collect();
-                    Json(json!({
-                        "status": "success",
-                        "sql": sql_mysql,
-                        "logic": format!("指标: {}, 关联维度: {}, 聚合: {}", metric.label, filters.len(), agg),
-                        "data": data
-                    })).into_response()
-                }
-                Err(e) => {
-                    Json(json!({"status": "error", "message": format!("MySQL执行失败: {}", e)}))
-                        .into_response()
+    }
+    let ids: Vec<Uuid> = dims.iter().map(|d| d.id).collect();
+    let res = sqlx::query_as::<_, (Uuid, String, String)>(
+        "SELECT dimension_node_id, value_code, value_label FROM dimension_values
+         WHERE dimension_node_id = ANY($1) AND value_code = ANY($2)",
+    )
+    .bind(&ids)
+    .bind(&codes)
+    .fetch_all(&state.db)
+    .await;
+    let mut labels = answer::ValueLabels::new();
+    match res {
+        Ok(found) => {
+            for (dim_id, code, label) in found {
+                if let Some(d) = dims.iter().find(|d| d.id == dim_id) {
+                    labels.entry(d.label.clone()).or_default().insert(code, label);
                 }
             }
         }
+        Err(e) => warn!("维度码值标签查询失败，答案中将展示码值: {}", e),
     }
-}
\ No newline at end of file
+    labels
+}
@@ -0,0 +1,336 @@
+use crate::api::mapping::{full_reload_semantic_engine, mark_deleted};
+use crate::ax_state::AppState;
+use crate::infra::connector::{PingResult, SqlDialect};
+use crate::infra::crypto::mask_connection_url;
+use crate::models::auth::Principal;
+use crate::models::revision::Author;
+use crate::models::schema::{CreateDataSourceRequest, DataSource, UpdateDataSourceRequest};
+use crate::service::revision;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Postgres, Row};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// 健康状态页单个数据源的探测上限：各数据源并发探测，整体耗时不超过该值
+const STATUS_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 支持的数据源类型：postgres / mysql / sqlite（DuckDB 待原生驱动可用后再接入）
+fn unsupported_db_type(db_type: &str) -> String {
+    format!("不支持的数据源类型: {}（支持 postgres / mysql / sqlite）", db_type)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteSourceQuery {
+    #[serde(default)]
+    pub cascade: bool, // 级联删除引用该数据源的本体节点
+}
+
+// --- 1. 登记与查询 ---
+
+/// 登记数据源（同 id 覆盖更新，并驱逐旧连接池）
+pub async fn register_data_source(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateDataSourceRequest>,
+) -> impl IntoResponse {
+    if SqlDialect::from_db_type(&payload.db_type).is_none() {
+        return (StatusCode::BAD_REQUEST, unsupported_db_type(&payload.db_type)).into_response();
+    }
+    // 连接串加密落库，另存脱敏串用于展示
+    let sealed = match state.pool_manager.seal_url(&payload.connection_url) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let res = sqlx::query(
+        "INSERT INTO data_sources (id, db_type, connection_url, display_name, masked_url, max_connections, min_connections, acquire_timeout_secs, idle_timeout_secs)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (id) DO UPDATE SET db_type=EXCLUDED.db_type, connection_url=EXCLUDED.connection_url, display_name=EXCLUDED.display_name, masked_url=EXCLUDED.masked_url,
+             max_connections=EXCLUDED.max_connections, min_connections=EXCLUDED.min_connections,
+             acquire_timeout_secs=EXCLUDED.acquire_timeout_secs, idle_timeout_secs=EXCLUDED.idle_timeout_secs",
+    )
+    .bind(&payload.id)
+    .bind(&payload.db_type)
+    .bind(&sealed)
+    .bind(&payload.display_name)
+    .bind(mask_connection_url(&payload.connection_url))
+    .bind(payload.max_connections)
+    .bind(payload.min_connections)
+    .bind(payload.acquire_timeout_secs)
+    .bind(payload.idle_timeout_secs)
+    .execute(&state.db)
+    .await;
+    match res {
+        Ok(_) => {
+            state.pool_manager.evict(&payload.id).await;
+            info!("数据源配置已更新: id={}", payload.id);
+            (StatusCode::CREATED, "Source Registered").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn list_data_sources(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources ORDER BY id")
+        .fetch_all(&state.db)
+        .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 2. 连通性测试 ---
+
+/// 测试尚未保存的数据源配置
+pub async fn test_new_data_source(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateDataSourceRequest>,
+) -> impl IntoResponse {
+    let source = match draft_source(&state, payload) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    Json(state.pool_manager.test_connection(&source).await).into_response()
+}
+
+/// 测试已登记的数据源（独立建连，不影响运行中的连接池）
+pub async fn test_data_source(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match find_source(&state, &id).await {
+        Ok(Some(s)) => Json(state.pool_manager.test_connection(&s).await).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Source config not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 3. 更新与删除 ---
+
+/// 更新数据源：连接信息变化时先测试新配置，通过后落库并重建连接池
+pub async fn update_data_source(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateDataSourceRequest>,
+) -> impl IntoResponse {
+    let mut source = match find_source(&state, &id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Source config not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let connection_changed = payload.connection_url.is_some() || payload.db_type.is_some();
+    if let Some(url) = &payload.connection_url {
+        source.connection_url = match state.pool_manager.seal_url(url) {
+            Ok(s) => s,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+        source.masked_url = Some(mask_connection_url(url));
+    }
+    if let Some(v) = payload.db_type {
+        if SqlDialect::from_db_type(&v).is_none() {
+            return (StatusCode::BAD_REQUEST, unsupported_db_type(&v)).into_response();
+        }
+        source.db_type = v;
+    }
+    if payload.display_name.is_some() {
+        source.display_name = payload.display_name;
+    }
+    source.max_connections = payload.max_connections.or(source.max_connections);
+    source.min_connections = payload.min_connections.or(source.min_connections);
+    source.acquire_timeout_secs = payload.acquire_timeout_secs.or(source.acquire_timeout_secs);
+    source.idle_timeout_secs = payload.idle_timeout_secs.or(source.idle_timeout_secs);
+
+    if connection_changed {
+        let probe = state.pool_manager.test_connection(&source).await;
+        if !probe.ok {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "message": "新连接配置测试失败，未保存", "test": probe })),
+            )
+                .into_response();
+        }
+    }
+
+    let res = sqlx::query(
+        "UPDATE data_sources SET db_type = $2, connection_url = $3, display_name = $4, masked_url = $5,
+             max_connections = $6, min_connections = $7, acquire_timeout_secs = $8, idle_timeout_secs = $9
+         WHERE id = $1",
+    )
+    .bind(&source.id)
+    .bind(&source.db_type)
+    .bind(&source.connection_url)
+    .bind(&source.display_name)
+    .bind(&source.masked_url)
+    .bind(source.max_connections)
+    .bind(source.min_connections)
+    .bind(source.acquire_timeout_secs)
+    .bind(source.idle_timeout_secs)
+    .execute(&state.db)
+    .await;
+    if let Err(e) = res {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    // 驱逐旧池并按新配置预热
+    state.pool_manager.evict(&id).await;
+    if let Err(e) = state.pool_manager.get_or_create_pool(&source).await {
+        warn!("数据源 {} 连接池重建失败: {}", id, e);
+    }
+    info!("数据源配置已更新并重建连接池: id={}", id);
+    Json(source).into_response()
+}
+
+/// 删除数据源：仍有本体节点引用时拒绝，除非指定 cascade=true 一并（草稿）删除这些节点
+/// 当前发布仍在服务该数据源上的节点时拒绝，需先发布删除这些节点的版本
+pub async fn delete_data_source(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+    Query(q): Query<DeleteSourceQuery>,
+) -> impl IntoResponse {
+    let referenced: Vec<String> = match sqlx::query(
+        "SELECT n.node_key FROM ontology_nodes n JOIN semantic_definitions d ON d.node_id = n.id
+         WHERE d.source_id = $1 AND n.deleted_at IS NULL ORDER BY n.node_key",
+    )
+    .bind(&id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => rows.iter().map(|r| r.get(0)).collect(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !referenced.is_empty() && !q.cascade {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "message": "数据源仍被本体节点引用，请先迁移节点或使用 cascade=true 级联删除",
+                "nodes": referenced
+            })),
+        )
+            .into_response();
+    }
+
+    let served: Vec<String> = match revision::serving_nodes_on_source(&state.db, &id).await {
+        Ok(keys) => keys,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !served.is_empty() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "message": "当前发布仍在服务该数据源上的节点，请先发布删除这些节点的版本",
+                "nodes": served
+            })),
+        )
+            .into_response();
+    }
+
+    let res: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let node_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT n.id FROM ontology_nodes n JOIN semantic_definitions d ON d.node_id = n.id WHERE d.source_id = $1",
+        )
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await?;
+        mark_deleted(&mut tx, &node_ids).await?;
+        revision::record_all(&mut tx, &Author::new(&principal.subject, "datasource")).await?;
+        // 数据源删除会级联删除其上的关联
+        let deleted = sqlx::query("DELETE FROM data_sources WHERE id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        revision::record_relations(&mut tx, &Author::new(&principal.subject, "datasource")).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+    .await;
+
+    match res {
+        Ok(0) => (StatusCode::NOT_FOUND, "Source config not found").into_response(),
+        Ok(_) => {
+            state.pool_manager.evict(&id).await;
+            if !referenced.is_empty() {
+                let _ = full_reload_semantic_engine(&state).await;
+            }
+            info!("数据源已删除: id={}, 级联节点 {} 个", id, referenced.len());
+            Json(json!({ "id": id, "deleted_nodes": referenced })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 4. 健康状态 ---
+
+/// 全部数据源的健康状态与连接池统计
+/// 已建池的数据源复用池探测，未建池的以临时连接探测（不会因此常驻连接池）
+/// 各数据源并发探测，超时的记为不可用
+pub async fn data_source_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let sources = match sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources ORDER BY id")
+        .fetch_all(&state.db)
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let report = join_all(sources.iter().map(|s| async {
+        let cached = state.pool_manager.cached(&s.id);
+        let probe = async {
+            match &cached {
+                Some(pool) => pool.ping().await,
+                None => state.pool_manager.test_connection(s).await,
+            }
+        };
+        let health = tokio::time::timeout(STATUS_PROBE_TIMEOUT, probe).await.unwrap_or_else(|_| PingResult {
+            ok: false,
+            latency_ms: STATUS_PROBE_TIMEOUT.as_millis(),
+            error: Some(format!("探测超时 ({}s)", STATUS_PROBE_TIMEOUT.as_secs())),
+        });
+        json!({
+            "id": s.id,
+            "db_type": s.db_type,
+            "display_name": s.display_name,
+            "pooled": cached.is_some(),
+            "pool": cached.as_ref().map(|p| p.stats()),
+            "health": health
+        })
+    }))
+    .await;
+    Json(report).into_response()
+}
+
+// --- 内部辅助 ---
+
+async fn find_source(state: &AppState, id: &str) -> sqlx::Result<Option<DataSource>> {
+    sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+}
+
+/// 由请求体构造未落库的数据源（连接串同样以密文形式流转）
+fn draft_source(state: &AppState, req: CreateDataSourceRequest) -> anyhow::Result<DataSource> {
+    Ok(DataSource {
+        connection_url: state.pool_manager.seal_url(&req.connection_url)?,
+        masked_url: Some(mask_connection_url(&req.connection_url)),
+        id: req.id,
+        db_type: req.db_type,
+        display_name: req.display_name,
+        max_connections: req.max_connections,
+        min_connections: req.min_connections,
+        acquire_timeout_secs: req.acquire_timeout_secs,
+        idle_timeout_secs: req.idle_timeout_secs,
+    })
+}
@@ -0,0 +1,228 @@
+use crate::api::mapping::{full_reload_semantic_engine, source_connector};
+use crate::ax_state::AppState;
+use crate::core::normalize::normalize_value;
+use crate::models::schema::{
+    AddSynonymRequest, DimensionLabelSource, DimensionValue, DimensionValueQuery, UpdateValueLabelRequest,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use sqlx::{Postgres, Row};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+// --- 1. 码值浏览 ---
+
+/// 维度的码值列表（附同义词），默认仅返回有效码值
+pub async fn list_dimension_values(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+    Query(q): Query<DimensionValueQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, DimensionValue>(
+        "SELECT v.*, COALESCE(array_agg(s.synonym ORDER BY s.synonym) FILTER (WHERE s.synonym IS NOT NULL), '{}') AS synonyms
+         FROM dimension_values v LEFT JOIN dimension_value_synonyms s ON s.value_id = v.id
+         WHERE v.dimension_node_id = $1 AND ($2 OR v.is_active)
+           AND ($3::text IS NULL OR v.value_label ILIKE '%' || $3 || '%' OR v.value_code ILIKE '%' || $3 || '%')
+         GROUP BY v.id ORDER BY v.value_label",
+    )
+    .bind(node_id)
+    .bind(q.include_inactive)
+    .bind(&q.keyword)
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 2. 标签来源配置 ---
+
+pub async fn get_label_source(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let res = sqlx::query_as::<Postgres, DimensionLabelSource>(
+        "SELECT * FROM dimension_label_sources WHERE dimension_node_id = $1",
+    )
+    .bind(node_id)
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(Some(s)) => Json(s).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Label source not configured").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 配置维度的标签来源：保存前以 LIMIT 1 试查验证列与表存在，下次同步生效
+pub async fn upsert_label_source(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+    Json(mut req): Json<DimensionLabelSource>,
+) -> impl IntoResponse {
+    req.dimension_node_id = node_id;
+    if req.lookup_table.is_some() != req.lookup_code_column.is_some() {
+        return (StatusCode::BAD_REQUEST, "lookup_table 与 lookup_code_column 需同时提供").into_response();
+    }
+    let def = match sqlx::query(
+        "SELECT d.source_id, d.target_table, d.sql_expression FROM semantic_definitions d
+         JOIN ontology_nodes n ON n.id = d.node_id WHERE d.node_id = $1 AND n.node_role = 'DIMENSION'",
+    )
+    .bind(node_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Dimension node not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let conn = match source_connector(&state, def.get("source_id")).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+
+    let (code_expr, table) = match (&req.lookup_table, &req.lookup_code_column) {
+        (Some(t), Some(c)) => (c.clone(), t.clone()),
+        _ => (def.get("sql_expression"), def.get("target_table")),
+    };
+    let probe = format!(
+        "{} LIMIT 1",
+        conn.dialect().distinct_values_sql(&code_expr, Some(&req.label_expression), &table)
+    );
+    if let Err(e) = conn.query(&probe).await {
+        return (StatusCode::UNPROCESSABLE_ENTITY, format!("标签来源试查失败: {}", e)).into_response();
+    }
+
+    let res = sqlx::query_as::<Postgres, DimensionLabelSource>(
+        "INSERT INTO dimension_label_sources (dimension_node_id, label_expression, lookup_table, lookup_code_column)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (dimension_node_id) DO UPDATE SET label_expression = EXCLUDED.label_expression,
+             lookup_table = EXCLUDED.lookup_table, lookup_code_column = EXCLUDED.lookup_code_column
+         RETURNING *",
+    )
+    .bind(node_id)
+    .bind(&req.label_expression)
+    .bind(&req.lookup_table)
+    .bind(&req.lookup_code_column)
+    .fetch_one(&state.db)
+    .await;
+    match res {
+        Ok(s) => {
+            info!("维度标签来源已配置: node={}, {}", node_id, probe);
+            Json(s).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn delete_label_source(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM dimension_label_sources WHERE dimension_node_id = $1")
+        .bind(node_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Label source not configured").into_response(),
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 3. 标签与同义词维护 ---
+
+/// 修改码值的展示标签（修改后锁定，不再被同步覆盖）
+pub async fn update_value_label(
+    State(state): State<Arc<AppState>>,
+    Path(value_id): Path<i32>,
+    Json(req): Json<UpdateValueLabelRequest>,
+) -> impl IntoResponse {
+    let label = req.label.trim();
+    if label.is_empty() {
+        return (StatusCode::BAD_REQUEST, "label 不能为空").into_response();
+    }
+    // 标签不设唯一约束（同步时允许退化），人工修改仍要求同一维度下不重复
+    let res = sqlx::query_scalar::<_, bool>(
+        "WITH target AS (SELECT id, dimension_node_id FROM dimension_values WHERE id = $1),
+              updated AS (
+                  UPDATE dimension_values d SET value_label = $2, normalized_label = $3, label_locked = TRUE
+                  FROM target t WHERE d.id = t.id AND NOT EXISTS (
+                      SELECT 1 FROM dimension_values x
+                      WHERE x.dimension_node_id = t.dimension_node_id AND x.value_label = $2 AND x.id <> t.id
+                  )
+                  RETURNING d.id
+              )
+         SELECT EXISTS (SELECT 1 FROM updated) FROM target",
+    )
+    .bind(value_id)
+    .bind(label)
+    .bind(normalize_value(label))
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(None) => (StatusCode::NOT_FOUND, "Dimension value not found").into_response(),
+        Ok(Some(false)) => (StatusCode::CONFLICT, "该维度下已有相同标签的码值").into_response(),
+        Ok(Some(true)) => {
+            let _ = full_reload_semantic_engine(&state).await;
+            StatusCode::OK.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn add_value_synonym(
+    State(state): State<Arc<AppState>>,
+    Path(value_id): Path<i32>,
+    Json(req): Json<AddSynonymRequest>,
+) -> impl IntoResponse {
+    let synonym = req.synonym.trim();
+    if synonym.is_empty() {
+        return (StatusCode::BAD_REQUEST, "synonym 不能为空").into_response();
+    }
+    let res = sqlx::query(
+        "INSERT INTO dimension_value_synonyms (value_id, synonym, normalized) VALUES ($1, $2, $3)
+         ON CONFLICT (value_id, synonym) DO NOTHING",
+    )
+    .bind(value_id)
+    .bind(synonym)
+    .bind(normalize_value(synonym))
+    .execute(&state.db)
+    .await;
+    match res {
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            (StatusCode::NOT_FOUND, "Dimension value not found").into_response()
+        }
+        Ok(_) => {
+            let _ = full_reload_semantic_engine(&state).await;
+            info!("码值同义词已添加: value_id={}, {}", value_id, synonym);
+            StatusCode::CREATED.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn delete_value_synonym(
+    State(state): State<Arc<AppState>>,
+    Path((value_id, synonym)): Path<(i32, String)>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM dimension_value_synonyms WHERE value_id = $1 AND synonym = $2")
+        .bind(value_id)
+        .bind(&synonym)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Synonym not found").into_response(),
+        Ok(_) => {
+            let _ = full_reload_semantic_engine(&state).await;
+            StatusCode::OK.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
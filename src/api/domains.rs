@@ -0,0 +1,305 @@
+use crate::ax_state::AppState;
+use crate::models::schema::{
+    CreateDatasetRequest, CreateDomainRequest, ScopeQuery, SemanticDataset, SemanticDomain, UpdateDatasetRequest,
+    UpdateDomainRequest,
+};
+use crate::service::sparql;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Postgres};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const DATASET_SELECT: &str = "SELECT s.id, s.domain_id, d.domain_key, s.dataset_key, s.label, COALESCE(s.join_config, '{}'::jsonb) AS join_config
+     FROM semantic_datasets s LEFT JOIN semantic_domains d ON d.id = s.domain_id";
+
+// --- 1. 业务域 ---
+
+pub async fn list_domains(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, SemanticDomain>("SELECT * FROM semantic_domains ORDER BY domain_key")
+        .fetch_all(&state.db)
+        .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn create_domain(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateDomainRequest>,
+) -> impl IntoResponse {
+    if payload.domain_key.trim().is_empty() || payload.label.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "domain_key 与 label 不能为空").into_response();
+    }
+    let res = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO semantic_domains (domain_key, label, description) VALUES ($1, $2, $3)
+         ON CONFLICT (domain_key) DO NOTHING RETURNING id",
+    )
+    .bind(payload.domain_key.trim())
+    .bind(payload.label.trim())
+    .bind(&payload.description)
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(Some(id)) => {
+            info!("业务域已创建: {}", payload.domain_key);
+            refresh_rdf(&state).await;
+            (StatusCode::CREATED, Json(json!({ "id": id }))).into_response()
+        }
+        Ok(None) => (StatusCode::CONFLICT, format!("业务域已存在: {}", payload.domain_key)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn update_domain(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateDomainRequest>,
+) -> impl IntoResponse {
+    if payload.label.as_deref().is_some_and(|l| l.trim().is_empty()) {
+        return (StatusCode::BAD_REQUEST, "label 不能为空").into_response();
+    }
+    let res = sqlx::query_as::<Postgres, SemanticDomain>(
+        "UPDATE semantic_domains SET label = COALESCE($2, label), description = COALESCE($3, description)
+         WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(payload.label.as_deref().map(str::trim))
+    .bind(&payload.description)
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(Some(domain)) => {
+            refresh_rdf(&state).await;
+            Json(domain).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Domain not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 删除业务域（级联删除其下数据集）；仍有语义节点挂在其数据集上时拒绝
+pub async fn delete_domain(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let in_use = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM ontology_nodes n JOIN semantic_datasets s ON s.id = n.dataset_id WHERE s.domain_id = $1",
+    )
+    .bind(id)
+    .fetch_one(&state.db)
+    .await;
+    match in_use {
+        Ok(0) => {}
+        Ok(n) => {
+            return (StatusCode::CONFLICT, format!("该业务域的数据集下仍有 {} 个语义节点（含删除尚未发布的节点），请先迁移或删除并发布", n)).into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+    match sqlx::query("DELETE FROM semantic_domains WHERE id = $1").bind(id).execute(&state.db).await {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Domain not found").into_response(),
+        Ok(_) => {
+            info!("业务域已删除: {}", id);
+            refresh_rdf(&state).await;
+            StatusCode::OK.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 2. 逻辑数据集 ---
+
+/// 数据集列表，可按业务域 (?domain=domain_key) 过滤
+pub async fn list_datasets(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<ScopeQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, SemanticDataset>(&format!(
+        "{} WHERE ($1::text IS NULL OR d.domain_key = $1) ORDER BY s.dataset_key",
+        DATASET_SELECT
+    ))
+    .bind(&q.domain)
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn create_dataset(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateDatasetRequest>,
+) -> impl IntoResponse {
+    if payload.dataset_key.trim().is_empty() || payload.label.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "dataset_key 与 label 不能为空").into_response();
+    }
+    let join_config = payload.join_config.unwrap_or_else(|| json!({}));
+    if let Err(e) = validate_join_config(&join_config) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    let res = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO semantic_datasets (domain_id, dataset_key, label, join_config) VALUES ($1, $2, $3, $4)
+         ON CONFLICT (dataset_key) DO NOTHING RETURNING id",
+    )
+    .bind(payload.domain_id)
+    .bind(payload.dataset_key.trim())
+    .bind(payload.label.trim())
+    .bind(&join_config)
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(Some(id)) => {
+            info!("数据集已创建: {}", payload.dataset_key);
+            refresh_rdf(&state).await;
+            (StatusCode::CREATED, Json(json!({ "id": id }))).into_response()
+        }
+        Ok(None) => (StatusCode::CONFLICT, format!("数据集已存在: {}", payload.dataset_key)).into_response(),
+        // 外键失败：domain_id 不存在
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+pub async fn update_dataset(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateDatasetRequest>,
+) -> impl IntoResponse {
+    if payload.label.as_deref().is_some_and(|l| l.trim().is_empty()) {
+        return (StatusCode::BAD_REQUEST, "label 不能为空").into_response();
+    }
+    if let Some(cfg) = &payload.join_config {
+        if let Err(e) = validate_join_config(cfg) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+    let res = sqlx::query(
+        "UPDATE semantic_datasets SET label = COALESCE($2, label), domain_id = COALESCE($3, domain_id),
+             join_config = COALESCE($4, join_config)
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(payload.label.as_deref().map(str::trim))
+    .bind(payload.domain_id)
+    .bind(&payload.join_config)
+    .execute(&state.db)
+    .await;
+    match res {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Dataset not found").into_response(),
+        Ok(_) => {
+            refresh_rdf(&state).await;
+            let row = sqlx::query_as::<Postgres, SemanticDataset>(&format!("{} WHERE s.id = $1", DATASET_SELECT))
+                .bind(id)
+                .fetch_one(&state.db)
+                .await;
+            match row {
+                Ok(ds) => Json(ds).into_response(),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            }
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// 删除数据集；仍有语义节点归属于它时拒绝
+pub async fn delete_dataset(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let in_use = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM ontology_nodes WHERE dataset_id = $1")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await;
+    match in_use {
+        Ok(0) => {}
+        Ok(n) => return (StatusCode::CONFLICT, format!("该数据集下仍有 {} 个语义节点（含删除尚未发布的节点），请先迁移或删除并发布", n)).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+    match sqlx::query("DELETE FROM semantic_datasets WHERE id = $1").bind(id).execute(&state.db).await {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Dataset not found").into_response(),
+        Ok(_) => {
+            info!("数据集已删除: {}", id);
+            refresh_rdf(&state).await;
+            StatusCode::OK.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// join_config 形如 {"platform_dim": {"join_type": "LEFT", "on": "f_order.pid = platform_dim.id"}}
+fn validate_join_config(cfg: &Value) -> Result<(), String> {
+    let Some(tables) = cfg.as_object() else {
+        return Err("join_config 必须是以表名为键的 JSON 对象".into());
+    };
+    for (table, join) in tables {
+        let on = join.get("on").and_then(Value::as_str).unwrap_or_default();
+        if on.trim().is_empty() {
+            return Err(format!("join_config.{} 缺少 on 条件", table));
+        }
+        if let Some(t) = join.get("join_type") {
+            let ok = t
+                .as_str()
+                .is_some_and(|t| ["INNER", "LEFT", "RIGHT", "FULL"].contains(&t.to_uppercase().as_str()));
+            if !ok {
+                return Err(format!("join_config.{}.join_type 仅支持 INNER / LEFT / RIGHT / FULL", table));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn refresh_rdf(state: &AppState) {
+    if let Err(e) = sparql::refresh(state).await {
+        warn!("本体三元组存储刷新失败: {}", e);
+    }
+}
+
+// --- 3. 归属范围 ---
+
+/// 按业务域 / 数据集过滤节点时的节点 ID 集合；未指定任何过滤条件时返回 None
+pub(crate) async fn nodes_in_scope(db: &PgPool, q: &ScopeQuery) -> sqlx::Result<Option<HashSet<Uuid>>> {
+    if q.domain.is_none() && q.dataset.is_none() {
+        return Ok(None);
+    }
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT n.id FROM ontology_nodes n
+         JOIN semantic_datasets s ON s.id = n.dataset_id
+         LEFT JOIN semantic_domains d ON d.id = s.domain_id
+         WHERE ($1::text IS NULL OR d.domain_key = $1) AND ($2::text IS NULL OR s.dataset_key = $2)",
+    )
+    .bind(&q.domain)
+    .bind(&q.dataset)
+    .fetch_all(db)
+    .await?;
+    Ok(Some(ids.into_iter().collect()))
+}
+
+/// 问数限定业务域时需排除的节点：归属于其他业务域的节点（未归属任何域的节点视为公共节点，不排除）
+/// 业务域不存在时返回 None
+pub(crate) async fn nodes_outside_domain(db: &PgPool, domain_key: &str) -> sqlx::Result<Option<HashSet<Uuid>>> {
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM semantic_domains WHERE domain_key = $1")
+        .bind(domain_key)
+        .fetch_one(db)
+        .await?;
+    if exists == 0 {
+        return Ok(None);
+    }
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT n.id FROM ontology_nodes n
+         JOIN semantic_datasets s ON s.id = n.dataset_id
+         JOIN semantic_domains d ON d.id = s.domain_id
+         WHERE d.domain_key <> $1",
+    )
+    .bind(domain_key)
+    .fetch_all(db)
+    .await?;
+    Ok(Some(ids.into_iter().collect()))
+}
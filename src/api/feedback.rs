@@ -0,0 +1,255 @@
+use crate::api::mapping::{full_reload_semantic_engine, load_all_nodes, refresh_after_edit, write_node};
+use crate::ax_state::AppState;
+use crate::core::normalize::normalize_value;
+use crate::models::auth::Principal;
+use crate::models::revision::Author;
+use crate::models::schema::{CreateNodeRequest, FeedbackRequest, QueryFeedback, ReviewFeedbackRequest};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::Deserialize;
+use sqlx::{Postgres, Transaction};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackListQuery {
+    pub status: Option<String>,
+}
+
+/// 提交问答反馈（绑定到某条问答日志）
+pub async fn submit_feedback(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<FeedbackRequest>,
+) -> impl IntoResponse {
+    let rating = payload.rating.to_uppercase();
+    if rating != "UP" && rating != "DOWN" {
+        return (StatusCode::BAD_REQUEST, "rating 仅支持 UP / DOWN").into_response();
+    }
+    if payload.corrected_value.is_some() && payload.corrected_dimension.is_none() {
+        return (StatusCode::BAD_REQUEST, "纠正维度值时必须同时给出 corrected_dimension").into_response();
+    }
+
+    let res = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO query_feedback (query_id, rating, comment, corrected_metric, corrected_dimension, corrected_value, phrase)
+         SELECT id, $2, $3, $4, $5, $6, $7 FROM query_log WHERE id = $1
+         RETURNING id",
+    )
+    .bind(payload.query_id)
+    .bind(&rating)
+    .bind(&payload.comment)
+    .bind(&payload.corrected_metric)
+    .bind(&payload.corrected_dimension)
+    .bind(&payload.corrected_value)
+    .bind(&payload.phrase)
+    .fetch_optional(&state.db)
+    .await;
+
+    match res {
+        Ok(Some(id)) => {
+            info!("收到问答反馈: query_id={}, rating={}", payload.query_id, rating);
+            (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Query log not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 反馈列表（默认全部，可按状态筛选，供审核台使用）
+pub async fn list_feedback(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<FeedbackListQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, QueryFeedback>(
+        "SELECT f.*, q.question FROM query_feedback f JOIN query_log q ON q.id = f.query_id
+         WHERE ($1::text IS NULL OR f.status = $1)
+         ORDER BY f.created_at DESC",
+    )
+    .bind(q.status.map(|s| s.to_uppercase()))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 审核反馈：采纳的纠正沉淀为本体别名 (走 save_mapping 同一落库流程) 或 A-Box 码值标签
+/// 状态流转 (PENDING -> ACCEPTED / REJECTED) 与纠正落库在同一事务内，并发审核只有一个生效，其余返回 409
+pub async fn review_feedback(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReviewFeedbackRequest>,
+) -> impl IntoResponse {
+    let status = match payload.action.to_uppercase().as_str() {
+        "REJECT" => "REJECTED",
+        "ACCEPT" => "ACCEPTED",
+        _ => return (StatusCode::BAD_REQUEST, "action 仅支持 ACCEPT / REJECT").into_response(),
+    };
+    let fb = match sqlx::query_as::<Postgres, QueryFeedback>(
+        "SELECT f.*, q.question FROM query_feedback f JOIN query_log q ON q.id = f.query_id WHERE f.id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(f)) => f,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Feedback not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let res: Result<Option<(String, Learned)>, (StatusCode, String)> = async {
+        let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        let mut tx = state.db.begin().await.map_err(internal)?;
+
+        // 1. 抢占状态：仅 PENDING 可流转，行锁保证并发审核串行
+        let claimed = sqlx::query_scalar::<_, Uuid>(
+            "UPDATE query_feedback SET status = $2, reviewed_at = NOW() WHERE id = $1 AND status = 'PENDING' RETURNING id",
+        )
+        .bind(id)
+        .bind(status)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal)?;
+        if claimed.is_none() {
+            return Ok(None);
+        }
+
+        // 2. 采纳时在同一事务内落库纠正
+        let (note, learned) = if status == "REJECTED" {
+            ("审核驳回".to_string(), Learned::default())
+        } else {
+            let phrase = payload.phrase.clone().or(fb.phrase.clone());
+            let author = Author::new(&principal.subject, "feedback");
+            apply_correction(&mut tx, &fb, phrase.as_deref(), &author).await?
+        };
+
+        sqlx::query("UPDATE query_feedback SET review_note = $2 WHERE id = $1")
+            .bind(id)
+            .bind(&note)
+            .execute(&mut *tx)
+            .await
+            .map_err(internal)?;
+        tx.commit().await.map_err(internal)?;
+        Ok(Some((note, learned)))
+    }
+    .await;
+
+    match res {
+        Ok(Some((note, learned))) => {
+            // A-Box 码值进入分词词典需重载推理引擎；仅别名变更走草稿刷新
+            if learned.abox {
+                let _ = full_reload_semantic_engine(&state).await;
+            } else if learned.tbox {
+                let _ = refresh_after_edit(&state).await;
+            }
+            info!("反馈审核完成: id={}, status={}, {}", id, status, note);
+            Json(serde_json::json!({ "id": id, "status": status, "note": note })).into_response()
+        }
+        Ok(None) => {
+            let current: Option<String> = sqlx::query_scalar("SELECT status FROM query_feedback WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&state.db)
+                .await
+                .ok()
+                .flatten();
+            (StatusCode::CONFLICT, format!("该反馈已处理: {}", current.unwrap_or_default())).into_response()
+        }
+        Err((code, msg)) => (code, msg).into_response(),
+    }
+}
+
+/// 本次采纳写入了哪些部分
+#[derive(Default)]
+struct Learned {
+    abox: bool,
+    tbox: bool,
+}
+
+/// 将纠正落入本体（在审核事务内执行）：
+/// - 维度 + 码值：口语表达写入 A-Box 作为该码值的标签
+/// - 指标 / 仅维度：口语表达追加为节点别名
+async fn apply_correction(
+    tx: &mut Transaction<'_, Postgres>,
+    fb: &QueryFeedback,
+    phrase: Option<&str>,
+    author: &Author,
+) -> Result<(String, Learned), (StatusCode, String)> {
+    let mut learned = Learned::default();
+    if fb.corrected_metric.is_none() && fb.corrected_dimension.is_none() {
+        return Ok(("无纠正内容，仅记录采纳".to_string(), learned));
+    }
+    let phrase = phrase
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .ok_or((StatusCode::BAD_REQUEST, "缺少用户口语表达 phrase，无法沉淀为别名或码值标签".to_string()))?;
+
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let keys: Vec<&String> = fb.corrected_metric.iter().chain(fb.corrected_dimension.iter()).collect();
+    let nodes = load_all_nodes(&mut **tx).await.map_err(internal)?;
+    let find = |key: &str| {
+        nodes
+            .iter()
+            .find(|n| n.node_key == key)
+            .ok_or((StatusCode::UNPROCESSABLE_ENTITY, format!("纠正引用了不存在的节点: {}", key)))
+    };
+    for key in keys {
+        find(key)?;
+    }
+
+    let mut notes = Vec::new();
+
+    // 1. A-Box：口语表达 -> 码值
+    if let (Some(dim_key), Some(code)) = (&fb.corrected_dimension, &fb.corrected_value) {
+        let dim = find(dim_key)?;
+        // 码值尚未同步时先补齐，口语表达记为该码值的同义词
+        sqlx::query(
+            "INSERT INTO dimension_values (dimension_node_id, value_label, value_code, normalized_label)
+             VALUES ($1, $2, $2, $3)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(dim.id)
+        .bind(code)
+        .bind(normalize_value(code))
+        .execute(&mut **tx)
+        .await
+        .map_err(internal)?;
+        sqlx::query(
+            "INSERT INTO dimension_value_synonyms (value_id, synonym, origin, normalized)
+             SELECT id, $3, 'FEEDBACK', $4 FROM dimension_values WHERE dimension_node_id = $1 AND value_code = $2
+             ON CONFLICT (value_id, synonym) DO NOTHING",
+        )
+        .bind(dim.id)
+        .bind(code)
+        .bind(phrase)
+        .bind(normalize_value(phrase))
+        .execute(&mut **tx)
+        .await
+        .map_err(internal)?;
+        learned.abox = true;
+        notes.push(format!("A-Box 码值标签 {}:'{}' -> '{}'", dim.label, phrase, code));
+    }
+
+    // 2. T-Box：口语表达 -> 节点别名
+    let alias_target = fb
+        .corrected_metric
+        .as_ref()
+        .or(fb.corrected_dimension.as_ref().filter(|_| fb.corrected_value.is_none()));
+    if let Some(key) = alias_target {
+        let node = find(key)?;
+        if node.label != phrase && !node.alias_names.iter().any(|a| a == phrase) {
+            let mut req = CreateNodeRequest::from(node);
+            req.alias_names.push(phrase.to_string());
+            write_node(tx, &req, author).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            learned.tbox = true;
+        }
+        notes.push(format!("别名 {} += '{}'", node.label, phrase));
+    }
+
+    Ok((notes.join("; "), learned))
+}
@@ -0,0 +1,108 @@
+use crate::api::chat::run_pipeline;
+use crate::ax_state::AppState;
+use crate::core::planner;
+use crate::models::auth::Principal;
+use crate::models::schema::{QueryLog, QueryLogSearch, QueryLogicalPlan};
+use crate::service::query_log::{self, QueryLogEntry};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde_json::json;
+use sqlx::Postgres;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// 检索问答审计日志（按会话 / 状态 / 数据源 / 用户 / 关键词，按时间倒序分页）
+pub async fn list_query_logs(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<QueryLogSearch>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, QueryLog>(
+        "SELECT * FROM query_log
+         WHERE ($1::text IS NULL OR session_id = $1)
+           AND ($2::text IS NULL OR status = $2)
+           AND ($3::text IS NULL OR source_id = $3)
+           AND ($4::text IS NULL OR question ILIKE '%' || $4 || '%')
+           AND ($5::text IS NULL OR user_id = $5)
+         ORDER BY created_at DESC
+         LIMIT $6 OFFSET $7",
+    )
+    .bind(&q.session_id)
+    .bind(q.status.as_ref().map(|s| s.to_lowercase()))
+    .bind(&q.source_id)
+    .bind(&q.keyword)
+    .bind(&q.user_id)
+    .bind(q.limit.unwrap_or(50).clamp(1, 500))
+    .bind(q.offset.unwrap_or(0).max(0))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 查看单条问答日志
+pub async fn get_query_log(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match find_log(&state, id).await {
+        Ok(Some(log)) => Json(log).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Query log not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 重放一次历史问答：以当前本体重新推理并执行，记录为新的日志并标注来源
+/// 返回新结果，并对比新旧逻辑计划是否发生变化（规范化哈希比对）
+pub async fn replay_query_log(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let original = match find_log(&state, id).await {
+        Ok(Some(log)) => log,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Query log not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let mut entry = QueryLogEntry::new(&original.question, original.session_id.clone());
+    entry.replay_of = Some(original.id);
+    entry.user_id = Some(principal.subject.clone());
+    entry.domain_key = original.domain_key.clone();
+    let mut body = run_pipeline(&state, &principal, &original.question, &mut entry).await;
+
+    let hash_of = |p: &QueryLogicalPlan| planner::plan_hash(&planner::canonicalize(p));
+    let original_hash = original
+        .plan
+        .clone()
+        .and_then(|v| serde_json::from_value::<QueryLogicalPlan>(v).ok())
+        .map(|p| hash_of(&p));
+    let plan_changed = original_hash != entry.plan.as_ref().map(hash_of);
+
+    let query_id = query_log::record(&state, &entry).await;
+    info!("重放问答日志: {} -> {:?}, 计划变化: {}", id, query_id, plan_changed);
+
+    body["query_id"] = json!(query_id);
+    body["replay_of"] = json!(original.id);
+    body["plan_changed"] = json!(plan_changed);
+    body["original"] = json!({
+        "status": original.status,
+        "sql": original.compiled_sql,
+        "row_count": original.row_count,
+        "created_at": original.created_at
+    });
+    Json(body).into_response()
+}
+
+async fn find_log(state: &AppState, id: Uuid) -> sqlx::Result<Option<QueryLog>> {
+    sqlx::query_as::<Postgres, QueryLog>("SELECT * FROM query_log WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+}
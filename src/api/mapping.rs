@@ -1,32 +1,68 @@
+use crate::api::domains::nodes_in_scope;
 use crate::ax_state::AppState;
 use crate::core::fst_engine::FstEngine;
+use crate::core::inference::SemanticInferenceEngine;
+use crate::infra::connector::DataSourceConnector;
+use crate::models::auth::Principal;
+use crate::models::revision::Author;
 use crate::models::schema::{
-    CreateDataSourceRequest, CreateNodeRequest, DataSource, FullSemanticNode, MetadataRequest,
+    CreateNodeRequest, DataSource, FullSemanticNode, MetadataRequest, ScopeQuery,
 };
 use axum::{
     extract::{Path, Query, State},
-    http::{header, StatusCode},
+    http::StatusCode,
     response::{IntoResponse, Response},
-    Json,
+    Extension, Json,
 };
-use sqlx::{Postgres, Row};
+use sqlx::{Postgres, Row, Transaction};
 use std::sync::Arc;
+use crate::service::revision;
+use crate::service::security::SecurityContext;
 use tracing::{info};
 use uuid::Uuid;
 
 // --- 1. 本体节点建模与管理 ---
 
 /// 保存或更新本体节点 (Metric/Dimension)
-/// 处理流程：开启事务 -> 更新主表 -> 更新定义表 -> 重置 T-Box 关系 -> 提交 -> 刷新 FST
+/// 处理流程：开启事务 -> 更新主表 -> 更新定义表 -> 重置 T-Box 关系 -> 记录修订 -> 提交 -> 刷新草稿视图
 pub async fn save_mapping(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
     Json(payload): Json<CreateNodeRequest>,
 ) -> impl IntoResponse {
+    match upsert_node(&state, payload, &Author::new(&principal.subject, "mapping")).await {
+        Ok(node_id) => (StatusCode::OK, Json(serde_json::json!({ "id": node_id }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// 节点落库与热刷新的核心流程（建模接口与反馈学习共用）
+pub(crate) async fn upsert_node(state: &AppState, payload: CreateNodeRequest, author: &Author) -> Result<Uuid, String> {
     let mut tx = match state.db.begin().await {
         Ok(t) => t,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => return Err(e.to_string()),
     };
 
+    let node_id = write_node(&mut tx, &payload, author).await?;
+
+    if let Err(e) = tx.commit().await {
+        return Err(format!("Transaction Commit Failed: {}", e));
+    }
+
+    info!("建模请求处理完成: node_id={}", node_id);
+
+    // 草稿刷新（实时模式下热重载推理引擎）
+    let _ = refresh_after_edit(state).await;
+
+    Ok(node_id)
+}
+
+/// 在调用方事务内写入单个节点（主表 + 定义 + T-Box 关联）并记录修订，供批量建模复用
+pub(crate) async fn write_node(
+    tx: &mut Transaction<'_, Postgres>,
+    payload: &CreateNodeRequest,
+    author: &Author,
+) -> Result<Uuid, String> {
     info!(
         "接收到建模请求: node_key={}, role={}",
         payload.node_key, payload.node_role
@@ -37,7 +73,7 @@ pub async fn save_mapping(
         "INSERT INTO ontology_nodes (node_key, label, node_role, semantic_type, dataset_id) 
          VALUES ($1, $2, $3, $4, $5) 
          ON CONFLICT (node_key) 
-         DO UPDATE SET label = EXCLUDED.label, node_role = EXCLUDED.node_role, semantic_type=EXCLUDED.semantic_type, dataset_id = EXCLUDED.dataset_id 
+         DO UPDATE SET label = EXCLUDED.label, node_role = EXCLUDED.node_role, semantic_type=EXCLUDED.semantic_type, dataset_id = EXCLUDED.dataset_id, deleted_at = NULL
          RETURNING id"
     )
     .bind(&payload.node_key)
@@ -45,17 +81,17 @@ pub async fn save_mapping(
     .bind(&payload.node_role)
     .bind(&payload.semantic_type)
     .bind(payload.dataset_id)
-    .fetch_one(&mut *tx).await {
+    .fetch_one(&mut **tx).await {
         Ok(row) => row.get("id"),
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Ontology Update Failed: {}", e)).into_response(),
+        Err(e) => return Err(format!("Ontology Update Failed: {}", e)),
     };
 
     // B. 更新 semantic_definitions (物理映射、SQL表达式、默认聚合、隐含约束)
     let constraints_json = serde_json::to_value(&payload.default_constraints).unwrap();
     let def_res = sqlx::query(
         r#"
-        INSERT INTO semantic_definitions (node_id, source_id, target_table, sql_expression, default_constraints, alias_names, default_agg, value_format)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8) 
+        INSERT INTO semantic_definitions (node_id, source_id, target_table, sql_expression, default_constraints, alias_names, default_agg, value_format, unit, number_format)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) 
         ON CONFLICT (node_id) 
         DO UPDATE SET 
             source_id = EXCLUDED.source_id, 
@@ -64,7 +100,9 @@ pub async fn save_mapping(
             default_constraints = EXCLUDED.default_constraints, 
             alias_names = EXCLUDED.alias_names, 
             default_agg = EXCLUDED.default_agg, 
-            value_format = EXCLUDED.value_format
+            value_format = EXCLUDED.value_format,
+            unit = EXCLUDED.unit,
+            number_format = EXCLUDED.number_format
         "#
     )
     .bind(node_id)
@@ -75,62 +113,59 @@ pub async fn save_mapping(
     .bind(&payload.alias_names)
     .bind(&payload.default_agg)
     .bind(&payload.value_format)
-    .execute(&mut *tx).await;
+    .bind(&payload.unit)
+    .bind(&payload.number_format)
+    .execute(&mut **tx).await;
 
     if let Err(e) = def_res {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Mapping Definition Failed: {}", e),
-        )
-            .into_response();
+        return Err(format!("Mapping Definition Failed: {}", e));
     }
 
     // C. 更新 T-Box 维度关联关系 (只有指标角色需要)
     let _ = sqlx::query("DELETE FROM metric_dimension_rels WHERE metric_node_id = $1")
         .bind(node_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await;
 
     if payload.node_role == "METRIC" {
-        for dim_id in payload.supported_dimension_ids {
-            let _ = sqlx::query(
+        for dim_id in &payload.supported_dimension_ids {
+            sqlx::query(
                 "INSERT INTO metric_dimension_rels (metric_node_id, dimension_node_id) VALUES ($1, $2)"
             )
             .bind(node_id)
             .bind(dim_id)
-            .execute(&mut *tx).await;
+            .execute(&mut **tx).await
+            .map_err(|e| format!("T-Box Relation Failed: {}", e))?;
         }
     }
 
-    if let Err(e) = tx.commit().await {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Transaction Commit Failed: {}", e),
-        )
-            .into_response();
-    }
-
-    info!("建模请求处理完成: node_id={}", node_id);
-
-    // 热刷新内存中的语义索引
-    let _ = full_reload_semantic_engine(&state).await;
+    // D. 记录修订（与上一修订无差异时不记录）
+    revision::record_node(tx, &payload.node_key, author)
+        .await
+        .map_err(|e| format!("Revision Record Failed: {}", e))?;
 
-    (StatusCode::OK, Json(serde_json::json!({ "id": node_id }))).into_response()
+    Ok(node_id)
 }
 
-/// 删除本体节点
+/// 删除本体节点（草稿删除：只打删除标记，发布后才物理删除）
 pub async fn delete_mapping(
     State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
     Path(id): Path<Uuid>,
 ) -> impl IntoResponse {
-    // 依赖数据库设置的 ON DELETE CASCADE 自动清理物理定义与关联
-    match sqlx::query("DELETE FROM ontology_nodes WHERE id = $1")
-        .bind(id)
-        .execute(&state.db)
-        .await
-    {
+    // 删除维度会移除指标上的关联，因此比对全部节点记录修订
+    let res: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let deleted = mark_deleted(&mut tx, &[id]).await?;
+        revision::record_all(&mut tx, &Author::new(&principal.subject, "mapping")).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+    .await;
+    match res {
+        Ok(0) => (StatusCode::NOT_FOUND, "Node not found").into_response(),
         Ok(_) => {
-            let _ = refresh_fst_cache(&state).await;
+            let _ = refresh_after_edit(&state).await;
             info!("删除语义节点: id={}", id);
             StatusCode::OK.into_response()
         }
@@ -138,40 +173,85 @@ pub async fn delete_mapping(
     }
 }
 
+/// 草稿删除节点：打删除标记并移除指向这些维度的 T-Box 关联
+/// 不物理删除，节点的安全策略、A-Box 码值、标签来源与同步调度在发布快照仍服务该节点期间保持有效
+pub(crate) async fn mark_deleted(tx: &mut Transaction<'_, Postgres>, ids: &[Uuid]) -> sqlx::Result<u64> {
+    let deleted = sqlx::query("UPDATE ontology_nodes SET deleted_at = NOW() WHERE id = ANY($1) AND deleted_at IS NULL")
+        .bind(ids)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+    sqlx::query("DELETE FROM metric_dimension_rels WHERE dimension_node_id = ANY($1)")
+        .bind(ids)
+        .execute(&mut **tx)
+        .await?;
+    Ok(deleted)
+}
+
 /// 获取全量本体节点列表 (用于前端表格展示，包含维度 ID 聚合)
-pub async fn list_mappings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let rows = sqlx::query_as::<Postgres, FullSemanticNode>(
+/// 按调用方安全策略过滤隐藏节点，可按业务域 / 数据集 (?domain=&dataset=) 过滤
+pub async fn list_mappings(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Query(scope): Query<ScopeQuery>,
+) -> impl IntoResponse {
+    let security = match SecurityContext::load(&state, &principal).await {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let in_scope = match nodes_in_scope(&state.db, &scope).await {
+        Ok(ids) => ids,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    match load_all_nodes(&state.db).await {
+        Ok(mut list) => {
+            list.retain(|n| security.is_visible(n) && in_scope.as_ref().is_none_or(|ids| ids.contains(&n.id)));
+            Json(list).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 读取全量本体节点（含 T-Box 维度关联，不含已删除的草稿节点）
+pub(crate) async fn load_all_nodes<'e, E: sqlx::PgExecutor<'e>>(db: E) -> sqlx::Result<Vec<FullSemanticNode>> {
+    sqlx::query_as::<Postgres, FullSemanticNode>(
         r#"
         SELECT n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, 
-               d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format,
+               d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format, d.unit, d.number_format,
                COALESCE(array_agg(r.dimension_node_id) FILTER (WHERE r.dimension_node_id IS NOT NULL), '{}') as supported_dimension_ids
         FROM ontology_nodes n 
         JOIN semantic_definitions d ON n.id = d.node_id
         LEFT JOIN metric_dimension_rels r ON n.id = r.metric_node_id
-        GROUP BY n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format
+        WHERE n.deleted_at IS NULL
+        GROUP BY n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format, d.unit, d.number_format
         "#
-    ).fetch_all(&state.db).await;
+    ).fetch_all(db).await
+}
 
-    match rows {
-        Ok(list) => Json(list).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+// --- 2. 物理元数据探测 ---
+
+/// 获取外部数据库的 schema 列表
+pub async fn get_metadata_schemas(
+    State(state): State<Arc<AppState>>,
+    Query(req): Query<MetadataRequest>,
+) -> impl IntoResponse {
+    match source_connector(&state, &req.source_id).await {
+        Ok(conn) => match conn.list_schemas().await {
+            Ok(list) => Json(list).into_response(),
+            Err(e) => (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+        },
+        Err(resp) => resp,
     }
 }
 
-// --- 2. 物理元数据探测与 A-Box 同步 ---
-
-/// 获取外部数据库的表列表 (级联第一步)
+/// 获取外部数据库的表列表 (级联第一步，schema 为空时取连接默认 schema)
 pub async fn get_metadata_tables(
     State(state): State<Arc<AppState>>,
     Query(req): Query<MetadataRequest>,
 ) -> impl IntoResponse {
-    let source = sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources WHERE id = $1")
-        .bind(&req.source_id)
-        .fetch_one(&state.db)
-        .await;
-    match source {
-        Ok(s) => Json(state.pool_manager.list_tables(&s).await.unwrap_or_default()).into_response(),
-        Err(_) => (StatusCode::NOT_FOUND, "Source config not found").into_response(),
+    match source_connector(&state, &req.source_id).await {
+        Ok(conn) => Json(conn.list_tables(req.schema.as_deref()).await.unwrap_or_default()).into_response(),
+        Err(resp) => resp,
     }
 }
 
@@ -180,210 +260,92 @@ pub async fn get_metadata_columns(
     State(state): State<Arc<AppState>>,
     Query(req): Query<MetadataRequest>,
 ) -> impl IntoResponse {
-    let source = sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources WHERE id = $1")
-        .bind(&req.source_id)
-        .fetch_one(&state.db)
-        .await;
-    match source {
-        Ok(s) => Json(
-            state
-                .pool_manager
-                .list_columns(&s, &req.table_name.unwrap_or_default())
+    match source_connector(&state, &req.source_id).await {
+        Ok(conn) => Json(
+            conn.list_columns(req.schema.as_deref(), &req.table_name.unwrap_or_default())
                 .await
                 .unwrap_or_default(),
         )
         .into_response(),
-        Err(_) => (StatusCode::NOT_FOUND, "Source config not found").into_response(),
+        Err(resp) => resp,
     }
 }
 
-/// 同步维度码值 (将物理数据值拉入 A-Box 语义存储)
-pub async fn sync_dimension_values(
+/// 探查外部表的完整元数据：列类型、可空性、主外键、注释与估算行数
+pub async fn describe_metadata_table(
     State(state): State<Arc<AppState>>,
-    Path(node_id): Path<Uuid>,
+    Query(req): Query<MetadataRequest>,
 ) -> impl IntoResponse {
-    let def_row = sqlx::query("SELECT d.source_id, d.target_table, d.sql_expression FROM semantic_definitions d WHERE d.node_id = $1")
-        .bind(node_id)
-        .fetch_one(&state.db).await;
-
-    let info = match def_row {
-        Ok(r) => r,
-        Err(_) => return (StatusCode::NOT_FOUND, "Ontology Definition missing").into_response(),
+    let Some(table) = req.table_name.as_deref().filter(|t| !t.is_empty()) else {
+        return (StatusCode::BAD_REQUEST, "table_name is required").into_response();
     };
+    match source_connector(&state, &req.source_id).await {
+        Ok(conn) => match conn.describe_table(req.schema.as_deref(), table).await {
+            Ok(meta) => Json(meta).into_response(),
+            Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+        },
+        Err(resp) => resp,
+    }
+}
 
-    let source_id: String = info.get("source_id");
-    let target_table: String = info.get("target_table");
-    let sql_expression: String = info.get("sql_expression");
-
+/// 按数据源 id 获取连接器（数据源不存在返回 404，建连失败返回 502）
+pub(crate) async fn source_connector(state: &AppState, source_id: &str) -> Result<Arc<dyn DataSourceConnector>, Response> {
     let source = sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources WHERE id = $1")
-        .bind(&source_id)
-        .fetch_one(&state.db)
+        .bind(source_id)
+        .fetch_optional(&state.db)
         .await
-        .unwrap();
-
-    let pool = state
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Source config not found").into_response())?;
+    state
         .pool_manager
         .get_or_create_pool(&source)
         .await
-        .unwrap();
-
-    // 执行基于逻辑表达式的去重查询
-    let sql = format!(
-        "SELECT DISTINCT ({}) :: text as val FROM {}",
-        sql_expression, target_table
-    );
-    info!("开始 A-Box 同步，物理查询: {}", sql);
-    let vals = match &*pool {
-        crate::infra::db_external::DynamicPool::Postgres(p) => sqlx::query(&sql)
-            .fetch_all(p)
-            .await
-            .unwrap()
-            .into_iter()
-            .filter_map(|r| r.try_get::<String, _>("val").ok())
-            .collect::<Vec<_>>(),
-        _ => vec![],
-    };
-
-    let count = vals.len();
-
-    for v in vals {
-        // 使用 bind 模式防止宏解析错误，保存实例数据
-        let _ = sqlx::query("INSERT INTO dimension_values (dimension_node_id, value_label, value_code) VALUES ($1, $2, $2) ON CONFLICT DO NOTHING")
-            .bind(node_id)
-            .bind(v)
-            .execute(&state.db).await;
-    }
-    info!("A-Box 同步完成，新增/更新 {} 个实例", count);
-
-    let _ = full_reload_semantic_engine(&state).await;
-    (StatusCode::OK, "A-Box Synced Successfully").into_response()
-}
-
-// --- 3. 语义资产导出 ---
-
-/// 导出本体知识库为标准 TTL (Turtle) 格式
-pub async fn export_ontology_ttl(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let nodes = sqlx::query_as::<Postgres, FullSemanticNode>(
-        "SELECT n.id, n.node_key, n.label, n.node_role, d.source_id, d.target_table, d.sql_expression, 
-                d.default_constraints, d.alias_names, d.default_agg, n.dataset_id,
-         COALESCE(array_agg(r.dimension_node_id) FILTER (WHERE r.dimension_node_id IS NOT NULL), '{}') as supported_dimension_ids
-         FROM ontology_nodes n JOIN semantic_definitions d ON n.id = d.node_id LEFT JOIN metric_dimension_rels r ON n.id = r.metric_node_id
-         GROUP BY n.id, n.node_key, n.label, n.node_role, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id"
-    ).fetch_all(&state.db).await.unwrap_or_default();
-
-    // 建立 ID 到语义 Key 的映射，用于 RDF 指向
-    let id_to_key_map: std::collections::HashMap<Uuid, String> =
-        nodes.iter().map(|n| (n.id, n.node_key.clone())).collect();
-    let mut ttl = String::from("@prefix sse: <http://example.org/sse#> .\n@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .\n@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n\n");
-
-    for n in nodes {
-        let uri = format!("sse:{}", n.node_key);
-        let role_iri = if n.node_role == "METRIC" {
-            "sse:Metric"
-        } else {
-            "sse:Dimension"
-        };
-
-        ttl.push_str(&format!("{} rdf:type {} ;\n", uri, role_iri));
-        ttl.push_str(&format!("    rdfs:label \"{}\" ;\n", n.label));
-        ttl.push_str(&format!("    sse:physicalTable \"{}\" ;\n", n.target_table));
-        ttl.push_str(&format!(
-            "    sse:sqlExpression \"{}\" ;\n",
-            n.sql_expression
-        ));
-
-        // 导出 T-Box 关联：使用语义标识符而非 UUID
-        if n.node_role == "METRIC" {
-            for dim_id in &n.supported_dimension_ids {
-                if let Some(dim_key) = id_to_key_map.get(dim_id) {
-                    ttl.push_str(&format!("    sse:hasDimension sse:{} ;\n", dim_key));
-                }
-            }
-        }
-        ttl.push_str(&format!("    sse:systemId \"{}\" .\n\n", n.id));
-    }
-
-    info!("语义 TTL 导出完成");
-
-    Response::builder()
-        .header(header::CONTENT_TYPE, "text/turtle")
-        .header(
-            header::CONTENT_DISPOSITION,
-            "attachment; filename=\"sse_enterprise_ontology.ttl\"",
-        )
-        .body(axum::body::Body::from(ttl))
-        .unwrap()
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()).into_response())
 }
 
-// --- 4. 数据源基础管理 ---
+// --- 3. 内存索引热重载 ---
 
-pub async fn register_data_source(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<CreateDataSourceRequest>,
-) -> impl IntoResponse {
-    let res = sqlx::query("INSERT INTO data_sources (id, db_type, connection_url, display_name) VALUES ($1, $2, $3, $4) ON CONFLICT (id) DO UPDATE SET connection_url=EXCLUDED.connection_url, display_name=EXCLUDED.display_name")
-    .bind(&payload.id).bind(&payload.db_type).bind(&payload.connection_url).bind(&payload.display_name).execute(&state.db).await;
-    match res {
-        Ok(_) => {
-            info!("数据源配置已更新: id={}", payload.id);
-            (StatusCode::CREATED, "Source Registered").into_response()
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+/// 建模修改后的刷新：存在生效发布时修改只进入草稿，仅刷新 SPARQL 视图；
+/// 实时模式（发布已全部撤回）下直接重载推理引擎
+pub(crate) async fn refresh_after_edit(state: &AppState) -> anyhow::Result<()> {
+    if revision::current_publication(&state.db).await?.is_none() {
+        return full_reload_semantic_engine(state).await;
     }
+    crate::service::sparql::refresh(state).await
 }
 
-pub async fn list_data_sources(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let rows = sqlx::query_as::<Postgres, DataSource>(
-        "SELECT id, db_type, connection_url, display_name FROM data_sources",
-    )
-    .fetch_all(&state.db)
-    .await;
-    match rows {
-        Ok(list) => Json(list).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-    }
-}
-
-/// 内部辅助：热重载内存语义索引
-async fn refresh_fst_cache(state: &AppState) -> anyhow::Result<()> {
-    let nodes = sqlx::query_as::<Postgres, FullSemanticNode>(
-        "SELECT n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, 
-                d.default_constraints, d.alias_names, d.default_agg, d.value_format, n.dataset_id, 
-                '{}'::uuid[] as supported_dimension_ids 
-         FROM ontology_nodes n 
-         JOIN semantic_definitions d ON n.id = d.node_id"
-    ).fetch_all(&state.db).await?;
-    let mut guard = state.fst.write().await;
-    *guard = FstEngine::build(&nodes)?;
-    info!("内存语义索引 FST 已热刷新");
-    Ok(())
-}
-
-async fn full_reload_semantic_engine(state: &AppState) -> anyhow::Result<()> {
-    let nodes = sqlx::query_as::<Postgres, FullSemanticNode>(
-        "SELECT n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format,
-        '{}'::uuid[] as supported_dimension_ids FROM ontology_nodes n JOIN semantic_definitions d ON n.id = d.node_id"
-    ).fetch_all(&state.db).await?;
-
-    // 1. 刷新 FST
+/// 进入分词词典的 A-Box 词汇：有效码值的展示标签及其同义词
+pub(crate) const ABOX_WORDS_SQL: &str = "SELECT value_label FROM dimension_values WHERE is_active
+     UNION SELECT normalized_label FROM dimension_values WHERE is_active AND normalized_label <> ''
+     UNION SELECT s.synonym FROM dimension_value_synonyms s JOIN dimension_values v ON v.id = s.value_id WHERE v.is_active
+     UNION SELECT s.normalized FROM dimension_value_synonyms s JOIN dimension_values v ON v.id = s.value_id WHERE v.is_active AND s.normalized <> ''";
+
+/// 重载推理引擎：服务的节点在存在生效发布时为发布快照，否则为实时本体
+/// 新的 FST 与分词器在锁外构建完成后一次性替换，问数不会看到新旧混合的索引
+pub(crate) async fn full_reload_semantic_engine(state: &AppState) -> anyhow::Result<()> {
+    let nodes = revision::load_serving_nodes(&state.db).await?;
+
+    // 1. 锁外构建 FST 与分词词典（重建词典，已移除的别名不再残留）
+    let fst = FstEngine::build(&nodes)?;
+    let mut words = nodes.iter().flat_map(|n| {
+        let mut v = vec![n.label.clone()];
+        v.extend(n.alias_names.clone());
+        v
+    }).collect::<Vec<String>>();
+    let codes = sqlx::query(ABOX_WORDS_SQL).fetch_all(&state.db).await?;
+    words.extend(codes.into_iter().map(|r| r.get::<String, _>(0)));
+    let mut engine = SemanticInferenceEngine::new();
+    engine.refresh_custom_words(words);
+
+    // 2. 原子替换：与问数路径同序加锁（先 engine 后 fst）
     {
+        let mut engine_guard = state.engine.write().await;
         let mut fst_guard = state.fst.write().await;
-        *fst_guard = FstEngine::build(&nodes)?;
+        *engine_guard = engine;
+        *fst_guard = fst;
     }
+    info!("推理引擎已切换: {} 个语义节点", nodes.len());
 
-    // 2. 刷新 Jieba
-    {
-        let mut engine_guard = state.engine.write().await;
-        let mut words = nodes.iter().flat_map(|n| {
-            let mut v = vec![n.label.clone()];
-            v.extend(n.alias_names.clone());
-            v
-        }).collect::<Vec<String>>();
-        
-        let codes = sqlx::query("SELECT value_label FROM dimension_values").fetch_all(&state.db).await?;
-        words.extend(codes.into_iter().map(|r| r.get::<String, _>(0)));
-        
-        engine_guard.refresh_custom_words(words);
-    }
-    Ok(())
-}
\ No newline at end of file
+    // 3. 刷新 SPARQL 三元组存储
+    crate::service::sparql::refresh(state).await
+}
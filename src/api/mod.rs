@@ -1,2 +1,14 @@
+pub mod auth;
 pub mod mapping;
-pub mod chat;
\ No newline at end of file
+pub mod chat;
+pub mod datasource;
+pub mod dimension_values;
+pub mod domains;
+pub mod feedback;
+pub mod history;
+pub mod ontology;
+pub mod revisions;
+pub mod security;
+pub mod shadow;
+pub mod sparql;
+pub mod sync;
\ No newline at end of file
@@ -0,0 +1,403 @@
+use crate::api::mapping::{full_reload_semantic_engine, refresh_after_edit, source_connector, write_node};
+use crate::ax_state::AppState;
+use crate::models::auth::Principal;
+use crate::models::bundle::{BundleExportQuery, BundleImportQuery, OntologyBundle, RdfExportQuery, RdfImportQuery};
+use crate::models::revision::Author;
+use crate::models::schema::{
+    BootstrapRequest, DiscoverRelationsRequest, OntologyDraft, OntologyRelation, RelationListQuery,
+};
+use crate::service::bootstrap::draft_from_table;
+use crate::service::{bundle, revision};
+use crate::service::rdf::{bundle_to_graph, graph_to_bundle, Namespaces};
+use crate::service::rdf_io::{self, RdfFormat};
+use crate::service::relations::discover;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde_json::json;
+use sqlx::{Postgres, Row};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// --- 1. 由物理表自动生成本体 ---
+
+/// 探查物理表并返回本体草稿（不落库）
+pub async fn bootstrap_draft(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BootstrapRequest>,
+) -> impl IntoResponse {
+    let conn = match source_connector(&state, &req.source_id).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let meta = match conn.describe_table(req.schema.as_deref(), &req.table_name).await {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+    let existing: HashSet<String> = match sqlx::query("SELECT node_key FROM ontology_nodes WHERE deleted_at IS NULL").fetch_all(&state.db).await {
+        Ok(rows) => rows.iter().map(|r| r.get(0)).collect(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // 显式指定 schema 时物理表名带上 schema 前缀
+    let target_table = match &req.schema {
+        Some(s) => format!("{}.{}", s, req.table_name),
+        None => req.table_name.clone(),
+    };
+    Json(draft_from_table(&req.source_id, &target_table, &meta, req.dataset_id, &existing)).into_response()
+}
+
+/// 提交（编辑后的）本体草稿：先写维度再写指标，单事务，任一节点失败整体回滚
+pub async fn bootstrap_commit(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Json(draft): Json<OntologyDraft>,
+) -> impl IntoResponse {
+    match commit_draft(&state, draft, &Author::new(&principal.subject, "bootstrap")).await {
+        Ok(ids) => {
+            let _ = refresh_after_edit(&state).await;
+            Json(json!({ "nodes": ids })).into_response()
+        }
+        Err((code, msg)) => (code, msg).into_response(),
+    }
+}
+
+async fn commit_draft(
+    state: &AppState,
+    draft: OntologyDraft,
+    author: &Author,
+) -> Result<HashMap<String, Uuid>, (StatusCode, String)> {
+    let mut seen = HashSet::new();
+    for d in &draft.nodes {
+        if !seen.insert(d.node.node_key.as_str()) {
+            return Err((StatusCode::BAD_REQUEST, format!("草稿中 node_key 重复: {}", d.node.node_key)));
+        }
+        if d.node.node_role != "METRIC" && d.node.node_role != "DIMENSION" {
+            return Err((StatusCode::BAD_REQUEST, format!("非法 node_role: {}", d.node.node_role)));
+        }
+    }
+
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let mut tx = state.db.begin().await.map_err(internal)?;
+    let mut ids: HashMap<String, Uuid> = HashMap::new();
+
+    let (dims, metrics): (Vec<_>, Vec<_>) = draft.nodes.into_iter().partition(|d| d.node.node_role == "DIMENSION");
+    for d in &dims {
+        let id = write_node(&mut tx, &d.node, author)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        ids.insert(d.node.node_key.clone(), id);
+    }
+
+    for mut m in metrics {
+        // 维度引用：优先解析为本草稿内的维度，其次为库中已有的同 key 维度
+        for key in &m.supported_dimension_keys {
+            let id = match ids.get(key) {
+                Some(id) => *id,
+                None => sqlx::query_scalar::<_, Uuid>(
+                    "SELECT id FROM ontology_nodes WHERE node_key = $1 AND node_role = 'DIMENSION' AND deleted_at IS NULL",
+                )
+                .bind(key)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(internal)?
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("指标 {} 引用了不存在的维度: {}", m.node.node_key, key)))?,
+            };
+            if !m.node.supported_dimension_ids.contains(&id) {
+                m.node.supported_dimension_ids.push(id);
+            }
+        }
+        let id = write_node(&mut tx, &m.node, author)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        ids.insert(m.node.node_key.clone(), id);
+    }
+
+    tx.commit().await.map_err(internal)?;
+    info!("本体草稿已提交: {} ({} 个节点)", draft.target_table, ids.len());
+    Ok(ids)
+}
+
+// --- 2. 表间关联发现 ---
+
+/// 扫描数据源目录发现表间关联，以 PROPOSED 状态写入（已存在的关联保持原状态）
+pub async fn discover_relations(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Json(req): Json<DiscoverRelationsRequest>,
+) -> impl IntoResponse {
+    let conn = match source_connector(&state, &req.source_id).await {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let tables = match conn.list_tables(req.schema.as_deref()).await {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+    };
+    let mut metas = Vec::with_capacity(tables.len());
+    for t in &tables {
+        match conn.describe_table(req.schema.as_deref(), t).await {
+            Ok(m) => metas.push(m),
+            Err(e) => warn!("关联发现跳过表 {}: {}", t, e),
+        }
+    }
+
+    let proposals = discover(&metas, req.schema.as_deref(), req.heuristics.unwrap_or(true));
+    let res: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let mut inserted = 0;
+        for p in &proposals {
+            inserted += sqlx::query(
+                "INSERT INTO ontology_relations (source_id, from_table, to_table, join_logic, origin, status)
+                 VALUES ($1, $2, $3, $4, $5, 'PROPOSED')
+                 ON CONFLICT (source_id, from_table, to_table, join_logic) DO NOTHING",
+            )
+            .bind(&req.source_id)
+            .bind(&p.from_table)
+            .bind(&p.to_table)
+            .bind(&p.join_logic)
+            .bind(&p.origin)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        revision::record_relations(&mut tx, &Author::new(&principal.subject, "relation")).await?;
+        tx.commit().await?;
+        Ok(inserted)
+    }
+    .await;
+    let inserted = match res {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    info!(
+        "关联发现完成: source={}, 扫描 {} 张表, 候选 {} 条, 新增 {} 条",
+        req.source_id,
+        metas.len(),
+        proposals.len(),
+        inserted
+    );
+    Json(json!({ "scanned_tables": metas.len(), "inserted": inserted, "proposals": proposals })).into_response()
+}
+
+pub async fn list_relations(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<RelationListQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, OntologyRelation>(
+        "SELECT * FROM ontology_relations
+         WHERE ($1::varchar IS NULL OR source_id = $1) AND ($2::varchar IS NULL OR status = $2)
+         ORDER BY id",
+    )
+    .bind(&q.source_id)
+    .bind(q.status.as_ref().map(|s| s.to_uppercase()))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 确认或驳回候选关联（驳回的关联不会被再次发现）
+pub async fn review_relation(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path((id, action)): Path<(i32, String)>,
+) -> impl IntoResponse {
+    let status = match action.as_str() {
+        "accept" => "ACCEPTED",
+        "reject" => "REJECTED",
+        _ => return (StatusCode::BAD_REQUEST, "action 仅支持 accept / reject").into_response(),
+    };
+    let res: Result<Option<OntologyRelation>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let relation = sqlx::query_as::<Postgres, OntologyRelation>(
+            "UPDATE ontology_relations SET status = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(status)
+        .fetch_optional(&mut *tx)
+        .await?;
+        revision::record_relations(&mut tx, &Author::new(&principal.subject, "relation")).await?;
+        tx.commit().await?;
+        Ok(relation)
+    }
+    .await;
+    match res {
+        Ok(Some(r)) => {
+            info!("关联审核: {} -> {}", r.join_logic, r.status);
+            Json(r).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Relation not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 删除关联（记为修订，可经丢弃草稿恢复）
+pub async fn delete_relation(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    let res: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let deleted = sqlx::query("DELETE FROM ontology_relations WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        revision::record_relations(&mut tx, &Author::new(&principal.subject, "relation")).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+    .await;
+    match res {
+        Ok(0) => (StatusCode::NOT_FOUND, "Relation not found").into_response(),
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 3. 本体包导入导出 ---
+
+/// 导出本体包（JSON / YAML），用于在环境之间迁移语义模型
+pub async fn export_bundle(State(state): State<Arc<AppState>>, Query(q): Query<BundleExportQuery>) -> Response {
+    let bundle = match bundle::export(&state.db, q.values.unwrap_or(true)).await {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    info!("📦 本体包导出: {} 个节点, {} 条关联", bundle.nodes.len(), bundle.relations.len());
+    match q.format.as_deref() {
+        Some("yaml") | Some("yml") => match serde_yaml::to_string(&bundle) {
+            Ok(body) => (
+                [
+                    (header::CONTENT_TYPE, "application/yaml"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"sse_ontology_bundle.yaml\""),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        Some("json") | None => Json(bundle).into_response(),
+        Some(other) => (StatusCode::BAD_REQUEST, format!("不支持的格式: {}", other)).into_response(),
+    }
+}
+
+/// 导入本体包：默认只校验并返回差异（dry_run），dry_run=false 时在单事务内落库
+pub async fn import_bundle(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Query(q): Query<BundleImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let is_yaml = match q.format.as_deref() {
+        Some(f) => f == "yaml" || f == "yml",
+        None => headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("yaml")),
+    };
+    let parsed: Result<OntologyBundle, String> = if is_yaml {
+        serde_yaml::from_str(&body).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&body).map_err(|e| e.to_string())
+    };
+    let bundle = match parsed {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("本体包解析失败: {}", e)).into_response(),
+    };
+
+    import_bundle_into(&state, bundle, q.dry_run.unwrap_or(true), &Author::new(&principal.subject, "bundle")).await
+}
+
+/// 校验、预览并（非 dry_run 时）应用本体包，本体包与 RDF 导入共用
+async fn import_bundle_into(state: &AppState, bundle: OntologyBundle, dry_run: bool, author: &Author) -> Response {
+    let mut report = match bundle::plan(&state.db, &bundle).await {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    report.dry_run = dry_run;
+    if report.dry_run {
+        return Json(report).into_response();
+    }
+    if !report.errors.is_empty() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response();
+    }
+
+    if let Err(e) = bundle::apply(&state.db, &bundle, author).await {
+        warn!("本体包导入失败，已回滚: {}", e);
+        report.errors.push(e.to_string());
+        return (StatusCode::CONFLICT, Json(report)).into_response();
+    }
+    report.applied = true;
+    let _ = full_reload_semantic_engine(state).await;
+    Json(report).into_response()
+}
+
+// --- 4. RDF 导入导出 ---
+
+/// 导出本体为 RDF（Turtle / JSON-LD / N-Triples）
+pub async fn export_ontology_rdf(State(state): State<Arc<AppState>>, Query(q): Query<RdfExportQuery>) -> Response {
+    let format = match q.format.as_deref() {
+        None => RdfFormat::Turtle,
+        Some(f) => match RdfFormat::parse(f) {
+            Some(f) => f,
+            None => return (StatusCode::BAD_REQUEST, format!("不支持的格式: {}", f)).into_response(),
+        },
+    };
+    let bundle = match bundle::export(&state.db, q.values.unwrap_or(true)).await {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let ns = Namespaces::new(q.base.as_deref());
+    let triples = bundle_to_graph(&bundle, &ns);
+    let body = rdf_io::serialize(format, &triples, &ns.prefixes());
+    info!("语义 RDF 导出完成: {:?}, {} 个三元组", format, triples.len());
+
+    let disposition = format!("attachment; filename=\"sse_enterprise_ontology.{}\"", format.extension());
+    (
+        [(header::CONTENT_TYPE, format.content_type().to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response()
+}
+
+/// 导入 RDF：还原为本体包后走与本体包相同的校验、差异预览与单事务落库
+pub async fn import_ontology_rdf(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Query(q): Query<RdfImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let format = match q.format.as_deref() {
+        Some(f) => RdfFormat::parse(f),
+        None => headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(RdfFormat::from_content_type)
+            .or(Some(RdfFormat::Turtle)),
+    };
+    let Some(format) = format else {
+        return (StatusCode::BAD_REQUEST, "format 仅支持 turtle / jsonld / ntriples").into_response();
+    };
+    let triples = match rdf_io::parse(format, &body) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("RDF 解析失败: {}", e)).into_response(),
+    };
+    let ns = Namespaces::new(q.base.as_deref());
+    let bundle = match graph_to_bundle(&triples, &ns) {
+        Ok(b) => b,
+        Err(errors) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "errors": errors }))).into_response(),
+    };
+    info!("RDF 导入解析完成: {} 个三元组, {} 个节点", triples.len(), bundle.nodes.len());
+    import_bundle_into(&state, bundle, q.dry_run.unwrap_or(true), &Author::new(&principal.subject, "bundle")).await
+}
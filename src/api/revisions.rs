@@ -0,0 +1,401 @@
+use crate::api::mapping::{full_reload_semantic_engine, refresh_after_edit, upsert_node};
+use crate::ax_state::AppState;
+use crate::models::auth::Principal;
+use crate::models::revision::{
+    Author, DiffQuery, OntologyPublication, OntologyRevision, PublishRequest, RevisionListQuery, RollbackRequest,
+    LintQuery, ValidateQuery, ValidationReport,
+};
+use crate::models::schema::CreateNodeRequest;
+use crate::service::{lint, revision, validation};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde_json::json;
+use sqlx::Postgres;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// --- 1. 修订历史与差异 ---
+
+/// 全局变更日志（按版本号倒序分页）
+pub async fn list_revisions(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<RevisionListQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, OntologyRevision>(
+        "SELECT * FROM ontology_revisions ORDER BY id DESC LIMIT $1 OFFSET $2",
+    )
+    .bind(q.limit.unwrap_or(50).clamp(1, 500))
+    .bind(q.offset.unwrap_or(0).max(0))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 单个节点的修订历史
+pub async fn node_history(
+    State(state): State<Arc<AppState>>,
+    Path(node_key): Path<String>,
+    Query(q): Query<RevisionListQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, OntologyRevision>(
+        "SELECT * FROM ontology_revisions WHERE node_key = $1 ORDER BY id DESC LIMIT $2 OFFSET $3",
+    )
+    .bind(&node_key)
+    .bind(q.limit.unwrap_or(50).clamp(1, 500))
+    .bind(q.offset.unwrap_or(0).max(0))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) if list.is_empty() && q.offset.unwrap_or(0) == 0 => {
+            (StatusCode::NOT_FOUND, "No revision found for node").into_response()
+        }
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn get_revision(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match find_revision(&state, id).await {
+        Ok(Some(r)) => Json(r).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Revision not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 比较两个版本的本体（from 默认当前发布版本，to 默认最新修订），可按节点过滤
+pub async fn diff_ontology(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<DiffQuery>,
+) -> impl IntoResponse {
+    let res: Result<_, sqlx::Error> = async {
+        let from = match q.from {
+            Some(v) => v,
+            None => revision::current_publication(&state.db).await?.map(|p| p.version).unwrap_or(0),
+        };
+        let to = match q.to {
+            Some(v) => v,
+            None => revision::latest_version(&state.db).await?,
+        };
+        let changes = revision::diff_versions(&state.db, from, to, q.node.as_deref()).await?;
+        Ok(json!({ "from": from, "to": to, "changes": changes }))
+    }
+    .await;
+    match res {
+        Ok(body) => Json(body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 2. 回滚 ---
+
+/// 将节点恢复为指定版本的定义（作为一个新修订写入，原历史保留）
+/// 快照中引用的维度或数据集已不存在时跳过该引用，并在响应中列出
+pub async fn rollback_node(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Path(node_key): Path<String>,
+    Json(payload): Json<RollbackRequest>,
+) -> impl IntoResponse {
+    let target = match find_revision(&state, payload.version).await {
+        Ok(Some(r)) if r.node_key == node_key => r,
+        Ok(Some(_)) => return (StatusCode::BAD_REQUEST, format!("版本 {} 不属于节点 {}", payload.version, node_key)).into_response(),
+        Ok(None) => return (StatusCode::NOT_FOUND, "Revision not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if revision::parse_relation_key(&node_key).is_some() {
+        return rollback_relation(&state, &principal, &node_key, target).await;
+    }
+    let Some(mut req) = target
+        .snapshot
+        .clone()
+        .and_then(|s| serde_json::from_value::<CreateNodeRequest>(s).ok())
+    else {
+        return (StatusCode::BAD_REQUEST, format!("版本 {} 是删除记录，无可恢复的定义", payload.version)).into_response();
+    };
+
+    let live_dims: Vec<Uuid> = match sqlx::query_scalar(
+        "SELECT id FROM ontology_nodes WHERE id = ANY($1) AND node_role = 'DIMENSION' AND deleted_at IS NULL",
+    )
+    .bind(&req.supported_dimension_ids)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let dropped_dimensions: Vec<Uuid> =
+        req.supported_dimension_ids.iter().filter(|id| !live_dims.contains(id)).copied().collect();
+    req.supported_dimension_ids.retain(|id| live_dims.contains(id));
+
+    let mut dropped_dataset = None;
+    if let Some(ds) = req.dataset_id {
+        match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM semantic_datasets WHERE id = $1")
+            .bind(ds)
+            .fetch_one(&state.db)
+            .await
+        {
+            Ok(0) => {
+                dropped_dataset = req.dataset_id.take();
+            }
+            Ok(_) => {}
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+
+    let author = Author::new(&principal.subject, &format!("rollback:{}", payload.version));
+    match upsert_node(&state, req, &author).await {
+        Ok(id) => {
+            info!("⏪ 节点 {} 已回滚至版本 {}", node_key, payload.version);
+            Json(json!({
+                "id": id,
+                "node_key": node_key,
+                "restored_from": payload.version,
+                "dropped_dimensions": dropped_dimensions,
+                "dropped_dataset": dropped_dataset
+            }))
+            .into_response()
+        }
+        Err(e) => (StatusCode::CONFLICT, e).into_response(),
+    }
+}
+
+/// 关联回滚：按快照原 id 重建，所属数据源已删除时拒绝
+async fn rollback_relation(
+    state: &AppState,
+    principal: &Principal,
+    node_key: &str,
+    target: OntologyRevision,
+) -> axum::response::Response {
+    let Some(id) = revision::parse_relation_key(node_key) else {
+        return (StatusCode::BAD_REQUEST, format!("无效的关联标识 {}", node_key)).into_response();
+    };
+    let Some(snap) = target.snapshot else {
+        return (StatusCode::BAD_REQUEST, format!("版本 {} 是删除记录，无可恢复的定义", target.id)).into_response();
+    };
+    let res: Result<bool, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let source_alive = match snap["source_id"].as_str() {
+            Some(source) => sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM data_sources WHERE id = $1)")
+                .bind(source)
+                .fetch_one(&mut *tx)
+                .await?,
+            None => true,
+        };
+        if !source_alive {
+            return Ok(false);
+        }
+        revision::write_relation(&mut tx, id, &snap).await?;
+        let author = Author::new(&principal.subject, &format!("rollback:{}", target.id));
+        revision::record_relations(&mut tx, &author).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+    match res {
+        Ok(true) => {
+            info!("⏪ 关联 {} 已回滚至版本 {}", node_key, target.id);
+            Json(json!({ "id": id, "node_key": node_key, "restored_from": target.id })).into_response()
+        }
+        Ok(false) => (StatusCode::CONFLICT, format!("关联 {} 所属的数据源已删除，无法恢复", node_key)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 3. 发布快照 ---
+
+/// 当前服务模式：发布快照 (published) 或实时本体 (live)，以及草稿中未发布的变更数
+pub async fn publish_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let res: Result<_, sqlx::Error> = async {
+        let current = revision::current_publication(&state.db).await?;
+        let latest = revision::latest_version(&state.db).await?;
+        let pending = match &current {
+            Some(p) => revision::diff_versions(&state.db, p.version, latest, None).await?.len(),
+            None => 0,
+        };
+        Ok(json!({
+            "mode": if current.is_some() { "published" } else { "live" },
+            "publication": current,
+            "latest_version": latest,
+            "pending_changes": pending
+        }))
+    }
+    .await;
+    match res {
+        Ok(body) => Json(body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn list_publications(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, OntologyPublication>("SELECT * FROM ontology_publications ORDER BY id DESC")
+        .fetch_all(&state.db)
+        .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 校验指定版本（默认最新修订，即草稿）能否发布
+pub async fn validate_ontology(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<ValidateQuery>,
+) -> impl IntoResponse {
+    let version = match resolve_version(&state, q.version).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    match validate_version(&state, version).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 发布本体快照：先校验该版本，存在 ERROR 级问题时拒绝发布 (422 + 校验报告)
+/// 通过后推理引擎原子切换为服务该版本；发布较早的版本即线上回退
+pub async fn publish_ontology(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+    Json(payload): Json<PublishRequest>,
+) -> impl IntoResponse {
+    let version = match resolve_version(&state, payload.version).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let report = match validate_version(&state, version).await {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    if !report.passed {
+        warn!("本体版本 {} 未通过校验，拒绝发布", version);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response();
+    }
+
+    let res = sqlx::query_as::<Postgres, OntologyPublication>(
+        "INSERT INTO ontology_publications (version, note, published_by) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(version)
+    .bind(&payload.note)
+    .bind(&principal.subject)
+    .fetch_one(&state.db)
+    .await;
+    match res {
+        Ok(publication) => {
+            if let Err(e) = full_reload_semantic_engine(&state).await {
+                warn!("发布后推理引擎重载失败: {}", e);
+            }
+            if let Err(e) = revision::purge_deleted(&state.db).await {
+                warn!("清理已删除节点失败: {}", e);
+            }
+            info!("🚀 本体已发布: 版本 {} (by {})", version, principal.subject);
+            (StatusCode::CREATED, Json(json!({ "publication": publication, "validation": report }))).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 撤回当前发布：推理引擎回到上一个未撤回的发布，不存在时服务实时本体
+pub async fn withdraw_publication(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let res = sqlx::query(
+        "UPDATE ontology_publications SET withdrawn_at = NOW()
+         WHERE id = (SELECT MAX(id) FROM ontology_publications WHERE withdrawn_at IS NULL)",
+    )
+    .execute(&state.db)
+    .await;
+    match res {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "No active publication").into_response(),
+        Ok(_) => {
+            if let Err(e) = full_reload_semantic_engine(&state).await {
+                warn!("撤回发布后推理引擎重载失败: {}", e);
+            }
+            if let Err(e) = revision::purge_deleted(&state.db).await {
+                warn!("清理已删除节点失败: {}", e);
+            }
+            let current = revision::current_publication(&state.db).await.ok().flatten();
+            info!("本体发布已撤回，当前服务版本: {:?}", current.as_ref().map(|p| p.version));
+            Json(json!({ "publication": current })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 本体一致性检查（默认检查最新修订，即草稿），可用 ?severity= 只看指定级别及以上
+pub async fn lint_ontology(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<LintQuery>,
+) -> impl IntoResponse {
+    let severity = q.severity.as_deref().unwrap_or("INFO").to_uppercase();
+    if !["ERROR", "WARNING", "INFO"].contains(&severity.as_str()) {
+        return (StatusCode::BAD_REQUEST, format!("不支持的级别: {}", severity)).into_response();
+    }
+    let version = match resolve_version(&state, q.version).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let res = async {
+        let nodes = revision::nodes_at(&state.db, version).await?;
+        lint::lint(&state, version, &nodes, &severity).await
+    }
+    .await;
+    match res {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// --- 4. 草稿 ---
+
+/// 丢弃草稿：实时本体重置为当前发布版本（重置记为新的修订，线上不受影响）
+pub async fn discard_draft(
+    State(state): State<Arc<AppState>>,
+    Extension(principal): Extension<Principal>,
+) -> impl IntoResponse {
+    let publication = match revision::current_publication(&state.db).await {
+        Ok(Some(p)) => p,
+        Ok(None) => return (StatusCode::CONFLICT, "当前无生效发布，没有可丢弃的草稿").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    match revision::reset_to(&state.db, publication.version, &Author::new(&principal.subject, "discard")).await {
+        Ok(nodes) => {
+            let _ = refresh_after_edit(&state).await;
+            info!("🗑️ 草稿已丢弃: 重置 {} 个节点至版本 {}", nodes.len(), publication.version);
+            Json(json!({ "version": publication.version, "reset_nodes": nodes })).into_response()
+        }
+        Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+    }
+}
+
+/// 版本号缺省为最新修订，需在 1 ~ 最新修订之间
+async fn resolve_version(state: &AppState, version: Option<i64>) -> Result<i64, Response> {
+    let latest = revision::latest_version(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    let version = version.unwrap_or(latest);
+    if version <= 0 || version > latest {
+        return Err((StatusCode::BAD_REQUEST, format!("版本号需在 1 ~ {} 之间", latest)).into_response());
+    }
+    Ok(version)
+}
+
+async fn validate_version(state: &AppState, version: i64) -> anyhow::Result<ValidationReport> {
+    let nodes = revision::nodes_at(&state.db, version).await?;
+    validation::validate(state, version, &nodes).await
+}
+
+async fn find_revision(state: &AppState, id: i64) -> sqlx::Result<Option<OntologyRevision>> {
+    sqlx::query_as::<Postgres, OntologyRevision>("SELECT * FROM ontology_revisions WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+}
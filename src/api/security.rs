@@ -0,0 +1,83 @@
+use crate::ax_state::AppState;
+use crate::models::auth::{CreatePolicyRequest, Role, SecurityPolicy};
+use crate::service::security::validate_predicate;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use sqlx::Postgres;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// 安全策略列表
+pub async fn list_policies(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, SecurityPolicy>("SELECT * FROM security_policies ORDER BY created_at")
+        .fetch_all(&state.db)
+        .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 创建安全策略（挂在节点或数据源二者之一上）
+pub async fn create_policy(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreatePolicyRequest>,
+) -> impl IntoResponse {
+    let effect = payload.effect.to_uppercase();
+    if effect != "ROW_FILTER" && effect != "HIDE" {
+        return (StatusCode::BAD_REQUEST, "effect 仅支持 ROW_FILTER / HIDE").into_response();
+    }
+    let role = match payload.role.as_deref().map(Role::parse) {
+        None => None,
+        Some(Some(r)) if r != Role::Admin => Some(r.as_str()),
+        Some(_) => return (StatusCode::BAD_REQUEST, "role 仅支持 analyst / modeler（管理员不受策略约束）").into_response(),
+    };
+    if payload.node_id.is_some() == payload.source_id.is_some() {
+        return (StatusCode::BAD_REQUEST, "node_id 与 source_id 必须且只能指定一个").into_response();
+    }
+    if effect == "ROW_FILTER" {
+        if let Err(e) = validate_predicate(payload.predicate.as_deref().unwrap_or_default()) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+
+    let res = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO security_policies (name, role, node_id, source_id, effect, predicate)
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(&payload.name)
+    .bind(role)
+    .bind(payload.node_id)
+    .bind(&payload.source_id)
+    .bind(&effect)
+    .bind(payload.predicate.as_deref().map(str::trim).filter(|_| effect == "ROW_FILTER"))
+    .fetch_one(&state.db)
+    .await;
+    match res {
+        Ok(id) => {
+            info!("安全策略已创建: {} ({}, role={:?})", payload.name, effect, role);
+            (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+pub async fn delete_policy(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM security_policies WHERE id = $1")
+        .bind(id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Policy not found").into_response(),
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
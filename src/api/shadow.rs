@@ -0,0 +1,104 @@
+use crate::ax_state::AppState;
+use crate::core::llm_fallback;
+use crate::core::planner::CanonicalPlan;
+use crate::infra::llm::planner_system_prompt;
+use crate::models::schema::{LogicalPlanDraft, PlanFilterDraft, ShadowRun};
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::Postgres;
+use std::sync::Arc;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct ShadowRunQuery {
+    pub limit: Option<i64>,
+}
+
+/// 查看最近的影子执行差异
+pub async fn list_shadow_runs(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<ShadowRunQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, ShadowRun>(
+        "SELECT * FROM shadow_runs ORDER BY created_at DESC LIMIT $1",
+    )
+    .bind(q.limit.unwrap_or(100))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 将影子执行差异导出为 JSONL 微调样本
+/// 每行一个 chat 格式样本：system(规划器提示词+当前目录) / user(提问) / assistant(规则推理给出的计划)
+pub async fn export_shadow_samples(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let runs = match sqlx::query_as::<Postgres, ShadowRun>("SELECT * FROM shadow_runs ORDER BY created_at")
+        .fetch_all(&state.db)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let nodes = llm_fallback::load_nodes(&state).await;
+    let system_prompt = match llm_fallback::load_dimension_rels(&state)
+        .await
+        .and_then(|rels| planner_system_prompt(&llm_fallback::build_catalog(&nodes, &rels)))
+    {
+        Ok(p) => p,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let mut jsonl = String::new();
+    for run in &runs {
+        // 规则计划为确定性事实，作为监督目标
+        let Ok(rule) = serde_json::from_value::<CanonicalPlan>(run.rule_plan.clone()) else {
+            continue;
+        };
+        let target = LogicalPlanDraft {
+            metric: rule.metric,
+            filters: rule
+                .filters
+                .into_iter()
+                .map(|(dimension, value)| PlanFilterDraft { dimension, value })
+                .collect(),
+            group_by: rule.group_by,
+            agg: Some(rule.agg),
+        };
+        let sample = json!({
+            "messages": [
+                { "role": "system", "content": system_prompt },
+                { "role": "user", "content": run.question },
+                { "role": "assistant", "content": serde_json::to_string(&target).unwrap_or_default() }
+            ],
+            "metadata": {
+                "shadow_run_id": run.id,
+                "provider": run.provider,
+                "llm_plan": run.llm_plan,
+                "llm_error": run.llm_error,
+                "created_at": run.created_at
+            }
+        });
+        jsonl.push_str(&sample.to_string());
+        jsonl.push('\n');
+    }
+
+    info!("影子样本导出完成: {} 条", runs.len());
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"sse_shadow_samples.jsonl\""),
+        ],
+        jsonl,
+    )
+        .into_response()
+}
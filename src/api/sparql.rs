@@ -0,0 +1,58 @@
+use crate::ax_state::AppState;
+use crate::models::bundle::SparqlRequest;
+use crate::service::sparql;
+use axum::{
+    extract::{FromRequest, Query, Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Form,
+};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// SPARQL 查询端点 (GET ?query= / POST application/sparql-query 或表单 query=)
+pub async fn sparql_query(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<SparqlRequest>,
+    req: Request,
+) -> Response {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let text = match q.query {
+        Some(text) => text,
+        None if content_type.starts_with("application/x-www-form-urlencoded") => {
+            match Form::<SparqlRequest>::from_request(req, &()).await {
+                Ok(Form(f)) => f.query.unwrap_or_default(),
+                Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            }
+        }
+        None => match String::from_request(req, &()).await {
+            Ok(body) => body,
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        },
+    };
+    if text.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "缺少 SPARQL 查询 (query)").into_response();
+    }
+
+    let store = state.rdf.read().await;
+    let result = sparql::parse_query(&text, store.prefixes()).and_then(|query| sparql::execute(&store, &query));
+    match result {
+        Ok(body) => {
+            info!("🔎 SPARQL 查询完成 (三元组 {} 条)", store.len());
+            (
+                [(header::CONTENT_TYPE, "application/sparql-results+json")],
+                body.to_string(),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            warn!("SPARQL 查询失败: {}", e);
+            (StatusCode::BAD_REQUEST, format!("SPARQL 查询失败: {}", e)).into_response()
+        }
+    }
+}
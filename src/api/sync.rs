@@ -0,0 +1,101 @@
+use crate::ax_state::AppState;
+use crate::models::schema::{SyncRun, SyncRunQuery, SyncSchedule, UpsertSyncScheduleRequest};
+use crate::service::sync::{parse_interval, sync_dimension};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use sqlx::Postgres;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// 手动同步维度码值 (将物理数据值拉入 A-Box 语义存储)，返回本次运行记录
+pub async fn sync_dimension_values(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match sync_dimension(&state, node_id, "MANUAL").await {
+        Ok(Some(run)) if run.status == "SUCCESS" => Json(run).into_response(),
+        Ok(Some(run)) => (StatusCode::BAD_GATEWAY, Json(run)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Dimension node not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 同步运行记录（按时间倒序，可按维度筛选）
+pub async fn list_sync_runs(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<SyncRunQuery>,
+) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, SyncRun>(
+        "SELECT * FROM sync_runs WHERE ($1::uuid IS NULL OR dimension_node_id = $1)
+         ORDER BY started_at DESC LIMIT $2",
+    )
+    .bind(q.node_id)
+    .bind(q.limit.unwrap_or(50).clamp(1, 500))
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn list_sync_schedules(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rows = sqlx::query_as::<Postgres, SyncSchedule>("SELECT * FROM dimension_sync_schedules ORDER BY next_run_at")
+        .fetch_all(&state.db)
+        .await;
+    match rows {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 设置维度的定时同步（首次运行在一个间隔之后）
+pub async fn upsert_sync_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+    Json(req): Json<UpsertSyncScheduleRequest>,
+) -> impl IntoResponse {
+    let Some(secs) = parse_interval(&req.interval).filter(|s| *s >= 60) else {
+        return (StatusCode::BAD_REQUEST, "interval 格式如 30m / 6h / 1d，最短 60 秒").into_response();
+    };
+    let res = sqlx::query_as::<Postgres, SyncSchedule>(
+        "INSERT INTO dimension_sync_schedules (dimension_node_id, interval_secs, enabled, next_run_at)
+         SELECT id, $2, $3, NOW() + $2 * INTERVAL '1 second' FROM ontology_nodes WHERE id = $1 AND node_role = 'DIMENSION'
+         ON CONFLICT (dimension_node_id) DO UPDATE SET interval_secs = EXCLUDED.interval_secs,
+             enabled = EXCLUDED.enabled, next_run_at = EXCLUDED.next_run_at
+         RETURNING *",
+    )
+    .bind(node_id)
+    .bind(secs)
+    .bind(req.enabled.unwrap_or(true))
+    .fetch_optional(&state.db)
+    .await;
+    match res {
+        Ok(Some(s)) => {
+            info!("维度同步调度已设置: node={}, 每 {} 秒", node_id, secs);
+            Json(s).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Dimension node not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+pub async fn delete_sync_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM dimension_sync_schedules WHERE dimension_node_id = $1")
+        .bind(node_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Schedule not found").into_response(),
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
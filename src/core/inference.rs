@@ -1,4 +1,5 @@
 use crate::ax_state::AppState;
+use crate::core::normalize::{normalize_text, normalize_value, to_half_width};
 use crate::models::schema::FullSemanticNode;
 use jieba_rs::Jieba;
 use regex::Regex;
@@ -8,14 +9,75 @@ use std::sync::Arc;
 use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
+/// 维度前置词：出现在维度名之前时表示按该维度分组（如 "按平台"、"各平台"）
+const GROUP_BY_PREFIXES: [&str; 5] = ["按", "各", "每个", "每", "分"];
+/// 维度后置词：出现在维度名之后时表示按该维度分组（如 "平台的收益"、"平台分布"）
+const GROUP_BY_SUFFIXES: [&str; 6] = ["的", "分布", "趋势", "走势", "排名", "占比"];
+/// 时间序列意图词：未指定具体日期时，按指标的日期维度展开
+const TIME_SERIES_KEYWORDS: [&str; 6] = ["趋势", "走势", "每天", "每日", "逐日", "按日"];
+
 pub struct SemanticInferenceEngine {
     jieba: Jieba,
 }
 
+/// 推理阶段的可识别失败（供上层决定是否走 LLM 兜底）
+#[derive(Debug)]
+pub enum InferenceError {
+    NoMetricAnchor,
+}
+
+impl std::fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferenceError::NoMetricAnchor => {
+                write!(f, "未识别到指标锚点，请明确提问目标（如：收益、应还）")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InferenceError {}
+
 #[derive(Debug)]
 pub struct InferenceResult {
     pub metric: FullSemanticNode,
     pub filters: Vec<(FullSemanticNode, String)>, // (维度节点, 物理值)
+    pub group_by: Vec<FullSemanticNode>,          // 仅出现维度名、未绑定值的分组维度
+}
+
+/// 维度名在提问中的用法
+#[derive(Debug, PartialEq)]
+enum DimensionMention<'a> {
+    GroupBy,
+    Value(&'a str),
+    Ignored,
+}
+
+/// 根据维度名前后的分词判断其用法
+/// 分组意图：如 "按平台"、"平台的收益"、"平台收益"；
+/// 动态值：后面跟着一个非指标且非“是/为”的词，捕获为动态 Value
+fn classify_dimension_mention<'a>(prev_word: &str, next_word: &'a str, next_is_node: bool) -> DimensionMention<'a> {
+    if GROUP_BY_PREFIXES.contains(&prev_word)
+        || next_word.is_empty()
+        || next_is_node
+        || GROUP_BY_SUFFIXES.contains(&next_word)
+    {
+        DimensionMention::GroupBy
+    } else if next_word.len() > 1 && next_word != "是" && next_word != "为" {
+        DimensionMention::Value(next_word)
+    } else {
+        DimensionMention::Ignored
+    }
+}
+
+/// 归一化后的词是否为该节点的名称或别名；对调用方不可见的节点一律不算，以免泄露其存在
+fn names_visible_node(node: &FullSemanticNode, word: &str, hidden: &HashSet<Uuid>) -> bool {
+    !hidden.contains(&node.id)
+        && (normalize_text(&node.label) == word || node.alias_names.iter().any(|a| normalize_text(a) == word))
+}
+
+fn is_time_series(query: &str) -> bool {
+    TIME_SERIES_KEYWORDS.iter().any(|k| query.contains(k))
 }
 
 impl SemanticInferenceEngine {
@@ -34,11 +96,13 @@ impl SemanticInferenceEngine {
         info!("分词器自定义词典已热重载，新增词汇数量: {}", cnt);
     }
 
-    #[instrument(skip(self, state), fields(query = %query))]
+    /// hidden：调用方无权访问的节点，不参与 FST 与 A-Box 匹配
+    #[instrument(skip(self, state, hidden), fields(query = %query))]
     pub async fn infer(
         &self,
         state: Arc<AppState>,
         query: &str,
+        hidden: &HashSet<Uuid>,
     ) -> anyhow::Result<InferenceResult> {
         let fst = state.fst.read().await;
         info!("🧠 启动语义推理流水线...");
@@ -50,49 +114,73 @@ impl SemanticInferenceEngine {
             info!("📍 识别到日期特征串: {}", d);
         }
 
-        // 2. 语义分词
-        let words = self.jieba.cut(query, false);
+        // 2. 语义分词（全角字符先转半角，保留大小写以便动态值原样捕获）
+        let query = to_half_width(query);
+        let words = self.jieba.cut(&query, false);
         debug!("分词 Token 序列: {:?}", words);
 
         let mut target_metrics = Vec::new();
         // 候选池：记录所有识别到的 (维度节点, 提取到的值)
         let mut raw_candidates = Vec::new();
+        // 分组候选：只提到了维度名而没有给出具体值
+        let mut group_candidates: Vec<FullSemanticNode> = Vec::new();
 
         // 3. 扫描识别
         for (idx, word) in words.iter().enumerate() {
-            let w = word.to_lowercase();
+            let w = normalize_text(word);
 
             // A. FST 匹配 (识别指标名和维度名)
             for entry in fst.node_cache.iter() {
                 let n = entry.value();
-                if n.label == w || n.alias_names.contains(&w) {
+                if hidden.contains(&n.id) {
+                    continue;
+                }
+                if normalize_text(&n.label) == w || n.alias_names.iter().any(|a| normalize_text(a) == w) {
                     if n.node_role == "METRIC" {
                         target_metrics.push(n.clone());
                     } else if n.node_role == "DIMENSION" {
                         debug!("FST 命中维度定义: {}", n.label);
-                        // 动态值推断逻辑：如果后面跟着一个非指标且非“是/为”的词，捕获为动态 Value
-                        if idx + 1 < words.len() {
-                            let next_word = words[idx + 1].trim();
-                            if next_word.len() > 1 && next_word != "是" && next_word != "为" {
-                                debug!("基于上下文捕获动态值: {} -> {}", n.label, next_word);
-                                raw_candidates.push((n.clone(), next_word.to_string()));
+                        let prev_word = if idx > 0 { words[idx - 1].trim() } else { "" };
+                        let next_word = words.get(idx + 1).map(|w| w.trim()).unwrap_or("");
+                        let next_norm = normalize_text(next_word);
+                        let next_is_node = fst.node_cache.iter().any(|e| names_visible_node(e.value(), &next_norm, hidden));
+                        match classify_dimension_mention(prev_word, next_word, next_is_node) {
+                            DimensionMention::GroupBy => {
+                                debug!("识别到分组维度: {}", n.label);
+                                group_candidates.push(n.clone());
                             }
+                            DimensionMention::Value(v) => {
+                                debug!("基于上下文捕获动态值: {} -> {}", n.label, v);
+                                raw_candidates.push((n.clone(), v.to_string()));
+                            }
+                            DimensionMention::Ignored => {}
                         }
                     }
                 }
             }
 
-            // B. A-Box 匹配 (在码值实例库中精准搜索)
+            // B. A-Box 匹配 (在码值实例库中按原文或归一化形式搜索)
+            let norm = normalize_value(word);
+            if norm.is_empty() {
+                continue;
+            }
             let val_rows = sqlx::query(
-                "SELECT dimension_node_id, value_code FROM dimension_values WHERE value_label = $1",
+                "SELECT dimension_node_id, value_code FROM dimension_values
+                 WHERE (value_label = $1 OR normalized_label = $2) AND is_active
+                 UNION SELECT v.dimension_node_id, v.value_code FROM dimension_value_synonyms s
+                 JOIN dimension_values v ON v.id = s.value_id WHERE (s.synonym = $1 OR s.normalized = $2) AND v.is_active",
             )
             .bind(*word)
+            .bind(&norm)
             .fetch_all(&state.db)
             .await?;
 
             for row in val_rows {
                 let dim_id: Uuid = row.get(0);
                 let code: String = row.get(1);
+                if hidden.contains(&dim_id) {
+                    continue;
+                }
                 if let Some(dn) = fst.node_cache.iter().find(|e| e.value().id == dim_id) {
                     debug!("A-Box 命中实例码值: {} -> {}", dn.value().label, word);
                     raw_candidates.push((dn.value().clone(), code));
@@ -103,22 +191,20 @@ impl SemanticInferenceEngine {
         // 4. 意图锚点确定
         if target_metrics.is_empty() {
             warn!("推理失败：未能在提问中定位到任何业务指标");
-            return Err(anyhow::anyhow!("未识别到指标锚点，请明确提问目标（如：收益、应还）"));
+            return Err(InferenceError::NoMetricAnchor.into());
         }
         let metric = target_metrics[0].clone();
         info!("🎯 锁定指标锚点: {}", metric.label);
 
         // 5. T-Box 语义合规性验证与去重
         // 获取当前指标在本体中关联的所有有效维度 ID
-        let supported_dim_ids: HashSet<Uuid> = sqlx::query!(
-            "SELECT dimension_node_id FROM metric_dimension_rels WHERE metric_node_id = $1",
-            metric.id
-        )
-        .fetch_all(&state.db)
-        .await?
-        .into_iter()
-        .map(|r| r.dimension_node_id)
-        .collect();
+        // 取自内存索引中的节点定义，发布快照模式下与快照保持一致
+        let supported_dim_ids: HashSet<Uuid> = metric
+            .supported_dimension_ids
+            .iter()
+            .copied()
+            .filter(|id| !hidden.contains(id))
+            .collect();
 
         let mut final_filters = Vec::new();
         let mut seen_pairs = HashSet::new();
@@ -151,9 +237,122 @@ impl SemanticInferenceEngine {
             }
         }
 
+        // C. 分组维度：必须是该指标支持的维度，且未被绑定具体值
+        let mut group_by: Vec<FullSemanticNode> = Vec::new();
+        for dim in group_candidates {
+            if supported_dim_ids.contains(&dim.id)
+                && !seen_pairs.iter().any(|(id, _)| id == &dim.id)
+                && !group_by.iter().any(|g| g.id == dim.id)
+            {
+                info!("📊 识别分组维度: {}", dim.label);
+                group_by.push(dim);
+            }
+        }
+
+        // D. 时间序列意图：提到"趋势/每天"但未给出日期时，按指标的日期维度展开
+        if is_time_series(&query) {
+            for dim_id in &supported_dim_ids {
+                if let Some(dim_node) = fst.node_cache.iter().find(|e| e.value().id == *dim_id) {
+                    let n = dim_node.value();
+                    if n.semantic_type == "DATE"
+                        && !seen_pairs.iter().any(|(id, _)| id == &n.id)
+                        && !group_by.iter().any(|g| g.id == n.id)
+                    {
+                        info!("📈 基于时间序列意图：按日期维度 '{}' 展开", n.label);
+                        group_by.push(n.clone());
+                    }
+                }
+            }
+        }
+
         Ok(InferenceResult {
             metric,
             filters: final_filters,
+            group_by,
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_dimension_mention_table() {
+        let cases = [
+            ("按", "统计", false, DimensionMention::GroupBy),
+            ("各", "收益", true, DimensionMention::GroupBy),
+            ("每个", "的", false, DimensionMention::GroupBy),
+            ("分", "看", false, DimensionMention::GroupBy),
+            ("", "", false, DimensionMention::GroupBy),
+            ("", "收益", true, DimensionMention::GroupBy),
+            ("", "的", false, DimensionMention::GroupBy),
+            ("", "分布", false, DimensionMention::GroupBy),
+            ("", "占比", false, DimensionMention::GroupBy),
+            ("", "A公司", false, DimensionMention::Value("A公司")),
+            ("查询", "腾讯", false, DimensionMention::Value("腾讯")),
+            ("", "是", false, DimensionMention::Ignored),
+            ("", "为", false, DimensionMention::Ignored),
+            ("", "x", false, DimensionMention::Ignored),
+        ];
+        for (prev, next, next_is_node, expected) in cases {
+            assert_eq!(classify_dimension_mention(prev, next, next_is_node), expected, "prev={:?} next={:?}", prev, next);
+        }
+    }
+
+    /// 按引擎分词后定位维度名，模拟 infer 中的前后词判断
+    fn mention_of<'a>(words: &[&'a str], dim: &str, nodes: &[&str]) -> DimensionMention<'a> {
+        let idx = words.iter().position(|w| *w == dim).unwrap_or_else(|| panic!("{} 未被切分为独立词: {:?}", dim, words));
+        let prev = if idx > 0 { words[idx - 1].trim() } else { "" };
+        let next = words.get(idx + 1).map(|w| w.trim()).unwrap_or("");
+        classify_dimension_mention(prev, next, nodes.contains(&next))
+    }
+
+    #[test]
+    fn group_by_parsing_after_segmentation() {
+        let mut engine = SemanticInferenceEngine::new();
+        engine.refresh_custom_words(vec!["平台".to_string(), "收益".to_string(), "A公司".to_string()]);
+        let nodes = ["平台", "收益"];
+        let cases = [
+            ("按平台统计收益", DimensionMention::GroupBy),
+            ("各平台收益", DimensionMention::GroupBy),
+            ("平台的收益", DimensionMention::GroupBy),
+            ("收益的平台分布", DimensionMention::GroupBy),
+            ("查询收益按平台", DimensionMention::GroupBy),
+            ("平台A公司的收益", DimensionMention::Value("A公司")),
+        ];
+        for (query, expected) in cases {
+            let words = engine.jieba.cut(query, false);
+            assert_eq!(mention_of(&words, "平台", &nodes), expected, "{}: {:?}", query, words);
+        }
+    }
+
+    #[test]
+    fn hidden_nodes_do_not_count_as_following_node() {
+        let secret = FullSemanticNode {
+            alias_names: vec!["机密".to_string()],
+            ..FullSemanticNode::stub("secret", "机密收益", "METRIC", "NUMBER")
+        };
+        let revenue = FullSemanticNode::stub("revenue", "收益", "METRIC", "NUMBER");
+        let none = HashSet::new();
+        assert!(names_visible_node(&secret, "机密", &none));
+        assert!(names_visible_node(&secret, "机密收益", &none));
+        assert!(!names_visible_node(&revenue, "机密", &none));
+
+        let hidden = HashSet::from([secret.id]);
+        assert!(!names_visible_node(&secret, "机密", &hidden));
+        assert!(!names_visible_node(&secret, "机密收益", &hidden));
+        assert!(names_visible_node(&revenue, "收益", &hidden));
+        // 紧随维度名的隐藏指标名按普通词处理，与不存在的词无从区分
+        assert_eq!(classify_dimension_mention("", "机密", names_visible_node(&secret, "机密", &hidden)), DimensionMention::Value("机密"));
+    }
+
+    #[test]
+    fn time_series_keywords() {
+        for q in ["收益趋势", "最近收益走势", "每天的收益", "每日收益", "逐日收益", "按日统计收益"] {
+            assert!(is_time_series(q), "{}", q);
+        }
+        for q in ["收益", "2025-12-24 的收益", "各平台收益"] {
+            assert!(!is_time_series(q), "{}", q);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::ax_state::AppState;
+use crate::core::inference::InferenceResult;
+use crate::core::normalize::normalize_value;
+use crate::core::planner;
+use crate::infra::llm::LlmProvider;
+use crate::models::schema::{CatalogEntry, FullSemanticNode, LogicalPlanDraft, QueryLogicalPlan};
+use regex::Regex;
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+/// LLM 允许声明的聚合方式
+const ALLOWED_AGGS: [&str; 6] = ["SUM", "AVG", "COUNT", "MAX", "MIN", "NONE"];
+
+/// 规则推理失败时的 LLM 兜底
+/// 流程：构建本体目录 -> LLM 生成计划草案 -> T-Box / A-Box 校验 -> 逻辑计划
+/// 调用方不得在持有推理引擎锁时调用（LLM 请求可能长达数十秒）
+#[instrument(skip(state, llm, hidden), fields(provider = %llm.name()))]
+pub async fn plan_with_llm(
+    state: &AppState,
+    llm: &dyn LlmProvider,
+    question: &str,
+    hidden: &HashSet<Uuid>,
+) -> anyhow::Result<QueryLogicalPlan> {
+    // 调用方不可见的节点不进入目录，草案引用时校验失败
+    let mut nodes = load_nodes(state).await;
+    nodes.retain(|n| !hidden.contains(&n.id));
+    let rels = load_dimension_rels(state).await?;
+    plan_over_nodes(&state.db, llm, &nodes, &rels, question).await
+}
+
+/// 基于给定节点集合向 LLM 规划并校验草案
+pub async fn plan_over_nodes(
+    db: &PgPool,
+    llm: &dyn LlmProvider,
+    nodes: &[FullSemanticNode],
+    rels: &HashMap<Uuid, HashSet<Uuid>>,
+    question: &str,
+) -> anyhow::Result<QueryLogicalPlan> {
+    let catalog = build_catalog(nodes, rels);
+
+    info!("🤖 规则推理未命中，转交 LLM 规划 (目录节点数: {})", catalog.len());
+    let draft = llm.plan(&catalog, question).await?;
+    info!("🤖 LLM 计划草案: {:?}", draft);
+
+    resolve_draft(db, nodes, rels, question, &draft).await
+}
+
+/// 校验草案并构建逻辑计划（草案显式声明的聚合方式优先）
+pub async fn resolve_draft(
+    db: &PgPool,
+    nodes: &[FullSemanticNode],
+    rels: &HashMap<Uuid, HashSet<Uuid>>,
+    question: &str,
+    draft: &LogicalPlanDraft,
+) -> anyhow::Result<QueryLogicalPlan> {
+    let inference = validate_draft(db, nodes, rels, draft).await?;
+    let mut plan = planner::build_plan(question, inference);
+    if let Some(agg) = draft.agg.as_deref().map(|a| a.to_uppercase()) {
+        plan.final_agg = agg;
+    }
+    Ok(plan)
+}
+
+/// 从内存语义索引中取出全部节点
+pub async fn load_nodes(state: &AppState) -> Vec<FullSemanticNode> {
+    let fst = state.fst.read().await;
+    fst.node_cache.iter().map(|e| e.value().clone()).collect()
+}
+
+/// 读取全量 T-Box 指标-维度关联（取自内存索引，与推理引擎服务的本体版本一致）
+pub async fn load_dimension_rels(state: &AppState) -> anyhow::Result<HashMap<Uuid, HashSet<Uuid>>> {
+    let fst = state.fst.read().await;
+    Ok(fst
+        .node_cache
+        .iter()
+        .filter(|e| !e.value().supported_dimension_ids.is_empty())
+        .map(|e| (e.value().id, e.value().supported_dimension_ids.iter().copied().collect()))
+        .collect())
+}
+
+/// 构建提供给 LLM 的本体目录（仅语义信息，不暴露物理表与表达式）
+pub fn build_catalog(nodes: &[FullSemanticNode], rels: &HashMap<Uuid, HashSet<Uuid>>) -> Vec<CatalogEntry> {
+    let id_to_key: HashMap<Uuid, &str> = nodes.iter().map(|n| (n.id, n.node_key.as_str())).collect();
+    let mut catalog: Vec<CatalogEntry> = nodes
+        .iter()
+        .map(|n| {
+            let mut supported_dimensions: Vec<String> = rels
+                .get(&n.id)
+                .map(|dims| dims.iter().filter_map(|d| id_to_key.get(d).map(|k| k.to_string())).collect())
+                .unwrap_or_default();
+            supported_dimensions.sort();
+            CatalogEntry {
+                node_key: n.node_key.clone(),
+                label: n.label.clone(),
+                node_role: n.node_role.clone(),
+                semantic_type: n.semantic_type.clone(),
+                alias_names: n.alias_names.clone(),
+                supported_dimensions,
+            }
+        })
+        .collect();
+    catalog.sort_by(|a, b| a.node_key.cmp(&b.node_key));
+    catalog
+}
+
+/// 校验 LLM 计划草案并解析为推理结果
+/// 指标必须存在；维度必须在 metric_dimension_rels 中；非日期维度值必须命中 A-Box，日期值必须为 YYYY-MM-DD
+pub async fn validate_draft(
+    db: &PgPool,
+    nodes: &[FullSemanticNode],
+    rels: &HashMap<Uuid, HashSet<Uuid>>,
+    draft: &LogicalPlanDraft,
+) -> anyhow::Result<InferenceResult> {
+    let by_key: HashMap<&str, &FullSemanticNode> = nodes.iter().map(|n| (n.node_key.as_str(), n)).collect();
+
+    // 1. 指标锚点
+    let metric = by_key
+        .get(draft.metric.as_str())
+        .filter(|n| n.node_role == "METRIC")
+        .ok_or_else(|| anyhow::anyhow!("LLM 计划引用了不存在的指标: {}", draft.metric))?;
+    let supported = rels.get(&metric.id).cloned().unwrap_or_default();
+
+    if let Some(agg) = &draft.agg {
+        if !ALLOWED_AGGS.contains(&agg.to_uppercase().as_str()) {
+            return Err(anyhow::anyhow!("LLM 计划包含不支持的聚合方式: {}", agg));
+        }
+    }
+
+    let resolve_dim = |key: &str| -> anyhow::Result<FullSemanticNode> {
+        let dim = by_key
+            .get(key)
+            .filter(|n| n.node_role == "DIMENSION")
+            .ok_or_else(|| anyhow::anyhow!("LLM 计划引用了不存在的维度: {}", key))?;
+        if !supported.contains(&dim.id) {
+            return Err(anyhow::anyhow!("维度 '{}' 不在指标 '{}' 的 T-Box 关联中", dim.label, metric.label));
+        }
+        Ok((*dim).clone())
+    };
+
+    // 2. 过滤条件：逐一校验 T-Box 关联与 A-Box 实例
+    let date_regex = Regex::new(r"^\d{4}-\d{2}-\d{2}$")?;
+    let mut filters = Vec::new();
+    for f in &draft.filters {
+        let dim = resolve_dim(&f.dimension)?;
+        let code = if dim.semantic_type == "DATE" {
+            if !date_regex.is_match(&f.value) {
+                return Err(anyhow::anyhow!("日期维度 '{}' 的值 '{}' 格式非法", dim.label, f.value));
+            }
+            f.value.clone()
+        } else {
+            let row = sqlx::query(
+                "SELECT v.value_code FROM dimension_values v
+                 WHERE v.dimension_node_id = $1 AND v.is_active
+                   AND (v.value_label = $2 OR v.value_code = $2 OR v.normalized_label = $3
+                        OR EXISTS (SELECT 1 FROM dimension_value_synonyms s
+                                   WHERE s.value_id = v.id AND (s.synonym = $2 OR s.normalized = $3)))
+                 ORDER BY (v.value_label = $2 OR v.value_code = $2) DESC
+                 LIMIT 1",
+            )
+            .bind(dim.id)
+            .bind(&f.value)
+            .bind(normalize_value(&f.value))
+            .fetch_optional(db)
+            .await?;
+            match row {
+                Some(r) => r.get::<String, _>(0),
+                None => {
+                    warn!("LLM 给出的维度值未命中 A-Box: {} = '{}'", dim.label, f.value);
+                    return Err(anyhow::anyhow!("维度 '{}' 中不存在值 '{}'", dim.label, f.value));
+                }
+            }
+        };
+        filters.push((dim, code));
+    }
+
+    // 3. 分组维度
+    let mut group_by: Vec<FullSemanticNode> = Vec::new();
+    for key in &draft.group_by {
+        let dim = resolve_dim(key)?;
+        if !filters.iter().any(|(d, _)| d.id == dim.id) && !group_by.iter().any(|g| g.id == dim.id) {
+            group_by.push(dim);
+        }
+    }
+
+    Ok(InferenceResult {
+        metric: (*metric).clone(),
+        filters,
+        group_by,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::llm::MockLlmProvider;
+    use crate::models::schema::PlanFilterDraft;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn node(key: &str, label: &str, role: &str, semantic_type: &str) -> FullSemanticNode {
+        FullSemanticNode { target_table: "t_revenue_data".to_string(), ..FullSemanticNode::stub(key, label, role, semantic_type) }
+    }
+
+    /// 收益 (支持 平台 / 日期) + 未关联的 区域
+    fn ontology() -> (Vec<FullSemanticNode>, HashMap<Uuid, HashSet<Uuid>>) {
+        let mut revenue = node("revenue", "收益", "METRIC", "NUMBER");
+        let platform = node("platform", "平台", "DIMENSION", "STRING");
+        let date = node("report_date", "日期", "DIMENSION", "DATE");
+        let region = node("region", "区域", "DIMENSION", "STRING");
+        revenue.supported_dimension_ids = vec![platform.id, date.id];
+        let rels = HashMap::from([(revenue.id, HashSet::from([platform.id, date.id]))]);
+        (vec![revenue, platform, date, region], rels)
+    }
+
+    /// 校验路径只在非日期维度取值时访问库，测试用例不触发连接
+    fn lazy_db() -> PgPool {
+        PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap()
+    }
+
+    fn draft(metric: &str, filters: &[(&str, &str)], group_by: &[&str], agg: Option<&str>) -> LogicalPlanDraft {
+        LogicalPlanDraft {
+            metric: metric.to_string(),
+            filters: filters
+                .iter()
+                .map(|(d, v)| PlanFilterDraft { dimension: d.to_string(), value: v.to_string() })
+                .collect(),
+            group_by: group_by.iter().map(|g| g.to_string()).collect(),
+            agg: agg.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_plan_binds_date_and_group_by() {
+        let (nodes, rels) = ontology();
+        let plan = plan_over_nodes(&lazy_db(), &MockLlmProvider, &nodes, &rels, "2024-01-01 各平台的收益")
+            .await
+            .unwrap();
+        assert_eq!(plan.metric.node_key, "revenue");
+        assert_eq!(plan.final_agg, "SUM");
+        let filters: Vec<_> = plan.dimensions.iter().map(|(d, v)| (d.node_key.as_str(), v.as_str())).collect();
+        assert_eq!(filters, vec![("report_date", "2024-01-01")]);
+        let groups: Vec<_> = plan.group_by.iter().map(|d| d.node_key.as_str()).collect();
+        assert_eq!(groups, vec!["platform"]);
+    }
+
+    #[tokio::test]
+    async fn mock_plan_ignores_unsupported_dimension() {
+        let (nodes, rels) = ontology();
+        let plan = plan_over_nodes(&lazy_db(), &MockLlmProvider, &nodes, &rels, "各区域收益").await.unwrap();
+        assert!(plan.group_by.is_empty());
+    }
+
+    #[tokio::test]
+    async fn mock_plan_fails_without_visible_metric() {
+        let (mut nodes, rels) = ontology();
+        nodes.retain(|n| n.node_role != "METRIC");
+        assert!(plan_over_nodes(&lazy_db(), &MockLlmProvider, &nodes, &rels, "收益").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn draft_agg_overrides_default() {
+        let (nodes, rels) = ontology();
+        let d = draft("revenue", &[], &["platform"], Some("avg"));
+        let plan = resolve_draft(&lazy_db(), &nodes, &rels, "收益", &d).await.unwrap();
+        assert_eq!(plan.final_agg, "AVG");
+    }
+
+    #[tokio::test]
+    async fn invalid_drafts_are_rejected() {
+        let (nodes, rels) = ontology();
+        let db = lazy_db();
+        let cases = [
+            ("unknown metric", draft("profit", &[], &[], None)),
+            ("dimension as metric", draft("platform", &[], &[], None)),
+            ("unsupported agg", draft("revenue", &[], &[], Some("MEDIAN"))),
+            ("unknown dimension", draft("revenue", &[], &["channel"], None)),
+            ("dimension outside T-Box", draft("revenue", &[], &["region"], None)),
+            ("malformed date", draft("revenue", &[("report_date", "2024/01/01")], &[], None)),
+        ];
+        for (name, d) in cases {
+            assert!(validate_draft(&db, &nodes, &rels, &d).await.is_err(), "{} should be rejected", name);
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod fst_engine;
-pub mod inference;
\ No newline at end of file
+pub mod inference;
+pub mod llm_fallback;
+pub mod normalize;
+pub mod planner;
\ No newline at end of file
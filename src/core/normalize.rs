@@ -0,0 +1,99 @@
+use std::env;
+use std::sync::OnceLock;
+
+/// 默认剥离的组织名后缀（按长度从长到短匹配，只剥离一次）
+const DEFAULT_STRIP_SUFFIXES: [&str; 4] = ["股份有限公司", "有限责任公司", "有限公司", "公司"];
+
+/// 剥离后缀列表：SSE_VALUE_STRIP_SUFFIXES 以逗号分隔覆盖默认值
+fn strip_suffixes() -> &'static [String] {
+    static SUFFIXES: OnceLock<Vec<String>> = OnceLock::new();
+    SUFFIXES.get_or_init(|| {
+        let mut list: Vec<String> = match env::var("SSE_VALUE_STRIP_SUFFIXES") {
+            Ok(v) => v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+            Err(_) => DEFAULT_STRIP_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+        };
+        list.sort_by_key(|s| std::cmp::Reverse(s.chars().count()));
+        list
+    })
+}
+
+/// 全角转半角（全角 ASCII 区段 U+FF01..U+FF5E 与全角空格）
+pub fn to_half_width(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// 文本归一化：全角转半角 + 小写 + 去首尾空白（用于节点名与提问词的比对）
+pub fn normalize_text(s: &str) -> String {
+    to_half_width(s).trim().to_lowercase()
+}
+
+/// 码值归一化：在文本归一化基础上剥离组织名后缀（如 "阿里巴巴有限公司" -> "阿里巴巴"）
+/// 剥离后为空时保留原文本，避免 "公司" 之类的值被归一为空串
+pub fn normalize_value(s: &str) -> String {
+    let text = normalize_text(s);
+    strip_suffixes()
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix.as_str()))
+        .map(|stem| stem.trim_end().to_string())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_width_conversion() {
+        let cases = [
+            ("ＡＢＣ１２３", "ABC123"),
+            ("ａｂｃ", "abc"),
+            ("（Ａ股）", "(A股)"),
+            ("腾讯\u{3000}音乐", "腾讯 音乐"),
+            ("！～", "!~"),
+            ("中文不变", "中文不变"),
+            ("", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(to_half_width(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn text_normalization() {
+        let cases = [
+            ("ABC", "abc"),
+            ("  Tencent Music ", "tencent music"),
+            ("\u{3000}ＱＱ音乐\u{3000}", "qq音乐"),
+            ("\t收益\n", "收益"),
+            ("   ", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_text(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn value_normalization_strips_one_suffix() {
+        let cases = [
+            ("阿里巴巴有限公司", "阿里巴巴"),
+            ("腾讯科技股份有限公司", "腾讯科技"),
+            ("字节跳动有限责任公司", "字节跳动"),
+            ("ＡＢＣ 公司", "abc"),
+            ("A公司", "a"),
+            ("公司", "公司"),
+            ("有限公司", "有限公司"),
+            ("分公司有限公司", "分公司"),
+            ("网易", "网易"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_value(input), expected, "{}", input);
+        }
+    }
+}
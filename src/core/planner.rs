@@ -0,0 +1,136 @@
+use crate::core::inference::InferenceResult;
+use crate::infra::connector::SqlDialect;
+use crate::models::schema::QueryLogicalPlan;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 由推理结果构建逻辑查询计划
+/// 负责确定聚合方式，并把指标与维度上的隐含业务约束展开为谓词
+pub fn build_plan(question: &str, inference: InferenceResult) -> QueryLogicalPlan {
+    let metric = inference.metric;
+
+    // 1. 确定聚合逻辑
+    let final_agg = if question.contains("平均") {
+        "AVG".to_string()
+    } else {
+        metric.default_agg.clone()
+    };
+
+    // 2. 收集业务隐含约束（指标自身 + 已绑定维度），取值待编译时按方言转义
+    let mut implicit_constraints = metric.default_constraints.0.clone();
+    for (dim_node, _) in &inference.filters {
+        implicit_constraints.extend(dim_node.default_constraints.0.iter().cloned());
+    }
+
+    QueryLogicalPlan {
+        dataset_context: metric.dataset_id,
+        metric,
+        dimensions: inference.filters,
+        group_by: inference.group_by,
+        implicit_constraints,
+        security_filters: Vec::new(),
+        final_agg,
+    }
+}
+
+/// 将逻辑计划编译为目标方言的物理 SQL
+pub fn compile_sql(plan: &QueryLogicalPlan, dialect: SqlDialect) -> String {
+    let metric = &plan.metric;
+    let agg = plan.final_agg.as_str();
+
+    // 1. 构造 SELECT 子句
+    let metric_item = if agg == "NONE" {
+        format!("{} as {}", metric.sql_expression, dialect.quote_ident(&metric.label))
+    } else {
+        format!("{}({}) as {}", agg, metric.sql_expression, dialect.quote_ident(&metric.label))
+    };
+
+    // 2. 组装 SQL 片段
+    let mut select_items = vec![metric_item];
+    let mut where_conds = vec!["1=1".to_string()];
+    let mut group_by_items = Vec::new();
+    let mut order_by_items = Vec::new();
+
+    for (dim_node, val_code) in &plan.dimensions {
+        where_conds.push(format!("{} = {}", dim_node.sql_expression, dialect.quote_literal(val_code)));
+        select_items.insert(
+            0,
+            format!("{} as {}", dim_node.sql_expression, dialect.quote_ident(&dim_node.label)),
+        );
+        if agg != "NONE" {
+            group_by_items.push(dim_node.sql_expression.clone());
+        }
+    }
+
+    // 3. 分组维度：日期维度按时间升序输出，便于绘制序列
+    for dim_node in &plan.group_by {
+        select_items.insert(
+            0,
+            format!("{} as {}", dim_node.sql_expression, dialect.quote_ident(&dim_node.label)),
+        );
+        if agg != "NONE" {
+            group_by_items.push(dim_node.sql_expression.clone());
+        }
+        if dim_node.semantic_type == "DATE" {
+            order_by_items.push(dim_node.sql_expression.clone());
+        }
+    }
+
+    where_conds.extend(
+        plan.implicit_constraints
+            .iter()
+            .map(|c| format!("{} {} {}", c.column, c.operator, dialect.quote_literal(&c.value))),
+    );
+    where_conds.extend(plan.security_filters.iter().cloned());
+
+    // 4. 拼装物理 SQL
+    let mut sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        select_items.join(", "),
+        metric.target_table,
+        where_conds.join(" AND ")
+    );
+
+    if !group_by_items.is_empty() {
+        sql.push_str(&format!(" GROUP BY {}", group_by_items.join(", ")));
+    }
+    if !order_by_items.is_empty() {
+        sql.push_str(&format!(" ORDER BY {}", order_by_items.join(", ")));
+    }
+    sql
+}
+
+/// 逻辑计划的规范形式：只保留语义标识，排序后可稳定比较
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CanonicalPlan {
+    pub metric: String,
+    pub filters: Vec<(String, String)>, // (维度 node_key, 码值)
+    pub group_by: Vec<String>,
+    pub agg: String,
+}
+
+/// 规范化逻辑计划：节点以 node_key 表示，过滤与分组按字典序排列
+pub fn canonicalize(plan: &QueryLogicalPlan) -> CanonicalPlan {
+    let mut filters: Vec<(String, String)> = plan
+        .dimensions
+        .iter()
+        .map(|(d, v)| (d.node_key.clone(), v.clone()))
+        .collect();
+    filters.sort();
+    filters.dedup();
+    let mut group_by: Vec<String> = plan.group_by.iter().map(|d| d.node_key.clone()).collect();
+    group_by.sort();
+    group_by.dedup();
+    CanonicalPlan {
+        metric: plan.metric.node_key.clone(),
+        filters,
+        group_by,
+        agg: plan.final_agg.to_uppercase(),
+    }
+}
+
+/// 规范计划的 SHA-256 指纹
+pub fn plan_hash(plan: &CanonicalPlan) -> String {
+    let bytes = serde_json::to_vec(plan).unwrap_or_default();
+    hex::encode(Sha256::digest(bytes))
+}
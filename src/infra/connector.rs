@@ -0,0 +1,724 @@
+use crate::core::planner;
+use crate::infra::db_internal::{mysql_row_to_json, pg_row_to_json, sqlite_row_to_json};
+use crate::models::schema::{ColumnMetadata, ForeignKeyRef, QueryLogicalPlan, TableMetadata};
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{MySql, Pool, Postgres, Row, Sqlite};
+use std::time::Instant;
+
+/// SQL 方言差异
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    /// 按数据源类型确定方言（无需建立连接）
+    pub fn from_db_type(db_type: &str) -> Option<Self> {
+        match db_type.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(SqlDialect::Postgres),
+            "mysql" => Some(SqlDialect::MySql),
+            "sqlite" => Some(SqlDialect::Sqlite),
+            _ => None,
+        }
+    }
+
+    /// 将表达式转为文本（用于 A-Box 码值同步）
+    pub fn cast_text(&self, expr: &str) -> String {
+        match self {
+            SqlDialect::Postgres => format!("({}) :: text", expr),
+            SqlDialect::MySql => format!("CAST(({}) AS CHAR)", expr),
+            SqlDialect::Sqlite => format!("CAST(({}) AS TEXT)", expr),
+        }
+    }
+
+    /// 维度码值的去重查询（结果列 val 为码值文本；给出标签表达式时另有 label 列）
+    pub fn distinct_values_sql(&self, expr: &str, label_expr: Option<&str>, table: &str) -> String {
+        match label_expr {
+            Some(label) => format!(
+                "SELECT DISTINCT {} AS val, {} AS label FROM {}",
+                self.cast_text(expr),
+                self.cast_text(label),
+                table
+            ),
+            None => format!("SELECT DISTINCT {} AS val FROM {}", self.cast_text(expr), table),
+        }
+    }
+
+    /// 引用标识符（列别名等）
+    pub fn quote_ident(&self, ident: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", ident.replace('`', "``")),
+            _ => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// 字符串字面量：单引号一律双写；MySQL 默认把反斜杠当转义符，需先转义反斜杠
+    pub fn quote_literal(&self, value: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''")),
+            _ => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    /// 只编译不执行的执行计划查询（发布前校验 SQL 表达式）
+    pub fn explain(&self, sql: &str) -> String {
+        match self {
+            SqlDialect::Sqlite => format!("EXPLAIN QUERY PLAN {}", sql),
+            _ => format!("EXPLAIN {}", sql),
+        }
+    }
+}
+
+/// 连接池运行状态
+#[derive(Debug, Serialize)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+/// 连通性探测结果
+#[derive(Debug, Serialize)]
+pub struct PingResult {
+    pub ok: bool,
+    pub latency_ms: u128,
+    pub error: Option<String>,
+}
+
+/// 外部数据源连接器：新增后端只需实现该 trait 并在 PoolManager::connect 中登记
+/// 查询结果统一为 JSON 对象（列名 -> 值）
+#[async_trait]
+pub trait DataSourceConnector: Send + Sync {
+    fn dialect(&self) -> SqlDialect;
+
+    /// 执行只读查询，返回全部结果行
+    async fn query(&self, sql: &str) -> anyhow::Result<Vec<Value>>;
+
+    /// 逐行流式读取（大结果集同步时避免一次性载入内存）
+    fn stream_rows<'a>(&'a self, sql: &'a str) -> BoxStream<'a, anyhow::Result<Value>>;
+
+    async fn list_schemas(&self) -> anyhow::Result<Vec<String>>;
+
+    /// 列出表与视图，schema 为空时使用连接的默认 schema
+    async fn list_tables(&self, schema: Option<&str>) -> anyhow::Result<Vec<String>>;
+
+    async fn list_columns(&self, schema: Option<&str>, table: &str) -> anyhow::Result<Vec<String>>;
+
+    /// 探查单表的列类型、可空性、主外键、注释与估算行数
+    async fn describe_table(&self, schema: Option<&str>, table: &str) -> anyhow::Result<TableMetadata>;
+
+    fn stats(&self) -> PoolStats;
+
+    async fn close(&self);
+
+    /// 按本方言编译并执行逻辑计划，返回实际执行的 SQL 与结果
+    async fn execute_plan(&self, plan: &QueryLogicalPlan) -> (String, anyhow::Result<Vec<Value>>) {
+        let sql = planner::compile_sql(plan, self.dialect());
+        let rows = self.query(&sql).await;
+        (sql, rows)
+    }
+
+    /// 通过 EXPLAIN 让数据源编译 SQL（不读取数据），返回编译错误
+    async fn explain(&self, sql: &str) -> anyhow::Result<()> {
+        self.query(&self.dialect().explain(sql)).await.map(|_| ())
+    }
+
+    /// 执行 SELECT 1 探测连通性
+    async fn ping(&self) -> PingResult {
+        let start = Instant::now();
+        let res = self.query("SELECT 1").await;
+        PingResult {
+            ok: res.is_ok(),
+            latency_ms: start.elapsed().as_millis(),
+            error: res.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// 按物理类型推断节点语义类型（STRING / NUMBER / DATE）
+pub fn semantic_type_hint(data_type: &str) -> &'static str {
+    let t = data_type.to_lowercase();
+    if ["date", "time", "year"].iter().any(|k| t.contains(k)) {
+        "DATE"
+    } else if ["int", "numeric", "decimal", "real", "double", "float", "money", "number"]
+        .iter()
+        .any(|k| t.contains(k))
+    {
+        "NUMBER"
+    } else {
+        "STRING"
+    }
+}
+
+/// 汇总列信息为表元数据；查不到列视为表不存在
+fn table_metadata(
+    schema: String,
+    table: &str,
+    comment: Option<String>,
+    approx_row_count: Option<i64>,
+    columns: Vec<ColumnMetadata>,
+) -> anyhow::Result<TableMetadata> {
+    if columns.is_empty() {
+        anyhow::bail!("表 {}.{} 不存在或没有可访问的列", schema, table);
+    }
+    Ok(TableMetadata {
+        primary_key: columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect(),
+        schema,
+        table_name: table.to_string(),
+        comment: comment.filter(|c| !c.is_empty()),
+        approx_row_count: approx_row_count.filter(|n| *n >= 0),
+        columns,
+    })
+}
+
+fn column_metadata(
+    name: String,
+    data_type: String,
+    nullable: bool,
+    is_primary_key: bool,
+    foreign_key: Option<ForeignKeyRef>,
+    comment: Option<String>,
+) -> ColumnMetadata {
+    ColumnMetadata {
+        suggested_semantic_type: semantic_type_hint(&data_type).to_string(),
+        name,
+        data_type,
+        nullable,
+        is_primary_key,
+        foreign_key,
+        comment: comment.filter(|c| !c.is_empty()),
+    }
+}
+
+fn first_column<R: Row>(rows: Vec<R>) -> Vec<String>
+where
+    for<'r> String: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    usize: sqlx::ColumnIndex<R>,
+{
+    rows.into_iter().filter_map(|r| r.try_get::<String, _>(0).ok()).collect()
+}
+
+// --- 1. PostgreSQL ---
+
+pub struct PostgresConnector(pub Pool<Postgres>);
+
+#[async_trait]
+impl DataSourceConnector for PostgresConnector {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Postgres
+    }
+
+    async fn query(&self, sql: &str) -> anyhow::Result<Vec<Value>> {
+        let rows = sqlx::query(sql).fetch_all(&self.0).await?;
+        Ok(rows.iter().map(pg_row_to_json).collect())
+    }
+
+    fn stream_rows<'a>(&'a self, sql: &'a str) -> BoxStream<'a, anyhow::Result<Value>> {
+        sqlx::query(sql)
+            .fetch(&self.0)
+            .map(|r| r.map(|row| pg_row_to_json(&row)).map_err(Into::into))
+            .boxed()
+    }
+
+    async fn list_schemas(&self) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT schema_name FROM information_schema.schemata
+             WHERE schema_name NOT IN ('pg_catalog', 'information_schema') AND schema_name NOT LIKE 'pg_toast%'
+             ORDER BY schema_name",
+        )
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_tables(&self, schema: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT table_name FROM information_schema.tables WHERE table_schema = COALESCE($1, current_schema()) ORDER BY table_name",
+        )
+        .bind(schema)
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_columns(&self, schema: Option<&str>, table: &str) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT column_name FROM information_schema.columns
+             WHERE table_schema = COALESCE($1, current_schema()) AND table_name = $2 ORDER BY ordinal_position",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    /// 基于系统目录探查：注释取自 COMMENT ON，行数取自 pg_class.reltuples（ANALYZE 后更新）
+    async fn describe_table(&self, schema: Option<&str>, table: &str) -> anyhow::Result<TableMetadata> {
+        let head = sqlx::query(
+            "SELECT n.nspname::text, obj_description(c.oid, 'pg_class'), c.reltuples::bigint
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE n.nspname = COALESCE($1, current_schema()) AND c.relname = $2
+               AND c.relkind IN ('r', 'p', 'v', 'm', 'f')",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(&self.0)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("表 {} 不存在", table))?;
+        let schema_name: String = head.try_get(0)?;
+
+        let rows = sqlx::query(
+            "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod), NOT a.attnotnull,
+                    EXISTS (SELECT 1 FROM pg_index i WHERE i.indrelid = c.oid AND i.indisprimary AND a.attnum = ANY(i.indkey)),
+                    col_description(c.oid, a.attnum), fk.ref_schema, fk.ref_table, fk.ref_column
+             FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+             LEFT JOIN LATERAL (
+                 SELECT fn.nspname::text AS ref_schema, fc.relname::text AS ref_table, fa.attname::text AS ref_column
+                 FROM pg_constraint con
+                 CROSS JOIN LATERAL unnest(con.conkey, con.confkey) AS k(src, dst)
+                 JOIN pg_class fc ON fc.oid = con.confrelid
+                 JOIN pg_namespace fn ON fn.oid = fc.relnamespace
+                 JOIN pg_attribute fa ON fa.attrelid = con.confrelid AND fa.attnum = k.dst
+                 WHERE con.conrelid = c.oid AND con.contype = 'f' AND k.src = a.attnum
+                 LIMIT 1
+             ) fk ON true
+             WHERE n.nspname = $1 AND c.relname = $2
+             ORDER BY a.attnum",
+        )
+        .bind(&schema_name)
+        .bind(table)
+        .fetch_all(&self.0)
+        .await?;
+
+        let mut columns = Vec::with_capacity(rows.len());
+        for r in &rows {
+            let ref_table: Option<String> = r.try_get(6)?;
+            columns.push(column_metadata(
+                r.try_get(0)?,
+                r.try_get(1)?,
+                r.try_get(2)?,
+                r.try_get(3)?,
+                ref_table.map(|t| ForeignKeyRef {
+                    schema: r.try_get(5).ok(),
+                    table: t,
+                    column: r.try_get(7).ok(),
+                }),
+                r.try_get(4)?,
+            ));
+        }
+        table_metadata(schema_name, table, head.try_get(1)?, head.try_get(2)?, columns)
+    }
+
+    fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.0.size(),
+            idle: self.0.num_idle(),
+            max_connections: self.0.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.0.close().await
+    }
+}
+
+// --- 2. MySQL ---
+
+pub struct MySqlConnector(pub Pool<MySql>);
+
+#[async_trait]
+impl DataSourceConnector for MySqlConnector {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::MySql
+    }
+
+    async fn query(&self, sql: &str) -> anyhow::Result<Vec<Value>> {
+        let rows = sqlx::query(sql).fetch_all(&self.0).await?;
+        Ok(rows.iter().map(mysql_row_to_json).collect())
+    }
+
+    fn stream_rows<'a>(&'a self, sql: &'a str) -> BoxStream<'a, anyhow::Result<Value>> {
+        sqlx::query(sql)
+            .fetch(&self.0)
+            .map(|r| r.map(|row| mysql_row_to_json(&row)).map_err(Into::into))
+            .boxed()
+    }
+
+    async fn list_schemas(&self) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT CAST(schema_name AS CHAR) FROM information_schema.schemata
+             WHERE schema_name NOT IN ('mysql', 'information_schema', 'performance_schema', 'sys')
+             ORDER BY schema_name",
+        )
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_tables(&self, schema: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT CAST(table_name AS CHAR) FROM information_schema.tables
+             WHERE table_schema = COALESCE(?, DATABASE()) ORDER BY table_name",
+        )
+        .bind(schema)
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_columns(&self, schema: Option<&str>, table: &str) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT CAST(column_name AS CHAR) FROM information_schema.columns
+             WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ? ORDER BY ordinal_position",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.0)
+        .await?;
+        Ok(first_column(rows))
+    }
+
+    /// 基于 information_schema 探查：InnoDB 的 TABLE_ROWS 为估算值
+    async fn describe_table(&self, schema: Option<&str>, table: &str) -> anyhow::Result<TableMetadata> {
+        let head = sqlx::query(
+            "SELECT CAST(TABLE_SCHEMA AS CHAR), CAST(TABLE_COMMENT AS CHAR), CAST(TABLE_ROWS AS SIGNED)
+             FROM information_schema.TABLES WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(&self.0)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("表 {} 不存在", table))?;
+        let schema_name: String = head.try_get(0)?;
+
+        let rows = sqlx::query(
+            "SELECT CAST(c.COLUMN_NAME AS CHAR), CAST(c.COLUMN_TYPE AS CHAR),
+                    CAST(c.IS_NULLABLE = 'YES' AS SIGNED), CAST(c.COLUMN_KEY = 'PRI' AS SIGNED),
+                    CAST(c.COLUMN_COMMENT AS CHAR), CAST(k.REFERENCED_TABLE_SCHEMA AS CHAR),
+                    CAST(k.REFERENCED_TABLE_NAME AS CHAR), CAST(k.REFERENCED_COLUMN_NAME AS CHAR)
+             FROM information_schema.COLUMNS c
+             LEFT JOIN information_schema.KEY_COLUMN_USAGE k
+               ON k.TABLE_SCHEMA = c.TABLE_SCHEMA AND k.TABLE_NAME = c.TABLE_NAME
+              AND k.COLUMN_NAME = c.COLUMN_NAME AND k.REFERENCED_TABLE_NAME IS NOT NULL
+             WHERE c.TABLE_SCHEMA = ? AND c.TABLE_NAME = ?
+             ORDER BY c.ORDINAL_POSITION",
+        )
+        .bind(&schema_name)
+        .bind(table)
+        .fetch_all(&self.0)
+        .await?;
+
+        let mut columns: Vec<ColumnMetadata> = Vec::with_capacity(rows.len());
+        for r in &rows {
+            let name: String = r.try_get(0)?;
+            // 同一列参与多个外键时只保留第一个
+            if columns.iter().any(|c| c.name == name) {
+                continue;
+            }
+            let ref_table: Option<String> = r.try_get(6)?;
+            columns.push(column_metadata(
+                name,
+                r.try_get(1)?,
+                r.try_get::<i64, _>(2)? != 0,
+                r.try_get::<i64, _>(3)? != 0,
+                ref_table.map(|t| ForeignKeyRef {
+                    schema: r.try_get(5).ok(),
+                    table: t,
+                    column: r.try_get(7).ok(),
+                }),
+                r.try_get(4)?,
+            ));
+        }
+        table_metadata(schema_name, table, head.try_get(1)?, head.try_get(2)?, columns)
+    }
+
+    fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.0.size(),
+            idle: self.0.num_idle(),
+            max_connections: self.0.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.0.close().await
+    }
+}
+
+// --- 3. SQLite ---
+
+pub struct SqliteConnector(pub Pool<Sqlite>);
+
+#[async_trait]
+impl DataSourceConnector for SqliteConnector {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Sqlite
+    }
+
+    async fn query(&self, sql: &str) -> anyhow::Result<Vec<Value>> {
+        let rows = sqlx::query(sql).fetch_all(&self.0).await?;
+        Ok(rows.iter().map(sqlite_row_to_json).collect())
+    }
+
+    fn stream_rows<'a>(&'a self, sql: &'a str) -> BoxStream<'a, anyhow::Result<Value>> {
+        sqlx::query(sql)
+            .fetch(&self.0)
+            .map(|r| r.map(|row| sqlite_row_to_json(&row)).map_err(Into::into))
+            .boxed()
+    }
+
+    async fn list_schemas(&self) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query("SELECT name FROM pragma_database_list ORDER BY seq")
+            .fetch_all(&self.0)
+            .await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_tables(&self, schema: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let sql = format!(
+            "SELECT name FROM {}.sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            self.dialect().quote_ident(schema.unwrap_or("main"))
+        );
+        let rows = sqlx::query(&sql).fetch_all(&self.0).await?;
+        Ok(first_column(rows))
+    }
+
+    async fn list_columns(&self, schema: Option<&str>, table: &str) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query("SELECT name FROM pragma_table_info(?, ?)")
+            .bind(table)
+            .bind(schema.unwrap_or("main"))
+            .fetch_all(&self.0)
+            .await?;
+        Ok(first_column(rows))
+    }
+
+    /// SQLite 无列注释；行数为精确 COUNT(*)
+    async fn describe_table(&self, schema: Option<&str>, table: &str) -> anyhow::Result<TableMetadata> {
+        let schema_name = schema.unwrap_or("main");
+        let fks = sqlx::query("SELECT \"from\", \"table\", \"to\" FROM pragma_foreign_key_list(?, ?)")
+            .bind(table)
+            .bind(schema_name)
+            .fetch_all(&self.0)
+            .await?;
+        let rows = sqlx::query("SELECT name, type, \"notnull\", pk FROM pragma_table_info(?, ?) ORDER BY cid")
+            .bind(table)
+            .bind(schema_name)
+            .fetch_all(&self.0)
+            .await?;
+
+        let mut columns = Vec::with_capacity(rows.len());
+        for r in &rows {
+            let name: String = r.try_get(0)?;
+            let foreign_key = fks
+                .iter()
+                .find(|fk| fk.try_get::<String, _>(0).is_ok_and(|from| from == name))
+                .map(|fk| ForeignKeyRef {
+                    schema: None,
+                    table: fk.try_get(1).unwrap_or_default(),
+                    column: fk.try_get(2).ok().flatten(),
+                });
+            columns.push(column_metadata(
+                name,
+                r.try_get(1)?,
+                r.try_get::<i64, _>(2)? == 0,
+                r.try_get::<i64, _>(3)? > 0,
+                foreign_key,
+                None,
+            ));
+        }
+        let approx_row_count = if columns.is_empty() {
+            None
+        } else {
+            let sql = format!(
+                "SELECT COUNT(*) FROM {}.{}",
+                self.dialect().quote_ident(schema_name),
+                self.dialect().quote_ident(table)
+            );
+            sqlx::query_scalar(&sql).fetch_one(&self.0).await.ok()
+        };
+        table_metadata(schema_name.to_string(), table, None, approx_row_count, columns)
+    }
+
+    fn stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.0.size(),
+            idle: self.0.num_idle(),
+            max_connections: self.0.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.0.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [SqlDialect; 3] = [SqlDialect::Postgres, SqlDialect::MySql, SqlDialect::Sqlite];
+
+    #[test]
+    fn quote_literal_doubles_single_quotes() {
+        for d in ALL {
+            assert_eq!(d.quote_literal("A公司"), "'A公司'");
+            assert_eq!(d.quote_literal("O'Brien"), "'O''Brien'");
+            assert_eq!(d.quote_literal(""), "''");
+        }
+    }
+
+    #[test]
+    fn quote_literal_escapes_backslash_only_for_mysql() {
+        let attack = "\\' OR 1=1 -- ";
+        assert_eq!(SqlDialect::MySql.quote_literal(attack), "'\\\\'' OR 1=1 -- '");
+        assert_eq!(SqlDialect::MySql.quote_literal("a\\b"), "'a\\\\b'");
+        for d in [SqlDialect::Postgres, SqlDialect::Sqlite] {
+            assert_eq!(d.quote_literal(attack), "'\\'' OR 1=1 -- '");
+            assert_eq!(d.quote_literal("a\\b"), "'a\\b'");
+        }
+    }
+
+    #[test]
+    fn quote_ident_per_dialect() {
+        assert_eq!(SqlDialect::MySql.quote_ident("收益"), "`收益`");
+        assert_eq!(SqlDialect::MySql.quote_ident("a`b"), "`a``b`");
+        for d in [SqlDialect::Postgres, SqlDialect::Sqlite] {
+            assert_eq!(d.quote_ident("收益"), "\"收益\"");
+            assert_eq!(d.quote_ident("a\"b"), "\"a\"\"b\"");
+        }
+    }
+
+    #[test]
+    fn dialect_from_db_type() {
+        assert_eq!(SqlDialect::from_db_type("PostgreSQL"), Some(SqlDialect::Postgres));
+        assert_eq!(SqlDialect::from_db_type("mysql"), Some(SqlDialect::MySql));
+        assert_eq!(SqlDialect::from_db_type("sqlite"), Some(SqlDialect::Sqlite));
+        assert_eq!(SqlDialect::from_db_type("duckdb"), None); // 待原生驱动接入，见 README 3.3
+        assert_eq!(SqlDialect::from_db_type("oracle"), None);
+    }
+
+    // --- SQLite 内存库：连接器端到端 ---
+
+    use crate::core::inference::InferenceResult;
+    use crate::models::schema::{BusinessConstraint, FullSemanticNode};
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    /// 单连接保证所有语句落在同一个内存库
+    async fn sqlite_fixture() -> SqliteConnector {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        for sql in [
+            "CREATE TABLE platforms (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            "CREATE TABLE revenue (
+                id INTEGER PRIMARY KEY,
+                platform_id INTEGER REFERENCES platforms(id),
+                platform_name TEXT,
+                amount REAL NOT NULL,
+                report_date DATE
+            )",
+            "INSERT INTO platforms VALUES (1, 'A'), (2, 'B')",
+            "INSERT INTO revenue VALUES
+                (1, 1, 'A', 100.5, '2024-01-01'), (2, 1, 'A', 50, '2024-01-02'),
+                (3, 2, 'B', 30, '2024-01-01'), (4, 1, 'O''Brien', 7, '2024-01-01')",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        SqliteConnector(pool)
+    }
+
+    fn node(key: &str, role: &str, semantic_type: &str, expr: &str) -> FullSemanticNode {
+        FullSemanticNode {
+            source_id: "lite".to_string(),
+            target_table: "revenue".to_string(),
+            sql_expression: expr.to_string(),
+            ..FullSemanticNode::stub(key, key, role, semantic_type)
+        }
+    }
+
+    fn plan(filters: Vec<(FullSemanticNode, String)>, group_by: Vec<FullSemanticNode>) -> QueryLogicalPlan {
+        let metric = node("revenue", "METRIC", "NUMBER", "amount");
+        planner::build_plan("", InferenceResult { metric, filters, group_by })
+    }
+
+    #[tokio::test]
+    async fn sqlite_execute_plan_filters_and_groups() {
+        let conn = sqlite_fixture().await;
+        let platform = node("platform", "DIMENSION", "STRING", "platform_name");
+        let date = node("report_date", "DIMENSION", "DATE", "report_date");
+
+        let (sql, rows) = conn.execute_plan(&plan(vec![(platform, "A".to_string())], vec![date])).await;
+        assert!(sql.contains("platform_name = 'A'"), "{}", sql);
+        assert_eq!(
+            rows.unwrap(),
+            vec![
+                json!({"report_date": "2024-01-01", "platform": "A", "revenue": 100.5}),
+                json!({"report_date": "2024-01-02", "platform": "A", "revenue": 50.0}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sqlite_execute_plan_quotes_captured_values() {
+        let conn = sqlite_fixture().await;
+        let platform = node("platform", "DIMENSION", "STRING", "platform_name");
+
+        let (_, rows) = conn.execute_plan(&plan(vec![(platform.clone(), "O'Brien".to_string())], Vec::new())).await;
+        assert_eq!(rows.unwrap(), vec![json!({"platform": "O'Brien", "revenue": 7.0})]);
+
+        let (_, rows) = conn.execute_plan(&plan(vec![(platform, "' OR 1=1 --".to_string())], Vec::new())).await;
+        assert!(rows.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn default_constraint_values_are_quoted_per_dialect() {
+        let conn = sqlite_fixture().await;
+        let mut metric = node("revenue", "METRIC", "NUMBER", "amount");
+        metric.default_constraints = sqlx::types::Json(vec![BusinessConstraint {
+            column: "platform_name".to_string(),
+            operator: "=".to_string(),
+            value: "O'Brien".to_string(),
+        }]);
+        let p = planner::build_plan("", InferenceResult { metric: metric.clone(), filters: Vec::new(), group_by: Vec::new() });
+
+        let (sql, rows) = conn.execute_plan(&p).await;
+        assert!(sql.contains("platform_name = 'O''Brien'"), "{}", sql);
+        assert_eq!(rows.unwrap(), vec![json!({"revenue": 7.0})]);
+
+        metric.default_constraints.0[0].value = "a\\' OR 1=1 --".to_string();
+        let p = planner::build_plan("", InferenceResult { metric, filters: Vec::new(), group_by: Vec::new() });
+        let mysql = planner::compile_sql(&p, SqlDialect::MySql);
+        assert!(mysql.ends_with("platform_name = 'a\\\\'' OR 1=1 --'"), "{}", mysql);
+        let postgres = planner::compile_sql(&p, SqlDialect::Postgres);
+        assert!(postgres.ends_with("platform_name = 'a\\'' OR 1=1 --'"), "{}", postgres);
+        let (_, rows) = conn.execute_plan(&p).await;
+        assert_eq!(rows.unwrap(), vec![json!({"revenue": null})]);
+    }
+
+    #[tokio::test]
+    async fn sqlite_describe_table_reports_keys_and_types() {
+        let conn = sqlite_fixture().await;
+        let meta = conn.describe_table(None, "revenue").await.unwrap();
+
+        assert_eq!(meta.schema, "main");
+        assert_eq!(meta.primary_key, vec!["id".to_string()]);
+        assert_eq!(meta.approx_row_count, Some(4));
+        let col = |name: &str| meta.columns.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(col("amount").suggested_semantic_type, "NUMBER");
+        assert!(!col("amount").nullable);
+        assert_eq!(col("report_date").suggested_semantic_type, "DATE");
+        assert_eq!(col("platform_name").suggested_semantic_type, "STRING");
+        let fk = col("platform_id").foreign_key.as_ref().unwrap();
+        assert_eq!((fk.table.as_str(), fk.column.as_deref()), ("platforms", Some("id")));
+
+        assert_eq!(conn.list_tables(None).await.unwrap(), vec!["platforms".to_string(), "revenue".to_string()]);
+        assert!(conn.describe_table(None, "missing").await.is_err());
+    }
+}
@@ -1,74 +1,169 @@
-use sqlx::{Pool, Postgres, MySql, postgres::PgPoolOptions, mysql::MySqlPoolOptions, Row};
-use dashmap::DashMap;
+use sqlx::{postgres::PgPoolOptions, mysql::MySqlPoolOptions, sqlite::SqlitePoolOptions, pool::PoolOptions, Database, Row};
+use dashmap::{mapref::entry::Entry, DashMap};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use crate::models::schema::DataSource;
+use crate::infra::crypto::{mask_connection_url, CredentialCipher};
+use crate::infra::connector::{DataSourceConnector, MySqlConnector, PingResult, PostgresConnector, SqliteConnector};
+use tracing::info;
 
-pub enum DynamicPool {
-    Postgres(Pool<Postgres>),
-    MySql(Pool<MySql>),
+/// 按数据源配置生成连接池参数（未配置时沿用默认 5 连接）
+fn pool_options<DB: Database>(source: &DataSource) -> PoolOptions<DB> {
+    let mut opts = PoolOptions::<DB>::new()
+        .max_connections(source.max_connections.map(|n| n.max(1) as u32).unwrap_or(5))
+        .min_connections(source.min_connections.unwrap_or(0).max(0) as u32)
+        .acquire_timeout(Duration::from_secs(source.acquire_timeout_secs.unwrap_or(30).max(1) as u64));
+    if let Some(secs) = source.idle_timeout_secs {
+        opts = opts.idle_timeout(Duration::from_secs(secs.max(1) as u64));
+    }
+    opts
 }
 
 pub struct PoolManager {
-    pools: DashMap<String, Arc<DynamicPool>>,
+    pools: DashMap<String, Arc<dyn DataSourceConnector>>,
+    cipher: CredentialCipher, // 连接串只在建连时解密
 }
 
 impl PoolManager {
-    pub fn new() -> Self {
+    pub fn new(cipher: CredentialCipher) -> Self {
         Self {
             pools: DashMap::new(),
+            cipher,
         }
     }
 
-    /// 探测外部数据库的所有表名
-        pub async fn list_tables(&self, source: &DataSource) -> anyhow::Result<Vec<String>> {
-        let pool = self.get_or_create_pool(source).await?;
-        match &*pool {
-            DynamicPool::Postgres(p) => {
-                // 使用非宏方式避免 Option 问题，或使用 filter_map
-                let rows = sqlx::query("SELECT tablename FROM pg_catalog.pg_tables WHERE schemaname = 'public'")
-                    .fetch_all(p).await?;
-                Ok(rows.into_iter().filter_map(|r| r.try_get::<String, _>(0).ok()).collect())
-            },
-            DynamicPool::MySql(p) => {
-                let rows = sqlx::query("SHOW TABLES").fetch_all(p).await?;
-                Ok(rows.into_iter().filter_map(|r| r.try_get::<String, _>(0).ok()).collect())
-            }
-        }
+    /// 加密待落库的连接串
+    pub fn seal_url(&self, plain: &str) -> anyhow::Result<String> {
+        self.cipher.seal(plain)
     }
 
-    /// 探测指定表的所有列名
-        pub async fn list_columns(&self, source: &DataSource, table: &str) -> anyhow::Result<Vec<String>> {
-        let pool = self.get_or_create_pool(source).await?;
-        match &*pool {
-            DynamicPool::Postgres(p) => {
-                let rows = sqlx::query("SELECT column_name FROM information_schema.columns WHERE table_name = $1")
-                    .bind(table)
-                    .fetch_all(p).await?;
-                Ok(rows.into_iter().filter_map(|r| r.try_get::<String, _>(0).ok()).collect())
-            },
-            DynamicPool::MySql(p) => {
-                let rows = sqlx::query(&format!("DESCRIBE {}", table)).fetch_all(p).await?;
-                Ok(rows.into_iter().filter_map(|r| r.try_get::<String, _>(0).ok()).collect())
-            }
+    /// 启动迁移：将历史明文连接串加密落库，并补齐脱敏展示串
+    pub async fn seal_plaintext_sources(&self, db: &sqlx::PgPool) -> anyhow::Result<usize> {
+        let rows = sqlx::query("SELECT id, connection_url FROM data_sources WHERE connection_url NOT LIKE 'enc:v1:%'")
+            .fetch_all(db)
+            .await?;
+        for row in &rows {
+            let id: String = row.get(0);
+            let plain: String = row.get(1);
+            sqlx::query("UPDATE data_sources SET connection_url = $2, masked_url = $3 WHERE id = $1")
+                .bind(&id)
+                .bind(self.cipher.seal(&plain)?)
+                .bind(mask_connection_url(&plain))
+                .execute(db)
+                .await?;
+            info!("🔐 数据源连接串已加密: {}", id);
         }
+        Ok(rows.len())
     }
 
-    pub async fn get_or_create_pool(&self, source: &DataSource) -> anyhow::Result<Arc<DynamicPool>> {
+    /// 获取（必要时创建）数据源连接器
+    /// 建连期间不持有分片锁；并发的首次访问各自建连后只保留先登记的连接池，其余立即关闭
+    pub async fn get_or_create_pool(&self, source: &DataSource) -> anyhow::Result<Arc<dyn DataSourceConnector>> {
         if let Some(pool) = self.pools.get(&source.id) {
             return Ok(pool.clone());
         }
-        let new_pool = match source.db_type.to_lowercase().as_str() {
+        let new_pool = self.connect(source).await?;
+        let existing = match self.pools.entry(source.id.clone()) {
+            Entry::Occupied(e) => e.get().clone(),
+            Entry::Vacant(e) => {
+                e.insert(new_pool.clone());
+                return Ok(new_pool);
+            }
+        };
+        new_pool.close().await;
+        info!("♻️ 数据源 {} 已有并发建立的连接池，关闭多余连接池", source.id);
+        Ok(existing)
+    }
+
+    /// 按数据源配置新建连接器（连接串唯一的解密点，也是新后端的登记处）
+    async fn connect(&self, source: &DataSource) -> anyhow::Result<Arc<dyn DataSourceConnector>> {
+        let url = self.cipher.open(&source.connection_url)?;
+        match source.db_type.to_lowercase().as_str() {
             "postgres" | "postgresql" => {
-                let pool = PgPoolOptions::new().max_connections(5).connect(&source.connection_url).await?;
-                Arc::new(DynamicPool::Postgres(pool))
+                let pool: PgPoolOptions = pool_options(source);
+                Ok(Arc::new(PostgresConnector(pool.connect(&url).await?)))
             }
             "mysql" => {
-                let pool = MySqlPoolOptions::new().max_connections(5).connect(&source.connection_url).await?;
-                Arc::new(DynamicPool::MySql(pool))
+                let pool: MySqlPoolOptions = pool_options(source);
+                Ok(Arc::new(MySqlConnector(pool.connect(&url).await?)))
             }
-            _ => return Err(anyhow::anyhow!("Unsupported DB type")),
-        };
-        self.pools.insert(source.id.clone(), new_pool.clone());
-        Ok(new_pool)
+            // 连接串如 sqlite:///data/app.db?mode=ro
+            "sqlite" => {
+                let pool: SqlitePoolOptions = pool_options(source);
+                Ok(Arc::new(SqliteConnector(pool.connect(&url).await?)))
+            }
+            _ => Err(anyhow::anyhow!("Unsupported DB type")),
+        }
+    }
+
+    /// 以独立的临时连接测试数据源（不影响缓存中的连接池）
+    pub async fn test_connection(&self, source: &DataSource) -> PingResult {
+        let start = Instant::now();
+        let mut probe = source.clone();
+        probe.max_connections = Some(1);
+        probe.min_connections = Some(0);
+        probe.acquire_timeout_secs = Some(probe.acquire_timeout_secs.unwrap_or(10).min(10));
+        match self.connect(&probe).await {
+            Ok(pool) => {
+                let mut res = pool.ping().await;
+                res.latency_ms = start.elapsed().as_millis();
+                pool.close().await;
+                res
+            }
+            Err(e) => PingResult {
+                ok: false,
+                latency_ms: start.elapsed().as_millis(),
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// 驱逐并关闭缓存的连接池（配置变更或删除后调用，下次访问时按新配置重建）
+    pub async fn evict(&self, source_id: &str) {
+        if let Some((_, pool)) = self.pools.remove(source_id) {
+            pool.close().await;
+            info!("♻️ 数据源连接池已驱逐: {}", source_id);
+        }
+    }
+
+    /// 已缓存连接池（未建连的数据源不在其中）
+    pub fn cached(&self, source_id: &str) -> Option<Arc<dyn DataSourceConnector>> {
+        self.pools.get(source_id).map(|p| p.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::join_all;
+
+    fn sqlite_source(id: &str) -> DataSource {
+        DataSource {
+            id: id.to_string(),
+            db_type: "sqlite".to_string(),
+            connection_url: "sqlite::memory:".to_string(),
+            display_name: None,
+            masked_url: None,
+            max_connections: Some(1),
+            min_connections: None,
+            acquire_timeout_secs: None,
+            idle_timeout_secs: None,
+        }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn concurrent_first_access_shares_one_pool() {
+        let manager = PoolManager::new(CredentialCipher::from_env().unwrap());
+        let source = sqlite_source("lite");
+        let pools = join_all((0..8).map(|_| manager.get_or_create_pool(&source))).await;
+        let pools: Vec<_> = pools.into_iter().map(Result::unwrap).collect();
+        let cached = manager.cached("lite").unwrap();
+        assert!(pools.iter().all(|p| Arc::ptr_eq(p, &cached)));
+        assert!(cached.ping().await.ok);
+
+        manager.evict("lite").await;
+        assert!(manager.cached("lite").is_none());
+        let fresh = manager.get_or_create_pool(&source).await.unwrap();
+        assert!(!Arc::ptr_eq(&fresh, &cached));
+    }
+}
@@ -1,6 +1,8 @@
 use sqlx::{postgres::PgRow, Column, Row, TypeInfo, postgres::PgPoolOptions, PgPool};
 use serde_json::{json, Value, Map};
 use sqlx::mysql::MySqlRow;
+use sqlx::sqlite::SqliteRow;
+use sqlx::ValueRef;
 use std::env;
 
 pub async fn init_db() -> PgPool {
@@ -85,4 +87,24 @@ pub fn mysql_row_to_json(row: &MySqlRow) -> Value {
     }
     
     Value::Object(map)
-}
\ No newline at end of file
+}
+
+/// SQLite 为动态类型：按每个值的实际存储类型转换（聚合等表达式列没有声明类型）
+pub fn sqlite_row_to_json(row: &SqliteRow) -> Value {
+    let mut map = Map::new();
+    for (idx, col) in row.columns().iter().enumerate() {
+        let storage = match row.try_get_raw(idx) {
+            Ok(raw) if !raw.is_null() => raw.type_info().name().to_string(),
+            _ => "NULL".to_string(),
+        };
+        let val = match storage.as_str() {
+            "NULL" => Value::Null,
+            "INTEGER" => json!(row.try_get_unchecked::<Option<i64>, _>(idx).unwrap_or(None)),
+            "REAL" => json!(row.try_get_unchecked::<Option<f64>, _>(idx).unwrap_or(None)),
+            "BOOLEAN" => json!(row.try_get_unchecked::<Option<bool>, _>(idx).unwrap_or(None)),
+            _ => json!(row.try_get_unchecked::<Option<String>, _>(idx).unwrap_or(None)),
+        };
+        map.insert(col.name().to_string(), val);
+    }
+    Value::Object(map)
+}
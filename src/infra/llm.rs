@@ -0,0 +1,183 @@
+use crate::models::schema::{CatalogEntry, LogicalPlanDraft, PlanFilterDraft};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::json;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 大模型语义规划能力抽象
+/// 约束：只允许输出结构化的逻辑计划草案（以 node_key 引用本体），绝不返回 SQL
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// 提供方名称，用于日志与响应标注
+    fn name(&self) -> &str;
+
+    /// 给定本体目录与用户提问，生成逻辑计划草案
+    async fn plan(&self, catalog: &[CatalogEntry], question: &str) -> anyhow::Result<LogicalPlanDraft>;
+}
+
+/// 根据环境变量装配 LLM 提供方
+/// LLM_PROVIDER=openai 时读取 LLM_BASE_URL / LLM_API_KEY / LLM_MODEL；=mock 时使用确定性模拟实现
+pub fn provider_from_env() -> Option<Arc<dyn LlmProvider>> {
+    match env::var("LLM_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+        "openai" => {
+            let base_url = env::var("LLM_BASE_URL").unwrap_or_else(|_| "https://api.openai.com/v1".into());
+            let api_key = env::var("LLM_API_KEY").unwrap_or_default();
+            let model = env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-4o-mini".into());
+            info!("🤖 LLM 兜底已启用: OpenAI 兼容接口 {} (model={})", base_url, model);
+            Some(Arc::new(OpenAiCompatibleProvider::new(base_url, api_key, model)))
+        }
+        "mock" => {
+            info!("🤖 LLM 兜底已启用: Mock 提供方");
+            Some(Arc::new(MockLlmProvider))
+        }
+        "" => None,
+        other => {
+            warn!("未知的 LLM_PROVIDER: {}，已禁用 LLM 兜底", other);
+            None
+        }
+    }
+}
+
+// --- 1. OpenAI 兼容实现 (DeepSeek / GPT / 本地 vLLM 等) ---
+
+pub struct OpenAiCompatibleProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(base_url: String, api_key: String, model: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    async fn plan(&self, catalog: &[CatalogEntry], question: &str) -> anyhow::Result<LogicalPlanDraft> {
+        let body = json!({
+            "model": self.model,
+            "temperature": 0,
+            "response_format": { "type": "json_object" },
+            "messages": [
+                { "role": "system", "content": planner_system_prompt(catalog)? },
+                { "role": "user", "content": question }
+            ]
+        });
+
+        let resp: serde_json::Value = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let content = resp["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("LLM 响应缺少 message.content"))?;
+        parse_plan_json(content)
+    }
+}
+
+/// 规划器系统提示词（在线调用与微调样本导出共用）
+pub fn planner_system_prompt(catalog: &[CatalogEntry]) -> anyhow::Result<String> {
+    Ok(format!(
+        "你是企业语义层的查询规划器。只能使用下面本体目录中的 node_key，禁止编造节点，禁止输出 SQL。\n\
+         请仅输出一个 JSON 对象，格式为：\n\
+         {{\"metric\": \"<指标 node_key>\", \"filters\": [{{\"dimension\": \"<维度 node_key>\", \"value\": \"<维度值>\"}}], \
+         \"group_by\": [\"<维度 node_key>\"], \"agg\": \"SUM|AVG|COUNT|MAX|MIN|NONE\"}}\n\
+         维度必须出现在指标的 supported_dimensions 中；日期值使用 YYYY-MM-DD。\n\
+         本体目录：\n{}",
+        serde_json::to_string(catalog)?
+    ))
+}
+
+/// 解析模型输出：容忍 ```json 代码块包裹
+fn parse_plan_json(content: &str) -> anyhow::Result<LogicalPlanDraft> {
+    let trimmed = content
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    serde_json::from_str(trimmed).map_err(|e| anyhow::anyhow!("LLM 输出不是合法的逻辑计划 JSON: {}", e))
+}
+
+// --- 2. 确定性模拟实现 (离线联调与测试) ---
+
+/// 基于目录字面匹配的模拟规划器：相同输入总是得到相同输出
+pub struct MockLlmProvider;
+
+#[async_trait]
+impl LlmProvider for MockLlmProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn plan(&self, catalog: &[CatalogEntry], question: &str) -> anyhow::Result<LogicalPlanDraft> {
+        let mentions = |e: &CatalogEntry| {
+            question.contains(&e.label) || e.alias_names.iter().any(|a| !a.is_empty() && question.contains(a))
+        };
+
+        // 1. 指标：取最长命中的标签，保证结果稳定
+        let metric = catalog
+            .iter()
+            .filter(|e| e.node_role == "METRIC" && mentions(e))
+            .max_by_key(|e| (e.label.chars().count(), e.node_key.clone()))
+            .ok_or_else(|| anyhow::anyhow!("Mock LLM 未能在目录中找到匹配的指标"))?;
+
+        // 2. 日期值绑定到指标支持的首个 DATE 维度
+        let date_regex = Regex::new(r"(\d{4}-\d{2}-\d{2})")?;
+        let mut filters = Vec::new();
+        if let Some(cap) = date_regex.captures(question) {
+            if let Some(dim) = catalog.iter().find(|e| {
+                e.semantic_type == "DATE" && metric.supported_dimensions.contains(&e.node_key)
+            }) {
+                filters.push(PlanFilterDraft {
+                    dimension: dim.node_key.clone(),
+                    value: cap[1].to_string(),
+                });
+            }
+        }
+
+        // 3. 其余被提及的支持维度作为分组
+        let group_by = catalog
+            .iter()
+            .filter(|e| {
+                e.node_role == "DIMENSION"
+                    && metric.supported_dimensions.contains(&e.node_key)
+                    && !filters.iter().any(|f| f.dimension == e.node_key)
+                    && mentions(e)
+            })
+            .map(|e| e.node_key.clone())
+            .collect();
+
+        Ok(LogicalPlanDraft {
+            metric: metric.node_key.clone(),
+            filters,
+            group_by,
+            agg: None,
+        })
+    }
+}
@@ -1,2 +1,5 @@
 pub mod db_internal;
-pub mod db_external;
\ No newline at end of file
+pub mod db_external;
+pub mod connector;
+pub mod crypto;
+pub mod llm;
\ No newline at end of file
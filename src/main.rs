@@ -4,26 +4,60 @@ mod infra;
 mod models;
 mod service;
 
-use axum::{routing::{get, post,delete}, Router};
+use axum::{middleware, routing::{get, post, put, delete}, Router};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tower_http::cors::{Any, CorsLayer};
+use axum::http::HeaderValue;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer; 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt}; 
 
 use sqlx::Row;
 
+use crate::api::auth::{
+    authenticate, create_api_key, list_api_keys, require_admin, require_modeler, revoke_api_key, whoami, AuthConfig,
+};
 use crate::api::chat::chat_query;
+use crate::api::datasource::{
+    data_source_status, delete_data_source, list_data_sources, register_data_source, test_data_source,
+    test_new_data_source, update_data_source,
+};
+use crate::api::dimension_values::{
+    add_value_synonym, delete_label_source, delete_value_synonym, get_label_source, list_dimension_values,
+    update_value_label, upsert_label_source,
+};
+use crate::api::domains::{
+    create_dataset, create_domain, delete_dataset, delete_domain, list_datasets, list_domains, update_dataset,
+    update_domain,
+};
+use crate::api::feedback::{list_feedback, review_feedback, submit_feedback};
+use crate::api::history::{get_query_log, list_query_logs, replay_query_log};
+use crate::api::ontology::{
+    bootstrap_commit, bootstrap_draft, delete_relation, discover_relations, export_bundle, export_ontology_rdf,
+    import_bundle, import_ontology_rdf, list_relations, review_relation,
+};
+use crate::api::revisions::{
+    diff_ontology, discard_draft, get_revision, lint_ontology, list_publications, list_revisions, node_history, publish_ontology,
+    publish_status, rollback_node, validate_ontology, withdraw_publication,
+};
+use crate::api::shadow::{export_shadow_samples, list_shadow_runs};
+use crate::api::sparql::sparql_query;
+use crate::api::sync::{
+    delete_sync_schedule, list_sync_runs, list_sync_schedules, sync_dimension_values, upsert_sync_schedule,
+};
+use crate::api::security::{create_policy, delete_policy, list_policies};
 use crate::api::mapping::{
-    list_mappings, register_data_source, save_mapping, list_data_sources, 
-    get_metadata_tables, get_metadata_columns, sync_dimension_values, export_ontology_ttl,
+    list_mappings, save_mapping,
+    get_metadata_schemas, get_metadata_tables, get_metadata_columns, describe_metadata_table,
     delete_mapping
 };
 use crate::core::fst_engine::FstEngine;
 use crate::core::inference::SemanticInferenceEngine;
 use crate::infra::db_external::PoolManager;
-use crate::models::schema::FullSemanticNode;
+use crate::infra::llm::LlmProvider;
+use crate::service::shadow::ShadowRunner;
+use crate::service::sparql::TripleStore;
 
 pub mod ax_state {
     use super::*;
@@ -32,6 +66,10 @@ pub mod ax_state {
         pub fst: RwLock<FstEngine>,
         pub pool_manager: PoolManager,
         pub engine: RwLock<SemanticInferenceEngine>, // 【核心】将推理引擎单例化
+        pub llm: Option<Arc<dyn LlmProvider>>,       // 规则推理失败时的 LLM 兜底 (可选)
+        pub shadow: Option<ShadowRunner>,            // 影子执行流水线投递端 (可选)
+        pub auth: AuthConfig,                        // 鉴权配置 (JWT / API Key)
+        pub rdf: RwLock<TripleStore>,                // 本体 RDF 视图 (SPARQL 查询)
     }
 }
 
@@ -50,23 +88,15 @@ async fn main() -> anyhow::Result<()> {
 
     let db = infra::db_internal::init_db().await;
 
-    // 2. 核心：启动时加载全量语义节点 (初始化 FST)
-    // 这里的 SQL 必须与 mapping.rs 中的 list 逻辑保持高度一致
-    let mappings_res = sqlx::query_as::<sqlx::Postgres, FullSemanticNode>(
-        r#"
-        SELECT n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.value_format, 
-               d.default_constraints, d.alias_names, d.default_agg, n.dataset_id,
-               COALESCE(array_agg(r.dimension_node_id) FILTER (WHERE r.dimension_node_id IS NOT NULL), '{}') as supported_dimension_ids
-        FROM ontology_nodes n 
-        JOIN semantic_definitions d ON n.id = d.node_id
-        LEFT JOIN metric_dimension_rels r ON n.id = r.metric_node_id
-        GROUP BY n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format
-        "#
-    )
-    .fetch_all(&db)
-    .await;
-
-    let nodes = match mappings_res {
+    // 2. 核心：启动时加载推理引擎服务的语义节点 (初始化 FST)
+    // 先为尚无修订历史的节点补齐基线，从未发布过时发布基线；存在生效发布时加载发布快照，否则加载实时本体
+    if let Err(e) = service::revision::ensure_baseline(&db).await {
+        tracing::warn!("本体修订基线补齐失败: {}", e);
+    }
+    if let Err(e) = service::revision::ensure_published(&db).await {
+        tracing::warn!("本体基线发布失败: {}", e);
+    }
+    let nodes = match service::revision::load_serving_nodes(&db).await {
         Ok(n) => {
             tracing::info!("✅ [Init] 成功加载 {} 个语义节点到内存索引", n.len());
             n
@@ -90,45 +120,151 @@ async fn main() -> anyhow::Result<()> {
         v
     }).collect::<Vec<String>>();
 
-    // 提取 A-Box 码值
-    let codes = sqlx::query("SELECT value_label FROM dimension_values").fetch_all(&db).await?;
+    // 提取 A-Box 码值（先补齐缺失的归一化形式）
+    if let Err(e) = service::sync::backfill_normalized(&db).await {
+        tracing::warn!("码值归一化补齐失败: {}", e);
+    }
+    let codes = sqlx::query(api::mapping::ABOX_WORDS_SQL).fetch_all(&db).await?;
     words.extend(codes.into_iter().map(|r| r.get::<String, _>(0)));
     
     inference_engine.refresh_custom_words(words);
     
     // 4. 初始化全局状态
+    let llm = infra::llm::provider_from_env();
+    // 影子执行依赖 LLM 提供方，未配置时不启动
+    let shadow = ShadowRunner::from_env().filter(|_| llm.is_some());
     let state = Arc::new(ax_state::AppState {
         db,
         fst: RwLock::new(fst_engine),
-        pool_manager: PoolManager::new(),
+        pool_manager: PoolManager::new(infra::crypto::CredentialCipher::from_env()?),
         engine: RwLock::new(inference_engine),
+        llm,
+        shadow: shadow.as_ref().map(|(runner, _)| runner.clone()),
+        auth: AuthConfig::from_env(),
+        rdf: RwLock::new(TripleStore::default()),
     });
 
+    if let Err(e) = service::sparql::refresh(&state).await {
+        tracing::warn!("本体三元组存储初始化失败: {}", e);
+    }
+
+    // 历史明文连接串加密迁移（需已配置 SSE_CREDENTIAL_KEY）
+    if let Err(e) = state.pool_manager.seal_plaintext_sources(&state.db).await {
+        tracing::warn!("数据源连接串加密迁移未完成: {}", e);
+    }
+
+    if let Some((_, rx)) = shadow {
+        service::shadow::spawn_worker(state.clone(), rx);
+    }
+    service::sync::spawn_scheduler(state.clone());
+
     // 5. 配置中间件与路由
+    // CORS_ALLOW_ORIGINS 以逗号分隔配置允许的前端来源，未配置时放开 (仅限开发)
+    let allow_origin = match std::env::var("CORS_ALLOW_ORIGINS") {
+        Ok(v) if !v.trim().is_empty() => AllowOrigin::list(
+            v.split(',').filter_map(|o| HeaderValue::from_str(o.trim()).ok()),
+        ),
+        _ => AllowOrigin::from(Any),
+    };
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(allow_origin)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    let app = Router::new()
-        // 语义建模接口
+    // 分析师：问数对话、浏览本体与数据源、提交反馈
+    let analyst_routes = Router::new()
+        .route("/api/chat", post(chat_query))
         .route("/api/mappings", get(list_mappings))
+        .route("/api/domains", get(list_domains))
+        .route("/api/datasets", get(list_datasets))
+        .route("/api/datasources", get(list_data_sources))
+        .route("/api/feedback", post(submit_feedback))
+        .route("/api/auth/me", get(whoami));
+
+    // 建模师：本体建模、数据源、同步与运营审计
+    let modeler_routes = Router::new()
+        // 语义建模接口
         .route("/api/mapping", post(save_mapping))
         .route("/api/mapping/{id}", delete(delete_mapping))
-        .route("/api/ontology/export", get(export_ontology_ttl))
+        .route("/api/domains", post(create_domain))
+        .route("/api/domains/{id}", put(update_domain).delete(delete_domain))
+        .route("/api/datasets", post(create_dataset))
+        .route("/api/datasets/{id}", put(update_dataset).delete(delete_dataset))
+        .route("/api/ontology/export", get(export_ontology_rdf))
+        .route("/api/ontology/import", post(import_ontology_rdf))
+        .route("/api/ontology/bundle", get(export_bundle).post(import_bundle))
+        .route("/api/ontology/bootstrap", post(bootstrap_draft))
+        .route("/api/ontology/bootstrap/commit", post(bootstrap_commit))
+        .route("/api/ontology/relations", get(list_relations))
+        .route("/api/ontology/relations/discover", post(discover_relations))
+        .route("/api/ontology/relations/{id}", delete(delete_relation))
+        .route("/api/ontology/relations/{id}/{action}", post(review_relation))
+        // 版本历史、回滚与发布快照
+        .route("/api/ontology/revisions", get(list_revisions))
+        .route("/api/ontology/revisions/{id}", get(get_revision))
+        .route("/api/ontology/nodes/{key}/history", get(node_history))
+        .route("/api/ontology/nodes/{key}/rollback", post(rollback_node))
+        .route("/api/ontology/diff", get(diff_ontology))
+        .route("/api/ontology/publish", get(publish_status).post(publish_ontology).delete(withdraw_publication))
+        .route("/api/ontology/publications", get(list_publications))
+        .route("/api/ontology/validate", post(validate_ontology))
+        .route("/api/ontology/lint", get(lint_ontology))
+        .route("/api/ontology/draft", delete(discard_draft))
+        // SPARQL 绕过行列级安全策略，仅对建模师开放
+        .route("/api/sparql", get(sparql_query).post(sparql_query))
         
         // 元数据与同步
+        .route("/api/metadata/schemas", get(get_metadata_schemas))
         .route("/api/metadata/tables", get(get_metadata_tables))
         .route("/api/metadata/columns", get(get_metadata_columns))
+        .route("/api/metadata/table", get(describe_metadata_table))
         .route("/api/sync-values/{id}", post(sync_dimension_values))
+        .route("/api/dimensions/{id}/values", get(list_dimension_values))
+        .route(
+            "/api/dimensions/{id}/label-source",
+            get(get_label_source).put(upsert_label_source).delete(delete_label_source),
+        )
+        .route("/api/dimension-values/{id}", put(update_value_label))
+        .route("/api/dimension-values/{id}/synonyms", post(add_value_synonym))
+        .route("/api/dimension-values/{id}/synonyms/{synonym}", delete(delete_value_synonym))
+        .route("/api/sync/runs", get(list_sync_runs))
+        .route("/api/sync/schedules", get(list_sync_schedules))
+        .route("/api/sync/schedules/{id}", put(upsert_sync_schedule).delete(delete_sync_schedule))
         
-        // 数据源管理
+        // 数据源管理：登记、测试、更新 (重建连接池)、删除与健康状态
         .route("/api/datasource", post(register_data_source))
-        .route("/api/datasources", get(list_data_sources))
-        
-        // 问数对话 (核心)
-        .route("/api/chat", post(chat_query))
-        
+        .route("/api/datasource/test", post(test_new_data_source))
+        .route("/api/datasource/{id}", put(update_data_source).delete(delete_data_source))
+        .route("/api/datasource/{id}/test", post(test_data_source))
+        .route("/api/datasources/status", get(data_source_status))
+
+        // 问答审计日志
+        .route("/api/query-logs", get(list_query_logs))
+        .route("/api/query-logs/{id}", get(get_query_log))
+        .route("/api/query-logs/{id}/replay", post(replay_query_log))
+
+        // 反馈审核
+        .route("/api/feedbacks", get(list_feedback))
+        .route("/api/feedback/{id}/review", post(review_feedback))
+
+        // 进化流水线：影子执行差异与微调样本
+        .route("/api/shadow/runs", get(list_shadow_runs))
+        .route("/api/shadow/export", get(export_shadow_samples))
+        .route_layer(middleware::from_fn(require_modeler));
+
+    // 管理员：API Key 与行列级安全策略
+    let admin_routes = Router::new()
+        .route("/api/auth/keys", get(list_api_keys).post(create_api_key))
+        .route("/api/auth/keys/{id}", delete(revoke_api_key))
+        .route("/api/security/policies", get(list_policies).post(create_policy))
+        .route("/api/security/policies/{id}", delete(delete_policy))
+        .route_layer(middleware::from_fn(require_admin));
+
+    let app = Router::new()
+        .merge(analyst_routes)
+        .merge(modeler_routes)
+        .merge(admin_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
         .with_state(state)
         .layer(cors)
         .layer(TraceLayer::new_for_http());
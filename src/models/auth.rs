@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 角色：权限逐级包含 (admin ⊇ modeler ⊇ analyst)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Analyst, // 只能问数与浏览
+    Modeler, // 可建模、管理数据源、同步 A-Box
+    Admin,   // 可管理 API Key
+}
+
+impl Role {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "analyst" => Some(Role::Analyst),
+            "modeler" => Some(Role::Modeler),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Analyst => "analyst",
+            Role::Modeler => "modeler",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// 已认证的调用方（由鉴权中间件注入请求扩展）
+#[derive(Debug, Serialize, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub role: Role,
+    pub auth_method: String, // jwt / api_key / disabled
+    pub attributes: HashMap<String, Vec<String>>, // 行级安全属性，如 {"platforms": ["A公司"]}
+}
+
+/// JWT 载荷（HS256，本地校验）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub role: String,
+    pub exp: usize,
+    #[serde(default)]
+    pub attrs: HashMap<String, Vec<String>>,
+}
+
+/// API Key 元信息（不含摘要）
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct ApiKeyInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub role: String,
+    pub attributes: Json<HashMap<String, Vec<String>>>,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub role: String,
+    #[serde(default)]
+    pub attributes: HashMap<String, Vec<String>>,
+}
+
+/// 安全策略：挂在本体节点或数据源上，按角色生效（管理员不受约束）
+/// - ROW_FILTER：强制过滤谓词，predicate 中的 user.<属性> 展开为调用方属性值列表
+/// - HIDE：对该角色隐藏节点（数据源级则隐藏其下全部节点）
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct SecurityPolicy {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Option<String>, // 为空表示所有非管理员角色
+    pub node_id: Option<Uuid>,
+    pub source_id: Option<String>,
+    pub effect: String,
+    pub predicate: Option<String>,
+    pub enabled: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePolicyRequest {
+    pub name: String,
+    pub role: Option<String>,
+    pub node_id: Option<Uuid>,
+    pub source_id: Option<String>,
+    pub effect: String,
+    pub predicate: Option<String>,
+}
@@ -0,0 +1,276 @@
+use crate::models::schema::BusinessConstraint;
+use serde::{Deserialize, Serialize};
+
+/// 当前本体包格式版本；导入时拒绝更高版本
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// 本体包：在环境之间（开发 -> 生产）迁移语义模型
+/// 所有对象以业务 Key 互相引用，不携带环境相关的 UUID 与数据源凭据
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OntologyBundle {
+    pub format_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub data_sources: Vec<BundleDataSource>,
+    #[serde(default)]
+    pub domains: Vec<BundleDomain>,
+    #[serde(default)]
+    pub datasets: Vec<BundleDataset>,
+    #[serde(default)]
+    pub nodes: Vec<BundleNode>,
+    #[serde(default)]
+    pub relations: Vec<BundleRelation>,
+}
+
+/// 数据源仅导出标识与类型；连接串需在目标环境预先注册
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleDataSource {
+    pub id: String,
+    pub db_type: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleDomain {
+    pub domain_key: String,
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleDataset {
+    pub dataset_key: String,
+    pub label: String,
+    #[serde(default)]
+    pub domain_key: Option<String>,
+    #[serde(default = "empty_object")]
+    pub join_config: serde_json::Value,
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleNode {
+    pub node_key: String,
+    pub label: String,
+    pub node_role: String,
+    #[serde(default = "default_semantic_type")]
+    pub semantic_type: String,
+    #[serde(default)]
+    pub dataset_key: Option<String>,
+    pub source_id: String,
+    pub target_table: String,
+    pub sql_expression: String,
+    #[serde(default)]
+    pub alias_names: Vec<String>,
+    #[serde(default)]
+    pub default_constraints: Vec<BusinessConstraint>,
+    #[serde(default = "default_agg")]
+    pub default_agg: String,
+    #[serde(default)]
+    pub value_format: Option<String>,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub number_format: Option<String>,
+    /// 指标支持的维度（维度 node_key）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_dimensions: Vec<String>,
+    /// 维度的码值标签来源
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_source: Option<BundleLabelSource>,
+    /// 维度的 A-Box 码值
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<BundleValue>,
+}
+
+fn default_semantic_type() -> String {
+    "STRING".to_string()
+}
+
+fn default_agg() -> String {
+    "SUM".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleLabelSource {
+    pub label_expression: String,
+    #[serde(default)]
+    pub lookup_table: Option<String>,
+    #[serde(default)]
+    pub lookup_code_column: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleValue {
+    pub code: String,
+    pub label: String,
+    #[serde(default)]
+    pub label_locked: bool,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 表间关联（JOIN 路径）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BundleRelation {
+    pub source_id: String,
+    pub from_table: String,
+    pub to_table: String,
+    pub join_logic: String,
+    #[serde(default = "default_relation_origin")]
+    pub origin: String,
+    #[serde(default = "default_relation_status")]
+    pub status: String,
+}
+
+fn default_relation_origin() -> String {
+    "MANUAL".to_string()
+}
+
+fn default_relation_status() -> String {
+    "ACCEPTED".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BundleExportQuery {
+    /// json（默认）或 yaml
+    pub format: Option<String>,
+    /// 是否包含 A-Box 码值，默认包含
+    pub values: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BundleImportQuery {
+    /// json 或 yaml；为空时按 Content-Type 判断
+    pub format: Option<String>,
+    /// 默认只做校验与差异预览，显式传 dry_run=false 才落库
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RdfExportQuery {
+    /// turtle（默认）/ jsonld / ntriples
+    pub format: Option<String>,
+    /// 覆盖 SSE_RDF_BASE_IRI
+    pub base: Option<String>,
+    /// 是否导出维度码值个体，默认导出
+    pub values: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RdfImportQuery {
+    /// turtle / jsonld / ntriples；为空时按 Content-Type 判断
+    pub format: Option<String>,
+    /// 需与导出时的基础 IRI 一致
+    pub base: Option<String>,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SparqlRequest {
+    /// SPARQL 查询文本（GET 查询参数或 POST 表单字段）
+    pub query: Option<String>,
+}
+
+/// 导入差异中的一项变更
+#[derive(Debug, Serialize, Clone)]
+pub struct BundleChange {
+    pub kind: String,   // domain / dataset / node / value / relation
+    pub key: String,
+    pub action: String, // CREATE / UPDATE
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct BundleImportReport {
+    pub dry_run: bool,
+    pub applied: bool,
+    pub errors: Vec<String>,
+    pub changes: Vec<BundleChange>,
+    pub unchanged: usize,
+}
+
+/// 测试用本体包：销售域下两个指标、三个维度（其中 orphan 未被任何指标引用）
+#[cfg(test)]
+pub mod fixtures {
+    use super::*;
+
+    fn node(key: &str, label: &str, role: &str, expr: &str, dims: &[&str]) -> BundleNode {
+        BundleNode {
+            node_key: key.to_string(),
+            label: label.to_string(),
+            node_role: role.to_string(),
+            semantic_type: if role == "METRIC" { "CURRENCY" } else { "STRING" }.to_string(),
+            dataset_key: Some("orders".to_string()),
+            source_id: "pg".to_string(),
+            target_table: "demo.fact_order".to_string(),
+            sql_expression: expr.to_string(),
+            alias_names: Vec::new(),
+            default_constraints: Vec::new(),
+            default_agg: "SUM".to_string(),
+            value_format: None,
+            unit: None,
+            number_format: None,
+            supported_dimensions: dims.iter().map(|d| d.to_string()).collect(),
+            label_source: None,
+            values: Vec::new(),
+        }
+    }
+
+    fn value(code: &str, label: &str, synonyms: &[&str]) -> BundleValue {
+        BundleValue {
+            code: code.to_string(),
+            label: label.to_string(),
+            label_locked: false,
+            is_active: true,
+            synonyms: synonyms.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    pub fn sample_bundle() -> OntologyBundle {
+        let mut revenue = node("revenue", "收益", "METRIC", "amount", &["channel", "region"]);
+        revenue.unit = Some("元".to_string());
+        revenue.alias_names = vec!["营收".to_string()];
+        let order_count = node("order_count", "订单数", "METRIC", "order_id", &["region"]);
+        let mut region = node("region", "区域", "DIMENSION", "region_code", &[]);
+        region.values = vec![value("EAST", "华东", &["东区"]), value("WEST", "华西", &[])];
+        let channel = node("channel", "渠道", "DIMENSION", "channel_code", &[]);
+        let orphan = node("orphan", "孤立维度", "DIMENSION", "orphan_code", &[]);
+        OntologyBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: None,
+            data_sources: vec![BundleDataSource {
+                id: "pg".to_string(),
+                db_type: "postgres".to_string(),
+                display_name: Some("主库".to_string()),
+            }],
+            domains: vec![BundleDomain { domain_key: "sales".to_string(), label: "销售".to_string(), description: None }],
+            datasets: vec![BundleDataset {
+                dataset_key: "orders".to_string(),
+                label: "订单".to_string(),
+                domain_key: Some("sales".to_string()),
+                join_config: serde_json::json!({}),
+            }],
+            nodes: vec![revenue, order_count, region, channel, orphan],
+            relations: vec![BundleRelation {
+                source_id: "pg".to_string(),
+                from_table: "demo.fact_order".to_string(),
+                to_table: "demo.dim_shop".to_string(),
+                join_logic: "demo.fact_order.shop_id = demo.dim_shop.shop_id".to_string(),
+                origin: "MANUAL".to_string(),
+                status: "ACCEPTED".to_string(),
+            }],
+        }
+    }
+}
@@ -1,6 +1,48 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct ChatRequest {
-    pub query: String, // 用户提问内容
-}
\ No newline at end of file
+    pub query: String,              // 用户提问内容
+    pub session_id: Option<String>, // 会话标识（审计与多轮对话使用）
+    pub domain: Option<String>,     // 业务域 (domain_key)，指定后只在该域及公共节点中匹配
+}
+
+/// 图表类型：前端按该枚举直接选择渲染组件
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartType {
+    Kpi,   // 单值指标卡
+    Line,  // 时间序列折线图
+    Bar,   // 分类对比柱状图
+    Pie,   // 占比饼图
+    Table, // 明细表格
+}
+
+/// 图表坐标轴 / 分组字段（字段名即结果集中的列名）
+#[derive(Debug, Serialize, Clone)]
+pub struct ChartField {
+    pub field: String,
+    pub semantic_type: String,
+}
+
+/// 图表度量字段，携带单位与数值格式供前端格式化
+#[derive(Debug, Serialize, Clone)]
+pub struct ChartMeasure {
+    pub field: String,
+    pub agg: String,
+    pub unit: Option<String>,
+    pub number_format: Option<String>,
+}
+
+/// 对话响应中的图表推荐（稳定 Schema，版本号变化时需同步前端）
+#[derive(Debug, Serialize, Clone)]
+pub struct ChartSpec {
+    pub version: u32,
+    pub chart_type: ChartType,
+    pub title: String,
+    pub x: Option<ChartField>,
+    pub series: Option<ChartField>,
+    pub y: Vec<ChartMeasure>,
+    pub row_count: usize,
+    pub reason: String,
+}
@@ -1,2 +1,5 @@
+pub mod auth;
+pub mod bundle;
 pub mod schema;
-pub mod context;
\ No newline at end of file
+pub mod context;
+pub mod revision;
\ No newline at end of file
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 本体修订：一次节点变更（全局递增版本号即 id）
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct OntologyRevision {
+    pub id: i64,
+    pub kind: String,     // NODE / RELATION
+    pub node_key: String, // 关联为 relation:<id>
+    pub node_id: Option<Uuid>,
+    pub action: String, // CREATE / UPDATE / DELETE
+    pub snapshot: Option<Value>,
+    pub diff: Value,
+    pub changed_by: Option<String>,
+    pub via: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 发布记录：推理引擎服务的本体版本
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct OntologyPublication {
+    pub id: i64,
+    pub version: i64,
+    pub note: Option<String>,
+    pub published_by: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub withdrawn_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 变更来源：操作人与入口
+#[derive(Debug, Clone)]
+pub struct Author {
+    pub by: String,
+    pub via: String,
+}
+
+impl Author {
+    pub fn new(by: &str, via: &str) -> Self {
+        Self { by: by.to_string(), via: via.to_string() }
+    }
+}
+
+/// 字段级差异
+#[derive(Debug, Serialize, Clone)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// 两个版本之间单个节点的差异
+#[derive(Debug, Serialize, Clone)]
+pub struct NodeChange {
+    pub node_key: String,
+    pub action: String, // ADDED / REMOVED / CHANGED
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    /// 起始版本，默认当前发布版本（未发布时为 0，即空本体）
+    pub from: Option<i64>,
+    /// 目标版本，默认最新修订
+    pub to: Option<i64>,
+    /// 只比较指定节点
+    pub node: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    pub version: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublishRequest {
+    /// 发布水位，默认最新修订
+    pub version: Option<i64>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateQuery {
+    /// 校验的版本，默认最新修订（即草稿）
+    pub version: Option<i64>,
+}
+
+/// 校验发现的问题
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationIssue {
+    pub severity: String, // ERROR / WARNING / INFO
+    pub node_key: String,
+    pub check: String,
+    pub message: String,
+}
+
+/// 发布前校验报告：存在 ERROR 级问题时不允许发布
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationReport {
+    pub version: i64,
+    pub checked_nodes: usize,
+    pub passed: bool,
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LintQuery {
+    /// 检查的版本，默认最新修订（即草稿）
+    pub version: Option<i64>,
+    /// 最低输出级别：ERROR / WARNING / INFO（默认 INFO，即全部）
+    pub severity: Option<String>,
+}
+
+/// 本体一致性检查报告（ERROR / WARNING / INFO 分级，按级别与节点排序）
+#[derive(Debug, Serialize, Clone)]
+pub struct LintReport {
+    pub version: i64,
+    pub checked_nodes: usize,
+    pub summary: std::collections::BTreeMap<String, usize>,
+    pub issues: Vec<ValidationIssue>,
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BusinessConstraint {
     pub column: String,
     pub operator: String,
@@ -29,9 +29,39 @@ pub struct FullSemanticNode {
     pub supported_dimension_ids: Vec<Uuid>,
     pub dataset_id: Option<Uuid>,
     pub value_format: Option<String>,
+    // 指标的计量单位（如 "元"）与数值格式模板（如 "#,##0.00"）
+    #[sqlx(default)]
+    pub unit: Option<String>,
+    #[sqlx(default)]
+    pub number_format: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// 测试用节点：sql_expression 取 node_key，数据源 pg、物理表 t，其余字段为空；需要时以结构体更新语法覆盖
+#[cfg(test)]
+impl FullSemanticNode {
+    pub fn stub(key: &str, label: &str, role: &str, semantic_type: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            node_key: key.to_string(),
+            label: label.to_string(),
+            node_role: role.to_string(),
+            semantic_type: semantic_type.to_string(),
+            source_id: "pg".to_string(),
+            target_table: "t".to_string(),
+            sql_expression: key.to_string(),
+            default_constraints: sqlx::types::Json(Vec::new()),
+            alias_names: Vec::new(),
+            default_agg: "SUM".to_string(),
+            supported_dimension_ids: Vec::new(),
+            dataset_id: None,
+            value_format: None,
+            unit: None,
+            number_format: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateNodeRequest {
     pub node_key: String,
     pub label: String,
@@ -46,14 +76,50 @@ pub struct CreateNodeRequest {
     pub default_agg: String,
     pub dataset_id: Option<Uuid>,
     pub value_format: Option<String>,
+    pub unit: Option<String>,
+    pub number_format: Option<String>,
+}
+
+impl From<&FullSemanticNode> for CreateNodeRequest {
+    fn from(n: &FullSemanticNode) -> Self {
+        Self {
+            node_key: n.node_key.clone(),
+            label: n.label.clone(),
+            node_role: n.node_role.clone(),
+            semantic_type: n.semantic_type.clone(),
+            source_id: n.source_id.clone(),
+            target_table: n.target_table.clone(),
+            sql_expression: n.sql_expression.clone(),
+            alias_names: n.alias_names.clone(),
+            default_constraints: n.default_constraints.0.clone(),
+            supported_dimension_ids: n.supported_dimension_ids.clone(),
+            default_agg: n.default_agg.clone(),
+            dataset_id: n.dataset_id,
+            value_format: n.value_format.clone(),
+            unit: n.unit.clone(),
+            number_format: n.number_format.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct DataSource {
     pub id: String,
     pub db_type: String,
+    #[serde(skip_serializing)] // 密文，永不出现在响应中
     pub connection_url: String,
     pub display_name: Option<String>,
+    #[sqlx(default)]
+    pub masked_url: Option<String>, // 脱敏展示串
+    // 连接池参数（为空时使用默认值）
+    #[sqlx(default)]
+    pub max_connections: Option<i32>,
+    #[sqlx(default)]
+    pub min_connections: Option<i32>,
+    #[sqlx(default)]
+    pub acquire_timeout_secs: Option<i32>,
+    #[sqlx(default)]
+    pub idle_timeout_secs: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,22 +128,398 @@ pub struct CreateDataSourceRequest {
     pub db_type: String,
     pub connection_url: String,
     pub display_name: Option<String>,
+    pub max_connections: Option<i32>,
+    pub min_connections: Option<i32>,
+    pub acquire_timeout_secs: Option<i32>,
+    pub idle_timeout_secs: Option<i32>,
+}
+
+/// 数据源更新：未提供的字段保持不变
+#[derive(Debug, Deserialize)]
+pub struct UpdateDataSourceRequest {
+    pub db_type: Option<String>,
+    pub connection_url: Option<String>,
+    pub display_name: Option<String>,
+    pub max_connections: Option<i32>,
+    pub min_connections: Option<i32>,
+    pub acquire_timeout_secs: Option<i32>,
+    pub idle_timeout_secs: Option<i32>,
+}
+
+/// 业务域：一组数据集的归属，问数时可按域限定候选节点
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct SemanticDomain {
+    pub id: Uuid,
+    pub domain_key: String,
+    pub label: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDomainRequest {
+    pub domain_key: String,
+    pub label: String,
+    pub description: Option<String>,
+}
+
+/// 业务域更新：未提供的字段保持不变
+#[derive(Debug, Deserialize)]
+pub struct UpdateDomainRequest {
+    pub label: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 逻辑数据集：一组可互相关联的物理表及其默认 JOIN 配置
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct SemanticDataset {
+    pub id: Uuid,
+    pub domain_id: Option<Uuid>,
+    #[sqlx(default)]
+    pub domain_key: Option<String>,
+    pub dataset_key: String,
+    pub label: String,
+    pub join_config: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDatasetRequest {
+    pub dataset_key: String,
+    pub label: String,
+    pub domain_id: Option<Uuid>,
+    pub join_config: Option<serde_json::Value>,
+}
+
+/// 数据集更新：未提供的字段保持不变
+#[derive(Debug, Deserialize)]
+pub struct UpdateDatasetRequest {
+    pub label: Option<String>,
+    pub domain_id: Option<Uuid>,
+    pub join_config: Option<serde_json::Value>,
+}
+
+/// 数据集列表 / 节点列表的归属过滤（按业务键）
+#[derive(Debug, Deserialize)]
+pub struct ScopeQuery {
+    pub domain: Option<String>,
+    pub dataset: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MetadataRequest {
     pub source_id: String,
+    pub schema: Option<String>,
     pub table_name: Option<String>,
 }
 
+/// 外部表元数据探查结果（建模界面据此预填节点定义）
+#[derive(Debug, Serialize, Clone)]
+pub struct TableMetadata {
+    pub schema: String,
+    pub table_name: String,
+    pub comment: Option<String>,
+    pub approx_row_count: Option<i64>, // 来自库内统计信息，可能滞后或缺失
+    pub primary_key: Vec<String>,
+    pub columns: Vec<ColumnMetadata>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ColumnMetadata {
+    pub name: String,
+    pub data_type: String, // 物理库原始类型，如 numeric(12,2) / varchar(64)
+    pub nullable: bool,
+    pub is_primary_key: bool,
+    pub foreign_key: Option<ForeignKeyRef>,
+    pub comment: Option<String>,
+    pub suggested_semantic_type: String, // 按物理类型推断的 STRING / NUMBER / DATE
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ForeignKeyRef {
+    pub schema: Option<String>,
+    pub table: String,
+    pub column: Option<String>, // SQLite 省略引用列时指向对方主键
+}
+
+/// 由物理表自动生成本体草稿
+#[derive(Debug, Deserialize)]
+pub struct BootstrapRequest {
+    pub source_id: String,
+    pub schema: Option<String>,
+    pub table_name: String,
+    pub dataset_id: Option<Uuid>,
+}
+
+/// 本体草稿：建模人员可增删改节点后整体提交（单事务写入）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OntologyDraft {
+    pub source_id: String,
+    pub target_table: String,
+    pub nodes: Vec<DraftNode>,
+    #[serde(default)]
+    pub skipped_columns: Vec<String>, // 主键等不建模的列，仅供展示
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DraftNode {
+    #[serde(flatten)]
+    pub node: CreateNodeRequest,
+    // 草稿内维度尚无 id，指标以 node_key 引用（也可引用库中已有节点）
+    #[serde(default)]
+    pub supported_dimension_keys: Vec<String>,
+    #[serde(default)]
+    pub exists: bool, // 同 node_key 节点已存在，提交时将被覆盖
+}
+
+/// 表间关联（join_logic 形如 "fact_order.shop_id = dim_shop.shop_id"）
+#[derive(Debug, Serialize, FromRow)]
+pub struct OntologyRelation {
+    pub id: i32,
+    pub source_id: Option<String>,
+    pub from_table: Option<String>,
+    pub to_table: Option<String>,
+    pub join_logic: String,
+    pub origin: String, // FK / HEURISTIC / MANUAL
+    pub status: String, // PROPOSED / ACCEPTED / REJECTED
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 关联发现产出的候选
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RelationProposal {
+    pub from_table: String,
+    pub to_table: String,
+    pub join_logic: String,
+    pub origin: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoverRelationsRequest {
+    pub source_id: String,
+    pub schema: Option<String>,
+    pub heuristics: Option<bool>, // 是否启用 *_id 命名启发式，默认启用
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RelationListQuery {
+    pub source_id: Option<String>,
+    pub status: Option<String>,
+}
+
 /// 吸收自 SuperSonic 的逻辑查询计划中间表达
-/// 目前在推理机中直接生成 SQL，但在多表关联阶段将由该结构体承载推理状态
-#[allow(dead_code)]
+/// 推理结果先落为逻辑计划，再由 core::planner 编译为物理 SQL
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct QueryLogicalPlan {
     pub metric: FullSemanticNode,
     pub dimensions: Vec<(FullSemanticNode, String)>,
-    pub implicit_filters: Vec<String>,
+    // 分组维度（无具体值，仅参与 SELECT / GROUP BY）
+    #[serde(default)]
+    pub group_by: Vec<FullSemanticNode>,
+    // 业务隐含约束（取值在编译时按目标方言转义）
+    #[serde(default)]
+    pub implicit_constraints: Vec<BusinessConstraint>,
+    // 行级安全谓词（按调用方策略注入，与隐含约束一同编译）
+    #[serde(default)]
+    pub security_filters: Vec<String>,
     pub final_agg: String,
     pub dataset_context: Option<Uuid>,
-}
\ No newline at end of file
+}
+
+/// 本体目录条目：提供给 LLM 的 T-Box 摘要（不含物理表与 SQL 表达式）
+#[derive(Debug, Serialize, Clone)]
+pub struct CatalogEntry {
+    pub node_key: String,
+    pub label: String,
+    pub node_role: String,
+    pub semantic_type: String,
+    pub alias_names: Vec<String>,
+    // 仅指标有值：该指标支持切片的维度 node_key 列表
+    pub supported_dimensions: Vec<String>,
+}
+
+/// LLM 返回的结构化逻辑计划（以 node_key 引用本体节点，禁止携带 SQL）
+/// 经 T-Box / A-Box 校验后解析为 QueryLogicalPlan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogicalPlanDraft {
+    pub metric: String,
+    #[serde(default)]
+    pub filters: Vec<PlanFilterDraft>,
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    #[serde(default)]
+    pub agg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanFilterDraft {
+    pub dimension: String,
+    pub value: String,
+}
+
+/// A-Box 维度码值（value_code 用于 SQL 过滤，value_label 与同义词用于识别提问）
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct DimensionValue {
+    pub id: i32,
+    pub dimension_node_id: Uuid,
+    pub value_label: String,
+    pub value_code: String,
+    /// 归一化后的标签（全半角、大小写、后缀剥离），用于容错匹配
+    pub normalized_label: Option<String>,
+    pub is_active: bool,
+    pub label_locked: bool,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    #[sqlx(default)]
+    pub synonyms: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DimensionValueQuery {
+    #[serde(default)]
+    pub include_inactive: bool,
+    pub keyword: Option<String>,
+}
+
+/// 维度标签来源：码表 (lookup_table + lookup_code_column) 或维度所在表的标签列
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct DimensionLabelSource {
+    #[serde(default)]
+    pub dimension_node_id: Uuid,
+    pub label_expression: String,
+    pub lookup_table: Option<String>,
+    pub lookup_code_column: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateValueLabelRequest {
+    pub label: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddSynonymRequest {
+    pub synonym: String,
+}
+
+/// A-Box 同步运行记录
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct SyncRun {
+    pub id: Uuid,
+    pub dimension_node_id: Uuid,
+    pub trigger: String, // MANUAL / SCHEDULED
+    pub status: String,  // RUNNING / SUCCESS / FAILED
+    pub scanned_count: i32,
+    pub inserted_count: i32,
+    pub reactivated_count: i32,
+    pub deactivated_count: i32,
+    #[sqlx(default)]
+    pub relabeled_count: i32,
+    #[sqlx(default)]
+    pub label_conflict_count: i32, // 标签与其他码值重复、退化为以码值作标签的数量
+    pub error: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncRunQuery {
+    pub node_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// 维度同步调度
+#[derive(Debug, Serialize, FromRow, Clone)]
+pub struct SyncSchedule {
+    pub dimension_node_id: Uuid,
+    pub interval_secs: i32,
+    pub enabled: bool,
+    pub next_run_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertSyncScheduleRequest {
+    pub interval: String, // "30m" / "6h" / "1d" 或纯秒数，最短 60 秒
+    pub enabled: Option<bool>,
+}
+
+/// 影子执行差异记录
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct ShadowRun {
+    pub id: Uuid,
+    pub question: String,
+    pub provider: String,
+    pub rule_plan: serde_json::Value,
+    pub llm_plan: Option<serde_json::Value>,
+    pub rule_hash: String,
+    pub llm_hash: Option<String>,
+    pub llm_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 用户对某次问答的反馈（点赞/点踩 + 可选纠正）
+#[derive(Debug, Deserialize)]
+pub struct FeedbackRequest {
+    pub query_id: Uuid,
+    pub rating: String, // UP / DOWN
+    pub comment: Option<String>,
+    pub corrected_metric: Option<String>,    // 正确指标 node_key
+    pub corrected_dimension: Option<String>, // 正确维度 node_key
+    pub corrected_value: Option<String>,     // 正确维度码值
+    pub phrase: Option<String>,              // 用户的口语表达，采纳后沉淀为别名或码值标签
+}
+
+/// 审核反馈：ACCEPT 时可由审核人补充/修正口语表达
+#[derive(Debug, Deserialize)]
+pub struct ReviewFeedbackRequest {
+    pub action: String, // ACCEPT / REJECT
+    pub phrase: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct QueryFeedback {
+    pub id: Uuid,
+    pub query_id: Uuid,
+    #[sqlx(default)]
+    pub question: Option<String>,
+    pub rating: String,
+    pub comment: Option<String>,
+    pub corrected_metric: Option<String>,
+    pub corrected_dimension: Option<String>,
+    pub corrected_value: Option<String>,
+    pub phrase: Option<String>,
+    pub status: String, // PENDING / ACCEPTED / REJECTED
+    pub review_note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 问答审计日志
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct QueryLog {
+    pub id: Uuid,
+    pub question: String,
+    pub session_id: Option<String>,
+    pub plan: Option<serde_json::Value>,
+    pub inference_mode: Option<String>,
+    pub compiled_sql: Option<String>,
+    pub source_id: Option<String>,
+    pub latency_ms: Option<i64>,
+    pub row_count: Option<i32>,
+    pub status: String,
+    pub error: Option<String>,
+    pub replay_of: Option<Uuid>,
+    #[sqlx(default)]
+    pub user_id: Option<String>,
+    #[sqlx(default)]
+    pub domain_key: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 问答日志检索条件
+#[derive(Debug, Deserialize)]
+pub struct QueryLogSearch {
+    pub session_id: Option<String>,
+    pub status: Option<String>,
+    pub source_id: Option<String>,
+    pub user_id: Option<String>,
+    pub keyword: Option<String>, // 提问内容模糊匹配
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,420 @@
+use crate::models::schema::FullSemanticNode;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// 小表格直接逐行播报的上限，超出后仅播报前 N 行
+const SMALL_TABLE_LIMIT: usize = 5;
+
+/// 维度码值的业务标签：维度列名 -> 码值 -> 标签；未收录的码值原样播报
+pub type ValueLabels = HashMap<String, HashMap<String, String>>;
+
+/// 基于模板的自然语言答案生成器
+/// 将物理查询结果集转写为中文结论句，例如："2025-12-24 A公司的收益为 12,500.00 元"
+/// 依次处理：空结果 -> 单值 -> 排名 -> 两两对比 -> 小表格
+pub fn summarize(
+    question: &str,
+    metric: &FullSemanticNode,
+    filters: &[(FullSemanticNode, String)],
+    agg: &str,
+    rows: &[Value],
+    labels: &ValueLabels,
+) -> String {
+    let metric_name = if agg == "AVG" {
+        format!("平均{}", metric.label)
+    } else {
+        metric.label.clone()
+    };
+
+    // 1. 结果集转为 (行描述, 指标值) 序列
+    let dim_columns = ordered_dimension_columns(filters, rows, &metric.label);
+    let items: Vec<(String, Option<f64>)> = rows
+        .iter()
+        .map(|row| {
+            let caption = dim_columns
+                .iter()
+                .filter_map(|c| row.get(c).and_then(value_to_text).map(|v| display_value(labels, c, v)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            (caption, row.get(&metric.label).and_then(value_to_f64))
+        })
+        .collect();
+
+    // 2. 空结果
+    if items.is_empty() {
+        let scope = filter_scope(filters, labels);
+        return if scope.is_empty() {
+            format!("未查询到{}的相关数据", metric_name)
+        } else {
+            format!("未查询到 {} 的{}数据", scope, metric_name)
+        };
+    }
+
+    // 3. 单值：过滤条件即为答案的主语
+    if items.len() == 1 {
+        let (caption, value) = &items[0];
+        let subject = if caption.is_empty() { filter_scope(filters, labels) } else { caption.clone() };
+        let value_text = format_metric_value(metric, *value);
+        return if subject.is_empty() {
+            format!("{}为 {}", metric_name, value_text)
+        } else {
+            format!("{}的{}为 {}", subject, metric_name, value_text)
+        };
+    }
+
+    // 4. 排名：提问中出现排名类意图时按指标值排序
+    if let Some(descending) = ranking_intent(question) {
+        // 缺失值无论升降序都排在末尾，避免"最低"落到空值行上
+        let mut ranked = items.clone();
+        ranked.sort_by(|a, b| match (a.1, b.1) {
+            (Some(x), Some(y)) if descending => y.total_cmp(&x),
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        let head = &ranked[0];
+        let extreme = if descending { "最高" } else { "最低" };
+        let listing = ranked
+            .iter()
+            .take(SMALL_TABLE_LIMIT)
+            .enumerate()
+            .map(|(i, (c, v))| format!("第{}名 {}（{}）", i + 1, c, format_metric_value(metric, *v)))
+            .collect::<Vec<_>>()
+            .join("，");
+        return format!(
+            "{}{}的是 {}，为 {}。排名：{}",
+            metric_name,
+            extreme,
+            head.0,
+            format_metric_value(metric, head.1),
+            listing
+        );
+    }
+
+    // 5. 两两对比：给出差值与变化率
+    if items.len() == 2 {
+        let (a, b) = (&items[0], &items[1]);
+        let mut text = format!(
+            "{}的{}为 {}，{}为 {}",
+            a.0,
+            metric_name,
+            format_metric_value(metric, a.1),
+            b.0,
+            format_metric_value(metric, b.1)
+        );
+        if let (Some(x), Some(y)) = (a.1, b.1) {
+            let diff = x - y;
+            let direction = if diff >= 0.0 { "高" } else { "低" };
+            text.push_str(&format!(
+                "，{}比{}{} {}",
+                a.0,
+                b.0,
+                direction,
+                format_metric_value(metric, Some(diff.abs()))
+            ));
+            if y != 0.0 {
+                text.push_str(&format!("（{:+.1}%）", diff / y.abs() * 100.0));
+            }
+        }
+        return text;
+    }
+
+    // 6. 小表格：逐行播报，超出上限时截断并给出合计
+    let listing = items
+        .iter()
+        .take(SMALL_TABLE_LIMIT)
+        .map(|(c, v)| format!("{} {}", c, format_metric_value(metric, *v)))
+        .collect::<Vec<_>>()
+        .join("；");
+    let mut text = if items.len() > SMALL_TABLE_LIMIT {
+        format!("共 {} 条{}结果，前 {} 条为：{}", items.len(), metric_name, SMALL_TABLE_LIMIT, listing)
+    } else {
+        format!("共 {} 条{}结果：{}", items.len(), metric_name, listing)
+    };
+    if agg == "SUM" || agg == "COUNT" {
+        let total: f64 = items.iter().filter_map(|(_, v)| *v).sum();
+        text.push_str(&format!("，合计 {}", format_metric_value(metric, Some(total))));
+    }
+    text
+}
+
+/// 按指标的单位与数值格式渲染单个值
+pub fn format_metric_value(metric: &FullSemanticNode, value: Option<f64>) -> String {
+    let Some(v) = value else {
+        return "暂无数据".to_string();
+    };
+    let number = match metric.number_format.as_deref() {
+        Some(pattern) if !pattern.is_empty() => format_number(v, pattern),
+        // 未配置格式时：整数不带小数，其余保留两位
+        _ if v.fract() == 0.0 => format_number(v, "#,##0"),
+        _ => format_number(v, "#,##0.00"),
+    };
+    match metric.unit.as_deref() {
+        Some(u) if !u.is_empty() && u != "%" => format!("{} {}", number, u),
+        Some(u) if !u.is_empty() => format!("{}{}", number, u),
+        _ => number,
+    }
+}
+
+/// 按 "#,##0.00" / "0.0%" / "0" 风格的模板格式化数值
+/// 支持：千分位（含 ","）、小数位数（"." 之后的 0 个数）、百分比（以 "%" 结尾）
+pub fn format_number(value: f64, pattern: &str) -> String {
+    let percent = pattern.ends_with('%');
+    let v = if percent { value * 100.0 } else { value };
+    let decimals = pattern
+        .split_once('.')
+        .map(|(_, frac)| frac.chars().filter(|c| *c == '0' || *c == '#').count())
+        .unwrap_or(0);
+    let raw = format!("{:.*}", decimals, v.abs());
+    let (int_part, frac_part) = match raw.split_once('.') {
+        Some((i, f)) => (i.to_string(), Some(f.to_string())),
+        None => (raw.clone(), None),
+    };
+
+    let int_part = if pattern.contains(',') {
+        let digits: Vec<char> = int_part.chars().collect();
+        let mut grouped = String::new();
+        for (i, ch) in digits.iter().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(*ch);
+        }
+        grouped
+    } else {
+        int_part
+    };
+
+    let mut out = String::new();
+    if v < 0.0 && raw.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    out.push_str(&int_part);
+    if let Some(f) = frac_part {
+        out.push('.');
+        out.push_str(&f);
+    }
+    if percent {
+        out.push('%');
+    }
+    out
+}
+
+/// 识别排名意图：Some(true) 为降序（最高/最多），Some(false) 为升序（最低/最少）
+fn ranking_intent(question: &str) -> Option<bool> {
+    if ["最低", "最少", "最小", "倒数"].iter().any(|k| question.contains(k)) {
+        return Some(false);
+    }
+    if ["排名", "排行", "最高", "最多", "最大", "前几", "top", "TOP", "Top"]
+        .iter()
+        .any(|k| question.contains(k))
+    {
+        return Some(true);
+    }
+    None
+}
+
+/// 结果集中的维度列：日期类维度在前（符合"时间 + 主体"的中文语序），其余保持原顺序
+fn ordered_dimension_columns(
+    filters: &[(FullSemanticNode, String)],
+    rows: &[Value],
+    metric_label: &str,
+) -> Vec<String> {
+    let mut columns: Vec<(bool, String)> = filters
+        .iter()
+        .map(|(d, _)| (d.semantic_type == "DATE", d.label.clone()))
+        .collect();
+    if let Some(Value::Object(first)) = rows.first() {
+        for key in first.keys() {
+            if key != metric_label && !columns.iter().any(|(_, c)| c == key) {
+                columns.push((false, key.clone()));
+            }
+        }
+    }
+    columns.sort_by_key(|(is_date, _)| !*is_date);
+    columns
+        .into_iter()
+        .map(|(_, c)| c)
+        .filter(|c| rows.iter().any(|r| r.get(c).is_some()))
+        .collect()
+}
+
+/// 由过滤条件拼出答案主语（日期在前，码值换为业务标签）
+fn filter_scope(filters: &[(FullSemanticNode, String)], labels: &ValueLabels) -> String {
+    let mut parts: Vec<&(FullSemanticNode, String)> = filters.iter().collect();
+    parts.sort_by_key(|(d, _)| d.semantic_type != "DATE");
+    parts
+        .iter()
+        .map(|(d, v)| display_value(labels, &d.label, v.clone()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn display_value(labels: &ValueLabels, column: &str, code: String) -> String {
+    labels.get(column).and_then(|m| m.get(&code)).cloned().unwrap_or(code)
+}
+
+fn value_to_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn value_to_text(v: &Value) -> Option<String> {
+    match v {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(label: &str, semantic_type: &str, unit: Option<&str>, number_format: Option<&str>) -> FullSemanticNode {
+        FullSemanticNode {
+            unit: unit.map(str::to_string),
+            number_format: number_format.map(str::to_string),
+            ..FullSemanticNode::stub(label, label, "METRIC", semantic_type)
+        }
+    }
+
+    fn revenue() -> FullSemanticNode {
+        node("收益", "NUMBER", None, None)
+    }
+
+    fn rows(pairs: &[(&str, Value)]) -> Vec<Value> {
+        pairs.iter().map(|(p, v)| json!({ "平台": p, "收益": v })).collect()
+    }
+
+    #[test]
+    fn format_number_patterns() {
+        let cases = [
+            (1234567.891, "#,##0.00", "1,234,567.89"),
+            (-1234.5, "#,##0.00", "-1,234.50"),
+            (0.0, "#,##0.00", "0.00"),
+            (100.0, "#,##0", "100"),
+            (-1e9, "#,##0", "-1,000,000,000"),
+            (1e15, "#,##0", "1,000,000,000,000,000"),
+            (1234.5, "0.0", "1234.5"),
+            (0.1234, "0.0%", "12.3%"),
+            (-0.0001, "0.00", "0.00"),
+        ];
+        for (value, pattern, expected) in cases {
+            assert_eq!(format_number(value, pattern), expected, "{} with {}", value, pattern);
+        }
+    }
+
+    #[test]
+    fn format_metric_value_units_and_defaults() {
+        assert_eq!(format_metric_value(&revenue(), None), "暂无数据");
+        let yuan = node("收益", "NUMBER", Some("元"), None);
+        assert_eq!(format_metric_value(&yuan, Some(12500.0)), "12,500 元");
+        assert_eq!(format_metric_value(&yuan, Some(1234567890.5)), "1,234,567,890.50 元");
+        assert_eq!(format_metric_value(&yuan, Some(-3.256)), "-3.26 元");
+        let rate = node("占比", "NUMBER", Some("%"), Some("0.0"));
+        assert_eq!(format_metric_value(&rate, Some(12.54)), "12.5%");
+        let formatted = node("收益", "NUMBER", None, Some("#,##0.00"));
+        assert_eq!(format_metric_value(&formatted, Some(7.0)), "7.00");
+    }
+
+    #[test]
+    fn summarize_empty_result() {
+        let platform = node("平台", "STRING", None, None);
+        assert_eq!(summarize("收益", &revenue(), &[], "SUM", &[], &ValueLabels::new()), "未查询到收益的相关数据");
+        assert_eq!(summarize("平均收益", &revenue(), &[], "AVG", &[], &ValueLabels::new()), "未查询到平均收益的相关数据");
+        assert_eq!(
+            summarize("A公司收益", &revenue(), &[(platform, "A公司".to_string())], "SUM", &[], &ValueLabels::new()),
+            "未查询到 A公司 的收益数据"
+        );
+    }
+
+    #[test]
+    fn summarize_single_value() {
+        let yuan = node("收益", "NUMBER", Some("元"), Some("#,##0.00"));
+        assert_eq!(summarize("收益", &yuan, &[], "SUM", &[json!({ "收益": "24400.50" })], &ValueLabels::new()), "收益为 24,400.50 元");
+
+        let platform = node("平台", "STRING", None, None);
+        let date = node("日期", "DATE", None, None);
+        let filters = [(platform, "A公司".to_string()), (date, "2025-12-24".to_string())];
+        assert_eq!(
+            summarize("收益", &revenue(), &filters, "SUM", &[json!({ "收益": null })], &ValueLabels::new()),
+            "2025-12-24 A公司的收益为 暂无数据"
+        );
+    }
+
+    #[test]
+    fn summarize_uses_business_labels_for_codes() {
+        let platform = node("平台", "STRING", None, None);
+        let labels: ValueLabels =
+            HashMap::from([("平台".to_string(), HashMap::from([("PLAT_01".to_string(), "A公司".to_string())]))]);
+        let filters = [(platform, "PLAT_01".to_string())];
+        assert_eq!(
+            summarize("收益", &revenue(), &filters, "SUM", &[json!({ "收益": 5 })], &labels),
+            "A公司的收益为 5"
+        );
+        assert_eq!(summarize("收益", &revenue(), &filters, "SUM", &[], &labels), "未查询到 A公司 的收益数据");
+        // 分组结果中的码值同样换为标签，未收录的码值原样播报
+        let data = rows(&[("PLAT_01", json!(3)), ("PLAT_99", json!(1))]);
+        assert_eq!(
+            summarize("收益", &revenue(), &[], "SUM", &data, &labels),
+            "A公司的收益为 3，PLAT_99为 1，A公司比PLAT_99高 2（+200.0%）"
+        );
+    }
+
+    #[test]
+    fn summarize_comparison_with_negative_value() {
+        let data = rows(&[("A", json!(100)), ("B", json!(-50))]);
+        assert_eq!(
+            summarize("A和B的收益", &revenue(), &[], "SUM", &data, &ValueLabels::new()),
+            "A的收益为 100，B为 -50，A比B高 150（+300.0%）"
+        );
+        let data = rows(&[("A", json!(10)), ("B", json!(0))]);
+        assert_eq!(summarize("收益", &revenue(), &[], "SUM", &data, &ValueLabels::new()), "A的收益为 10，B为 0，A比B高 10");
+    }
+
+    #[test]
+    fn summarize_ranking() {
+        let data = rows(&[("A", json!(1)), ("B", json!(3)), ("C", json!(2))]);
+        assert_eq!(
+            summarize("收益最高的平台", &revenue(), &[], "SUM", &data, &ValueLabels::new()),
+            "收益最高的是 B，为 3。排名：第1名 B（3），第2名 C（2），第3名 A（1）"
+        );
+        assert!(summarize("收益最低的平台", &revenue(), &[], "SUM", &data, &ValueLabels::new()).starts_with("收益最低的是 A"));
+
+        // 空值在升降序下都排在末尾
+        let data = rows(&[("A", json!(null)), ("B", json!(3)), ("C", json!(-2))]);
+        assert_eq!(
+            summarize("收益最低的平台", &revenue(), &[], "SUM", &data, &ValueLabels::new()),
+            "收益最低的是 C，为 -2。排名：第1名 C（-2），第2名 B（3），第3名 A（暂无数据）"
+        );
+        assert_eq!(
+            summarize("收益最高的平台", &revenue(), &[], "SUM", &data, &ValueLabels::new()),
+            "收益最高的是 B，为 3。排名：第1名 B（3），第2名 C（-2），第3名 A（暂无数据）"
+        );
+    }
+
+    #[test]
+    fn summarize_multi_row_table() {
+        let data = rows(&[("A", json!(1)), ("B", json!(2)), ("C", json!(null))]);
+        assert_eq!(
+            summarize("各平台收益", &revenue(), &[], "SUM", &data, &ValueLabels::new()),
+            "共 3 条收益结果：A 1；B 2；C 暂无数据，合计 3"
+        );
+        assert_eq!(
+            summarize("各平台平均收益", &revenue(), &[], "AVG", &data, &ValueLabels::new()),
+            "共 3 条平均收益结果：A 1；B 2；C 暂无数据"
+        );
+
+        let many: Vec<Value> = (1..=7).map(|i| json!({ "平台": format!("P{}", i), "收益": i })).collect();
+        assert_eq!(
+            summarize("各平台收益", &revenue(), &[], "SUM", &many, &ValueLabels::new()),
+            "共 7 条收益结果，前 5 条为：P1 1；P2 2；P3 3；P4 4；P5 5，合计 28"
+        );
+    }
+}
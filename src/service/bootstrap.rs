@@ -0,0 +1,81 @@
+use crate::models::schema::{ColumnMetadata, CreateNodeRequest, DraftNode, OntologyDraft, TableMetadata};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// 由表元数据推断本体草稿
+/// 规则：主键跳过；外键与 *_id 列 -> 维度；数值列 -> 指标 (SUM)；其余 -> 维度（语义类型按物理类型推断）
+/// 标签优先取列注释，草稿内全部指标默认关联全部维度
+pub fn draft_from_table(
+    source_id: &str,
+    target_table: &str,
+    meta: &TableMetadata,
+    dataset_id: Option<Uuid>,
+    existing_keys: &HashSet<String>,
+) -> OntologyDraft {
+    let mut metrics = Vec::new();
+    let mut dimensions = Vec::new();
+    let mut skipped_columns = Vec::new();
+
+    for col in &meta.columns {
+        if col.is_primary_key {
+            skipped_columns.push(col.name.clone());
+            continue;
+        }
+        let is_metric = col.suggested_semantic_type == "NUMBER" && !is_key_column(col);
+        let semantic_type = if is_key_column(col) { "STRING" } else { col.suggested_semantic_type.as_str() };
+        let node = CreateNodeRequest {
+            node_key: node_key(&meta.table_name, &col.name),
+            label: col.comment.clone().unwrap_or_else(|| col.name.clone()),
+            node_role: if is_metric { "METRIC" } else { "DIMENSION" }.to_string(),
+            semantic_type: semantic_type.to_string(),
+            source_id: source_id.to_string(),
+            target_table: target_table.to_string(),
+            sql_expression: col.name.clone(),
+            alias_names: if col.comment.is_some() { vec![col.name.clone()] } else { Vec::new() },
+            default_constraints: Vec::new(),
+            supported_dimension_ids: Vec::new(),
+            default_agg: if is_metric { "SUM" } else { "NONE" }.to_string(),
+            dataset_id,
+            value_format: (semantic_type == "DATE").then(|| "yyyy-MM-dd".to_string()),
+            unit: None,
+            number_format: None,
+        };
+        let draft = DraftNode {
+            exists: existing_keys.contains(&node.node_key),
+            node,
+            supported_dimension_keys: Vec::new(),
+        };
+        if is_metric {
+            metrics.push(draft);
+        } else {
+            dimensions.push(draft);
+        }
+    }
+
+    let dim_keys: Vec<String> = dimensions.iter().map(|d| d.node.node_key.clone()).collect();
+    for m in &mut metrics {
+        m.supported_dimension_keys = dim_keys.clone();
+    }
+
+    OntologyDraft {
+        source_id: source_id.to_string(),
+        target_table: target_table.to_string(),
+        nodes: dimensions.into_iter().chain(metrics).collect(),
+        skipped_columns,
+    }
+}
+
+/// 外键或 *_id 命名的列是关联键，即使为数值也不做指标
+fn is_key_column(col: &ColumnMetadata) -> bool {
+    let name = col.name.to_lowercase();
+    col.foreign_key.is_some() || name == "id" || name.ends_with("_id")
+}
+
+/// node_key = 表名_列名（非字母数字字符替换为下划线）
+fn node_key(table: &str, column: &str) -> String {
+    format!("{}_{}", table, column)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
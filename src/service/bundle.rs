@@ -0,0 +1,511 @@
+use crate::api::mapping::{load_all_nodes, write_node};
+use crate::core::normalize::normalize_value;
+use crate::models::bundle::{
+    BundleChange, BundleDataSource, BundleDataset, BundleDomain, BundleImportReport, BundleLabelSource, BundleNode,
+    BundleRelation, BundleValue, OntologyBundle, BUNDLE_FORMAT_VERSION,
+};
+use crate::models::revision::Author;
+use crate::models::schema::CreateNodeRequest;
+use crate::service::revision;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+use uuid::Uuid;
+
+const NODE_ROLES: [&str; 2] = ["METRIC", "DIMENSION"];
+const SEMANTIC_TYPES: [&str; 3] = ["STRING", "NUMBER", "DATE"];
+const RELATION_ORIGINS: [&str; 3] = ["FK", "HEURISTIC", "MANUAL"];
+const RELATION_STATUSES: [&str; 3] = ["PROPOSED", "ACCEPTED", "REJECTED"];
+
+// --- 1. 导出 ---
+
+/// 将当前库中的本体导出为本体包（数据源不含连接串）
+pub async fn export(db: &PgPool, include_values: bool) -> anyhow::Result<OntologyBundle> {
+    let data_sources = sqlx::query("SELECT id, db_type, display_name FROM data_sources ORDER BY id")
+        .fetch_all(db)
+        .await?
+        .iter()
+        .map(|r| BundleDataSource { id: r.get(0), db_type: r.get(1), display_name: r.get(2) })
+        .collect();
+
+    let domains = sqlx::query("SELECT domain_key, label, description FROM semantic_domains ORDER BY domain_key")
+        .fetch_all(db)
+        .await?
+        .iter()
+        .map(|r| BundleDomain { domain_key: r.get(0), label: r.get(1), description: r.get(2) })
+        .collect();
+
+    let dataset_rows = sqlx::query(
+        "SELECT s.id, s.dataset_key, s.label, d.domain_key, COALESCE(s.join_config, '{}'::jsonb)
+         FROM semantic_datasets s LEFT JOIN semantic_domains d ON d.id = s.domain_id ORDER BY s.dataset_key",
+    )
+    .fetch_all(db)
+    .await?;
+    let dataset_keys: HashMap<Uuid, String> = dataset_rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+    let datasets = dataset_rows
+        .iter()
+        .map(|r| BundleDataset { dataset_key: r.get(1), label: r.get(2), domain_key: r.get(3), join_config: r.get(4) })
+        .collect();
+
+    let mut label_sources: HashMap<Uuid, BundleLabelSource> = sqlx::query(
+        "SELECT dimension_node_id, label_expression, lookup_table, lookup_code_column FROM dimension_label_sources",
+    )
+    .fetch_all(db)
+    .await?
+    .iter()
+    .map(|r| {
+        let ls = BundleLabelSource { label_expression: r.get(1), lookup_table: r.get(2), lookup_code_column: r.get(3) };
+        (r.get(0), ls)
+    })
+    .collect();
+
+    let mut values: HashMap<Uuid, Vec<BundleValue>> = HashMap::new();
+    if include_values {
+        let rows = sqlx::query(
+            "SELECT v.dimension_node_id, v.value_code, v.value_label, v.label_locked, v.is_active,
+                    COALESCE(array_agg(s.synonym ORDER BY s.synonym) FILTER (WHERE s.synonym IS NOT NULL), '{}')
+             FROM dimension_values v LEFT JOIN dimension_value_synonyms s ON s.value_id = v.id
+             GROUP BY v.id ORDER BY v.value_code",
+        )
+        .fetch_all(db)
+        .await?;
+        for r in rows {
+            values.entry(r.get(0)).or_default().push(BundleValue {
+                code: r.get(1),
+                label: r.get(2),
+                label_locked: r.get(3),
+                is_active: r.get(4),
+                synonyms: r.get(5),
+            });
+        }
+    }
+
+    let full_nodes = load_all_nodes(db).await?;
+    let node_keys: HashMap<Uuid, String> = full_nodes.iter().map(|n| (n.id, n.node_key.clone())).collect();
+    let mut nodes: Vec<BundleNode> = full_nodes
+        .into_iter()
+        .map(|n| {
+            let mut supported: Vec<String> =
+                n.supported_dimension_ids.iter().filter_map(|id| node_keys.get(id).cloned()).collect();
+            supported.sort();
+            BundleNode {
+                label_source: label_sources.remove(&n.id),
+                values: values.remove(&n.id).unwrap_or_default(),
+                dataset_key: n.dataset_id.and_then(|id| dataset_keys.get(&id).cloned()),
+                supported_dimensions: supported,
+                node_key: n.node_key,
+                label: n.label,
+                node_role: n.node_role,
+                semantic_type: n.semantic_type,
+                source_id: n.source_id,
+                target_table: n.target_table,
+                sql_expression: n.sql_expression,
+                alias_names: n.alias_names,
+                default_constraints: n.default_constraints.0,
+                default_agg: n.default_agg,
+                value_format: n.value_format,
+                unit: n.unit,
+                number_format: n.number_format,
+            }
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.node_key.cmp(&b.node_key));
+
+    let relations = sqlx::query(
+        "SELECT source_id, from_table, to_table, join_logic, origin, status FROM ontology_relations
+         WHERE source_id IS NOT NULL AND from_table IS NOT NULL AND to_table IS NOT NULL
+         ORDER BY source_id, from_table, to_table, join_logic",
+    )
+    .fetch_all(db)
+    .await?
+    .iter()
+    .map(|r| BundleRelation {
+        source_id: r.get(0),
+        from_table: r.get(1),
+        to_table: r.get(2),
+        join_logic: r.get(3),
+        origin: r.get(4),
+        status: r.get(5),
+    })
+    .collect();
+
+    Ok(OntologyBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: Some(chrono::Utc::now()),
+        data_sources,
+        domains,
+        datasets,
+        nodes,
+        relations,
+    })
+}
+
+// --- 2. 校验与差异预览 ---
+
+/// 校验本体包并计算与当前库的差异（不落库）
+/// 导入只做新增与更新：包中未出现的对象保持不变，同义词只增不删
+pub async fn plan(db: &PgPool, bundle: &OntologyBundle) -> anyhow::Result<BundleImportReport> {
+    let current = export(db, true).await?;
+    let mut report = BundleImportReport::default();
+    let errors = &mut report.errors;
+
+    if bundle.format_version > BUNDLE_FORMAT_VERSION {
+        errors.push(format!(
+            "不支持的本体包版本 {}（当前最高支持 {}）",
+            bundle.format_version, BUNDLE_FORMAT_VERSION
+        ));
+    }
+
+    // 数据源：连接串不随本体包迁移，必须已在目标环境注册且类型一致
+    let sources: HashMap<&str, &str> =
+        current.data_sources.iter().map(|s| (s.id.as_str(), s.db_type.as_str())).collect();
+    for s in &bundle.data_sources {
+        match sources.get(s.id.as_str()) {
+            None => errors.push(format!("数据源 {} 未在目标环境注册（连接串不随本体包迁移）", s.id)),
+            Some(t) if !t.eq_ignore_ascii_case(&s.db_type) => {
+                errors.push(format!("数据源 {} 类型不一致: 包中为 {}，目标环境为 {}", s.id, s.db_type, t))
+            }
+            _ => {}
+        }
+    }
+    let require_source = |errors: &mut Vec<String>, id: &str, owner: &str| {
+        if !sources.contains_key(id) {
+            errors.push(format!("{} 引用的数据源 {} 未在目标环境注册", owner, id));
+        }
+    };
+
+    // 业务 Key 唯一性与引用完整性（引用可以指向包内对象，也可以指向目标环境已有对象）
+    let domain_keys = unique_keys(errors, "domain_key", bundle.domains.iter().map(|d| d.domain_key.as_str()));
+    let dataset_keys = unique_keys(errors, "dataset_key", bundle.datasets.iter().map(|d| d.dataset_key.as_str()));
+    unique_keys(errors, "node_key", bundle.nodes.iter().map(|n| n.node_key.as_str()));
+
+    for d in &bundle.datasets {
+        if let Some(k) = &d.domain_key {
+            if !domain_keys.contains(k.as_str()) && !current.domains.iter().any(|c| &c.domain_key == k) {
+                errors.push(format!("数据集 {} 引用了不存在的业务域: {}", d.dataset_key, k));
+            }
+        }
+    }
+
+    let current_nodes: HashMap<&str, &BundleNode> = current.nodes.iter().map(|n| (n.node_key.as_str(), n)).collect();
+    let bundle_nodes: HashMap<&str, &BundleNode> = bundle.nodes.iter().map(|n| (n.node_key.as_str(), n)).collect();
+    for n in &bundle.nodes {
+        if !NODE_ROLES.contains(&n.node_role.as_str()) {
+            errors.push(format!("节点 {} 的 node_role 非法: {}", n.node_key, n.node_role));
+        }
+        if !SEMANTIC_TYPES.contains(&n.semantic_type.as_str()) {
+            errors.push(format!("节点 {} 的 semantic_type 非法: {}", n.node_key, n.semantic_type));
+        }
+        require_source(errors, &n.source_id, &format!("节点 {}", n.node_key));
+        if let Some(k) = &n.dataset_key {
+            if !dataset_keys.contains(k.as_str()) && !current.datasets.iter().any(|c| &c.dataset_key == k) {
+                errors.push(format!("节点 {} 引用了不存在的数据集: {}", n.node_key, k));
+            }
+        }
+        for k in &n.supported_dimensions {
+            let role = bundle_nodes
+                .get(k.as_str())
+                .or_else(|| current_nodes.get(k.as_str()))
+                .map(|d| d.node_role.as_str());
+            if role != Some("DIMENSION") {
+                errors.push(format!("指标 {} 引用了不存在的维度: {}", n.node_key, k));
+            }
+        }
+        if n.node_role != "METRIC" && !n.supported_dimensions.is_empty() {
+            errors.push(format!("只有指标可以声明 supported_dimensions: {}", n.node_key));
+        }
+        if n.node_role != "DIMENSION" && (!n.values.is_empty() || n.label_source.is_some()) {
+            errors.push(format!("只有维度可以携带码值与标签来源: {}", n.node_key));
+        }
+        let (mut codes, mut labels) = (HashSet::new(), HashSet::new());
+        for v in &n.values {
+            if !codes.insert(v.code.as_str()) {
+                errors.push(format!("维度 {} 的码值重复: {}", n.node_key, v.code));
+            }
+            if !labels.insert(v.label.as_str()) {
+                errors.push(format!("维度 {} 的码值标签重复: {}", n.node_key, v.label));
+            }
+        }
+    }
+    for r in &bundle.relations {
+        require_source(errors, &r.source_id, &format!("关联 {} -> {}", r.from_table, r.to_table));
+        if !RELATION_ORIGINS.contains(&r.origin.as_str()) || !RELATION_STATUSES.contains(&r.status.as_str()) {
+            errors.push(format!("关联 {} -> {} 的 origin/status 非法", r.from_table, r.to_table));
+        }
+    }
+
+    // 差异：按业务 Key 与当前库逐项比对
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let mut diff = |kind: &str, key: String, existing: Option<bool>| match existing {
+        None => changes.push(BundleChange { kind: kind.into(), key, action: "CREATE".into() }),
+        Some(false) => changes.push(BundleChange { kind: kind.into(), key, action: "UPDATE".into() }),
+        Some(true) => unchanged += 1,
+    };
+
+    for d in &bundle.domains {
+        let existing = current.domains.iter().find(|c| c.domain_key == d.domain_key);
+        diff("domain", d.domain_key.clone(), existing.map(|c| c == d));
+    }
+    for d in &bundle.datasets {
+        let existing = current.datasets.iter().find(|c| c.dataset_key == d.dataset_key);
+        diff("dataset", d.dataset_key.clone(), existing.map(|c| c == d));
+    }
+    for n in &bundle.nodes {
+        let existing = current_nodes.get(n.node_key.as_str());
+        diff("node", n.node_key.clone(), existing.map(|c| same_definition(c, n)));
+        let current_values: HashMap<&str, &BundleValue> = existing
+            .map(|c| c.values.iter().map(|v| (v.code.as_str(), v)).collect())
+            .unwrap_or_default();
+        for v in &n.values {
+            let existing = current_values.get(v.code.as_str()).map(|c| {
+                c.label == v.label
+                    && c.label_locked == v.label_locked
+                    && c.is_active == v.is_active
+                    && v.synonyms.iter().all(|s| c.synonyms.contains(s))
+            });
+            diff("value", format!("{}:{}", n.node_key, v.code), existing);
+        }
+    }
+    for r in &bundle.relations {
+        let existing = current.relations.iter().find(|c| {
+            c.source_id == r.source_id && c.from_table == r.from_table && c.to_table == r.to_table && c.join_logic == r.join_logic
+        });
+        diff(
+            "relation",
+            format!("{}:{} -> {}", r.source_id, r.from_table, r.to_table),
+            existing.map(|c| c == r),
+        );
+    }
+
+    report.changes = changes;
+    report.unchanged = unchanged;
+    Ok(report)
+}
+
+fn unique_keys<'a>(errors: &mut Vec<String>, what: &str, keys: impl Iterator<Item = &'a str>) -> HashSet<&'a str> {
+    let mut seen = HashSet::new();
+    for k in keys {
+        if !seen.insert(k) {
+            errors.push(format!("本体包中 {} 重复: {}", what, k));
+        }
+    }
+    seen
+}
+
+/// 节点定义比对（不含码值，码值单独比对）
+fn same_definition(a: &BundleNode, b: &BundleNode) -> bool {
+    let strip = |n: &BundleNode| {
+        let mut n = n.clone();
+        n.values.clear();
+        n.supported_dimensions.sort();
+        n
+    };
+    strip(a) == strip(b)
+}
+
+// --- 3. 落库 ---
+
+/// 在单个事务中应用本体包（调用方需先通过 plan 校验）
+pub async fn apply(db: &PgPool, bundle: &OntologyBundle, author: &Author) -> anyhow::Result<()> {
+    let mut tx = db.begin().await?;
+
+    let mut domain_ids: HashMap<String, Uuid> = HashMap::new();
+    for d in &bundle.domains {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO semantic_domains (domain_key, label, description) VALUES ($1, $2, $3)
+             ON CONFLICT (domain_key) DO UPDATE SET label = EXCLUDED.label, description = EXCLUDED.description
+             RETURNING id",
+        )
+        .bind(&d.domain_key)
+        .bind(&d.label)
+        .bind(&d.description)
+        .fetch_one(&mut *tx)
+        .await?;
+        domain_ids.insert(d.domain_key.clone(), id);
+    }
+
+    let mut dataset_ids: HashMap<String, Uuid> = HashMap::new();
+    for d in &bundle.datasets {
+        let domain_id = match &d.domain_key {
+            Some(k) => Some(resolve_id(&mut tx, &domain_ids, "SELECT id FROM semantic_domains WHERE domain_key = $1", k).await?),
+            None => None,
+        };
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO semantic_datasets (domain_id, dataset_key, label, join_config) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (dataset_key) DO UPDATE SET domain_id = EXCLUDED.domain_id, label = EXCLUDED.label, join_config = EXCLUDED.join_config
+             RETURNING id",
+        )
+        .bind(domain_id)
+        .bind(&d.dataset_key)
+        .bind(&d.label)
+        .bind(&d.join_config)
+        .fetch_one(&mut *tx)
+        .await?;
+        dataset_ids.insert(d.dataset_key.clone(), id);
+    }
+
+    // 先写维度再写指标，指标的维度引用才能解析到本包内新建的维度
+    let mut node_ids: HashMap<String, Uuid> = HashMap::new();
+    let (dims, metrics): (Vec<_>, Vec<_>) = bundle.nodes.iter().partition(|n| n.node_role == "DIMENSION");
+    for n in dims.into_iter().chain(metrics) {
+        let dataset_id = match &n.dataset_key {
+            Some(k) => Some(resolve_id(&mut tx, &dataset_ids, "SELECT id FROM semantic_datasets WHERE dataset_key = $1", k).await?),
+            None => None,
+        };
+        let mut supported_dimension_ids = Vec::with_capacity(n.supported_dimensions.len());
+        for k in &n.supported_dimensions {
+            supported_dimension_ids
+                .push(resolve_id(&mut tx, &node_ids, "SELECT id FROM ontology_nodes WHERE node_key = $1 AND deleted_at IS NULL", k).await?);
+        }
+        let req = CreateNodeRequest {
+            node_key: n.node_key.clone(),
+            label: n.label.clone(),
+            node_role: n.node_role.clone(),
+            semantic_type: n.semantic_type.clone(),
+            source_id: n.source_id.clone(),
+            target_table: n.target_table.clone(),
+            sql_expression: n.sql_expression.clone(),
+            alias_names: n.alias_names.clone(),
+            default_constraints: n.default_constraints.clone(),
+            supported_dimension_ids,
+            default_agg: n.default_agg.clone(),
+            dataset_id,
+            value_format: n.value_format.clone(),
+            unit: n.unit.clone(),
+            number_format: n.number_format.clone(),
+        };
+        let id = write_node(&mut tx, &req, author).await.map_err(anyhow::Error::msg)?;
+        node_ids.insert(n.node_key.clone(), id);
+
+        if n.node_role == "DIMENSION" {
+            write_label_source(&mut tx, id, n.label_source.as_ref()).await?;
+            write_values(&mut tx, id, &n.values).await?;
+        }
+    }
+
+    for r in &bundle.relations {
+        sqlx::query(
+            "INSERT INTO ontology_relations (source_id, from_table, to_table, join_logic, origin, status)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (source_id, from_table, to_table, join_logic) DO UPDATE SET origin = EXCLUDED.origin, status = EXCLUDED.status",
+        )
+        .bind(&r.source_id)
+        .bind(&r.from_table)
+        .bind(&r.to_table)
+        .bind(&r.join_logic)
+        .bind(&r.origin)
+        .bind(&r.status)
+        .execute(&mut *tx)
+        .await?;
+    }
+    revision::record_relations(&mut tx, author).await?;
+
+    tx.commit().await?;
+    info!(
+        "📦 本体包导入完成: {} 个业务域, {} 个数据集, {} 个节点, {} 条关联",
+        bundle.domains.len(),
+        bundle.datasets.len(),
+        bundle.nodes.len(),
+        bundle.relations.len()
+    );
+    Ok(())
+}
+
+/// 业务 Key -> UUID：优先取本包内刚写入的对象，其次查库
+async fn resolve_id(
+    tx: &mut Transaction<'_, Postgres>,
+    written: &HashMap<String, Uuid>,
+    sql: &str,
+    key: &str,
+) -> anyhow::Result<Uuid> {
+    if let Some(id) = written.get(key) {
+        return Ok(*id);
+    }
+    sqlx::query_scalar(sql)
+        .bind(key)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("引用的对象不存在: {}", key))
+}
+
+async fn write_label_source(
+    tx: &mut Transaction<'_, Postgres>,
+    node_id: Uuid,
+    source: Option<&BundleLabelSource>,
+) -> anyhow::Result<()> {
+    match source {
+        Some(ls) => {
+            sqlx::query(
+                "INSERT INTO dimension_label_sources (dimension_node_id, label_expression, lookup_table, lookup_code_column)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (dimension_node_id) DO UPDATE SET label_expression = EXCLUDED.label_expression,
+                     lookup_table = EXCLUDED.lookup_table, lookup_code_column = EXCLUDED.lookup_code_column",
+            )
+            .bind(node_id)
+            .bind(&ls.label_expression)
+            .bind(&ls.lookup_table)
+            .bind(&ls.lookup_code_column)
+            .execute(&mut **tx)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM dimension_label_sources WHERE dimension_node_id = $1")
+                .bind(node_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// 码值按 (维度, 码值) 合并写入，同义词只增不删
+async fn write_values(tx: &mut Transaction<'_, Postgres>, node_id: Uuid, values: &[BundleValue]) -> anyhow::Result<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let codes: Vec<&str> = values.iter().map(|v| v.code.as_str()).collect();
+    let labels: Vec<&str> = values.iter().map(|v| v.label.as_str()).collect();
+    let norms: Vec<String> = values.iter().map(|v| normalize_value(&v.label)).collect();
+    let locked: Vec<bool> = values.iter().map(|v| v.label_locked).collect();
+    let active: Vec<bool> = values.iter().map(|v| v.is_active).collect();
+    sqlx::query(
+        "INSERT INTO dimension_values (dimension_node_id, value_code, value_label, normalized_label, label_locked, is_active)
+         SELECT $1, b.code, b.label, b.norm, b.locked, b.active
+         FROM UNNEST($2::text[], $3::text[], $4::text[], $5::bool[], $6::bool[]) AS b(code, label, norm, locked, active)
+         ON CONFLICT (dimension_node_id, value_code) DO UPDATE SET value_label = EXCLUDED.value_label,
+             normalized_label = EXCLUDED.normalized_label, label_locked = EXCLUDED.label_locked, is_active = EXCLUDED.is_active",
+    )
+    .bind(node_id)
+    .bind(&codes)
+    .bind(&labels)
+    .bind(&norms)
+    .bind(&locked)
+    .bind(&active)
+    .execute(&mut **tx)
+    .await?;
+
+    let (mut syn_codes, mut synonyms, mut syn_norms) = (Vec::new(), Vec::new(), Vec::new());
+    for v in values {
+        for s in &v.synonyms {
+            syn_codes.push(v.code.as_str());
+            synonyms.push(s.as_str());
+            syn_norms.push(normalize_value(s));
+        }
+    }
+    if !synonyms.is_empty() {
+        sqlx::query(
+            "INSERT INTO dimension_value_synonyms (value_id, synonym, normalized)
+             SELECT v.id, b.synonym, b.norm FROM UNNEST($2::text[], $3::text[], $4::text[]) AS b(code, synonym, norm)
+             JOIN dimension_values v ON v.dimension_node_id = $1 AND v.value_code = b.code
+             ON CONFLICT (value_id, synonym) DO NOTHING",
+        )
+        .bind(node_id)
+        .bind(&syn_codes)
+        .bind(&synonyms)
+        .bind(&syn_norms)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use crate::models::context::{ChartField, ChartMeasure, ChartSpec, ChartType};
+use crate::models::schema::{FullSemanticNode, QueryLogicalPlan};
+
+/// 当前图表 Schema 版本
+pub const CHART_SPEC_VERSION: u32 = 1;
+/// 饼图最多展示的扇区数，超出后退化为柱状图
+const PIE_MAX_SLICES: usize = 8;
+/// 占比意图关键词
+const SHARE_KEYWORDS: [&str; 5] = ["占比", "份额", "构成", "比例", "比重"];
+
+/// 根据逻辑计划的维度语义类型与结果行数推荐图表
+/// 规则：单值 -> KPI；含日期分组 -> 折线；占比意图 -> 饼图；分类分组 -> 柱状；其余 -> 表格
+pub fn recommend(question: &str, plan: &QueryLogicalPlan, row_count: usize) -> ChartSpec {
+    let metric = &plan.metric;
+    let measure = ChartMeasure {
+        field: metric.label.clone(),
+        agg: plan.final_agg.clone(),
+        unit: metric.unit.clone(),
+        number_format: metric.number_format.clone(),
+    };
+
+    let date_dim = plan.group_by.iter().find(|d| d.semantic_type == "DATE");
+    let category_dims: Vec<&FullSemanticNode> = plan
+        .group_by
+        .iter()
+        .filter(|d| d.semantic_type != "DATE")
+        .collect();
+    let share_intent = SHARE_KEYWORDS.iter().any(|k| question.contains(k));
+
+    let (chart_type, x, series, reason) = if row_count == 1 && plan.group_by.is_empty() {
+        (ChartType::Kpi, None, None, "结果为单个数值".to_string())
+    } else if let Some(d) = date_dim.filter(|_| row_count > 1) {
+        (
+            ChartType::Line,
+            Some(field_of(d)),
+            category_dims.first().map(|c| field_of(c)),
+            format!("按时间维度 '{}' 展开的序列", d.label),
+        )
+    } else if category_dims.len() == 1 && row_count > 1 {
+        let d = category_dims[0];
+        if share_intent && row_count <= PIE_MAX_SLICES {
+            (ChartType::Pie, Some(field_of(d)), None, format!("'{}' 各成员的占比", d.label))
+        } else {
+            (ChartType::Bar, Some(field_of(d)), None, format!("按分类维度 '{}' 对比", d.label))
+        }
+    } else {
+        (ChartType::Table, None, None, "多维或明细结果，以表格呈现".to_string())
+    };
+
+    ChartSpec {
+        version: CHART_SPEC_VERSION,
+        chart_type,
+        title: metric.label.clone(),
+        x,
+        series,
+        y: vec![measure],
+        row_count,
+        reason,
+    }
+}
+
+fn field_of(node: &FullSemanticNode) -> ChartField {
+    ChartField {
+        field: node.label.clone(),
+        semantic_type: node.semantic_type.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(label: &str, role: &str, semantic_type: &str) -> FullSemanticNode {
+        FullSemanticNode { unit: Some("元".to_string()), ..FullSemanticNode::stub(label, label, role, semantic_type) }
+    }
+
+    /// (提问, 分组维度, 行数, 图表类型, x 轴, 系列)
+    type Case<'a> = (&'a str, &'a [(&'a str, &'a str)], usize, ChartType, Option<&'a str>, Option<&'a str>);
+
+    fn plan(group_by: &[(&str, &str)]) -> QueryLogicalPlan {
+        QueryLogicalPlan {
+            metric: node("收益", "METRIC", "NUMBER"),
+            dimensions: Vec::new(),
+            group_by: group_by.iter().map(|(l, t)| node(l, "DIMENSION", t)).collect(),
+            implicit_constraints: Vec::new(),
+            security_filters: Vec::new(),
+            final_agg: "SUM".to_string(),
+            dataset_context: None,
+        }
+    }
+
+    #[test]
+    fn recommend_chart_type() {
+        let date = ("日期", "DATE");
+        let platform = ("平台", "STRING");
+        let channel = ("渠道", "STRING");
+        let cases: [Case; 11] = [
+            ("收益", &[], 1, ChartType::Kpi, None, None),
+            ("收益", &[], 0, ChartType::Table, None, None),
+            ("收益趋势", &[date], 30, ChartType::Line, Some("日期"), None),
+            ("各平台收益趋势", &[platform, date], 60, ChartType::Line, Some("日期"), Some("平台")),
+            ("收益趋势", &[date], 1, ChartType::Table, None, None),
+            ("各平台收益", &[platform], 5, ChartType::Bar, Some("平台"), None),
+            ("各平台收益占比", &[platform], 5, ChartType::Pie, Some("平台"), None),
+            ("各平台收益份额", &[platform], PIE_MAX_SLICES, ChartType::Pie, Some("平台"), None),
+            ("各平台收益占比", &[platform], PIE_MAX_SLICES + 1, ChartType::Bar, Some("平台"), None),
+            ("各平台收益", &[platform], 1, ChartType::Table, None, None),
+            ("各平台各渠道收益", &[platform, channel], 12, ChartType::Table, None, None),
+        ];
+        for (question, group_by, rows, chart_type, x, series) in cases {
+            let spec = recommend(question, &plan(group_by), rows);
+            let ctx = format!("{} / {:?} / {} 行", question, group_by, rows);
+            assert_eq!(spec.chart_type, chart_type, "{}", ctx);
+            assert_eq!(spec.x.as_ref().map(|f| f.field.as_str()), x, "{}", ctx);
+            assert_eq!(spec.series.as_ref().map(|f| f.field.as_str()), series, "{}", ctx);
+            assert_eq!(spec.row_count, rows);
+        }
+    }
+
+    #[test]
+    fn recommend_carries_measure_format() {
+        let spec = recommend("收益", &plan(&[]), 1);
+        assert_eq!(spec.version, CHART_SPEC_VERSION);
+        assert_eq!(spec.title, "收益");
+        assert_eq!(spec.y.len(), 1);
+        assert_eq!(spec.y[0].field, "收益");
+        assert_eq!(spec.y[0].agg, "SUM");
+        assert_eq!(spec.y[0].unit.as_deref(), Some("元"));
+    }
+}
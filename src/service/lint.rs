@@ -0,0 +1,221 @@
+use crate::ax_state::AppState;
+use crate::models::revision::{LintReport, ValidationIssue};
+use crate::models::schema::FullSemanticNode;
+use crate::service::validation::{check_alias_collisions, check_dimension_refs, connect, issue, node_domains};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::OnceLock;
+use tracing::info;
+
+/// 默认约束可用的比较符：planner 按 `列 比较符 '值'` 渲染，因此只允许接单个字面量的比较符
+const VALID_OPERATORS: [&str; 10] = ["=", "!=", "<>", ">", ">=", "<", "<=", "LIKE", "NOT LIKE", "ILIKE"];
+
+/// 表达式中不视为列名的关键字与类型名
+const SQL_KEYWORDS: [&str; 54] = [
+    "AND", "OR", "NOT", "NULL", "IS", "IN", "AS", "CASE", "WHEN", "THEN", "ELSE", "END", "DISTINCT", "TRUE", "FALSE",
+    "LIKE", "ILIKE", "BETWEEN", "INTERVAL", "FROM", "FOR", "ON", "OVER", "PARTITION", "BY", "ASC", "DESC", "FILTER",
+    "WHERE", "YEAR", "QUARTER", "MONTH", "WEEK", "DAY", "HOUR", "MINUTE", "SECOND", "EPOCH", "INT", "INTEGER", "BIGINT",
+    "TEXT", "VARCHAR", "CHAR", "DATE", "TIMESTAMP", "TIME", "NUMERIC", "DECIMAL", "REAL", "FLOAT", "DOUBLE",
+    "PRECISION", "BOOLEAN",
+];
+
+const SEVERITIES: [&str; 3] = ["ERROR", "WARNING", "INFO"];
+
+fn rank(severity: &str) -> usize {
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(SEVERITIES.len())
+}
+
+/// 本体一致性检查：T-Box 结构、别名冲突、默认约束与外部元数据中的列是否仍存在
+/// 与发布校验不同，此处不执行 EXPLAIN，数据源不可达只记为 WARNING
+pub async fn lint(state: &AppState, version: i64, nodes: &[FullSemanticNode], min_severity: &str) -> anyhow::Result<LintReport> {
+    let mut issues = check_dimension_refs(nodes);
+    issues.extend(check_alias_collisions(nodes, &node_domains(&state.db, nodes).await?));
+    issues.extend(check_structure(nodes));
+    issues.extend(check_constraints(nodes));
+    issues.extend(check_columns(state, nodes).await?);
+
+    // 汇总统计全部问题，列表只输出不低于 min_severity 的部分
+    let mut summary: BTreeMap<String, usize> = SEVERITIES.iter().map(|s| (s.to_string(), 0)).collect();
+    for i in &issues {
+        *summary.entry(i.severity.clone()).or_default() += 1;
+    }
+    issues.retain(|i| rank(&i.severity) <= rank(min_severity));
+    issues.sort_by(|a, b| (rank(&a.severity), &a.node_key, &a.check).cmp(&(rank(&b.severity), &b.node_key, &b.check)));
+    info!("🧹 本体检查完成: 版本 {}, {} 个节点, {:?}", version, nodes.len(), summary);
+    Ok(LintReport { version, checked_nodes: nodes.len(), summary, issues })
+}
+
+// --- 1. T-Box 结构 ---
+
+/// 指标无维度、维度未被任何指标关联、指标缺少日期维度
+fn check_structure(nodes: &[FullSemanticNode]) -> Vec<ValidationIssue> {
+    let linked: HashSet<_> = nodes
+        .iter()
+        .filter(|n| n.node_role == "METRIC")
+        .flat_map(|n| n.supported_dimension_ids.iter())
+        .collect();
+    let by_id: HashMap<_, _> = nodes.iter().map(|n| (n.id, n)).collect();
+    let mut issues = Vec::new();
+
+    for n in nodes {
+        match n.node_role.as_str() {
+            "METRIC" if n.supported_dimension_ids.is_empty() => issues.push(issue(
+                "WARNING",
+                &n.node_key,
+                "metric_without_dimensions",
+                "指标未关联任何维度，只能回答总量问题".to_string(),
+            )),
+            "METRIC" => {
+                let has_date = n
+                    .supported_dimension_ids
+                    .iter()
+                    .any(|id| by_id.get(id).is_some_and(|d| d.semantic_type == "DATE"));
+                if !has_date {
+                    // 同表存在日期维度却未关联，多半是漏配
+                    let candidates: Vec<&str> = nodes
+                        .iter()
+                        .filter(|d| d.node_role == "DIMENSION" && d.semantic_type == "DATE" && d.target_table == n.target_table)
+                        .map(|d| d.node_key.as_str())
+                        .collect();
+                    let (severity, message) = if candidates.is_empty() {
+                        ("INFO", "指标未关联日期维度，无法回答按日期或趋势的问题".to_string())
+                    } else {
+                        ("WARNING", format!("指标未关联日期维度，同表存在日期维度: {}", candidates.join(", ")))
+                    };
+                    issues.push(issue(severity, &n.node_key, "metric_without_date_dimension", message));
+                }
+            }
+            "DIMENSION" if !linked.contains(&n.id) => issues.push(issue(
+                "WARNING",
+                &n.node_key,
+                "orphan_dimension",
+                "维度未被任何指标关联，提问中不会生效".to_string(),
+            )),
+            _ => {}
+        }
+    }
+    issues
+}
+
+// --- 2. 默认约束 ---
+
+fn check_constraints(nodes: &[FullSemanticNode]) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for n in nodes {
+        for c in &n.default_constraints.0 {
+            let op = c.operator.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+            if !VALID_OPERATORS.contains(&op.as_str()) {
+                issues.push(issue(
+                    "ERROR",
+                    &n.node_key,
+                    "invalid_constraint_operator",
+                    format!("约束 {} 的比较符 '{}' 无效，可用: {}", c.column, c.operator, VALID_OPERATORS.join(" ")),
+                ));
+            }
+            if c.column.trim().is_empty() {
+                issues.push(issue("ERROR", &n.node_key, "invalid_constraint_column", "约束缺少列名".to_string()));
+            }
+        }
+    }
+    issues
+}
+
+// --- 3. 外部元数据 ---
+
+/// 对照数据源的列清单检查 sql_expression 与约束引用的列；表不存在记为 ERROR
+async fn check_columns(state: &AppState, nodes: &[FullSemanticNode]) -> anyhow::Result<Vec<ValidationIssue>> {
+    let mut by_table: BTreeMap<(&str, &str), Vec<&FullSemanticNode>> = BTreeMap::new();
+    for n in nodes.iter().filter(|n| !n.target_table.is_empty()) {
+        by_table.entry((n.source_id.as_str(), n.target_table.as_str())).or_default().push(n);
+    }
+
+    let mut issues = Vec::new();
+    let mut unreachable: HashMap<&str, String> = HashMap::new();
+    for ((source_id, table), members) in by_table {
+        if let Some(msg) = unreachable.get(source_id) {
+            for n in members {
+                issues.push(issue("WARNING", &n.node_key, "source_unreachable", msg.clone()));
+            }
+            continue;
+        }
+        let conn = match connect(state, source_id).await? {
+            Ok(c) => c,
+            Err(e) => {
+                let msg = format!("数据源 {} 不可用，跳过列检查: {}", source_id, e);
+                for n in &members {
+                    issues.push(issue("WARNING", &n.node_key, "source_unreachable", msg.clone()));
+                }
+                unreachable.insert(source_id, msg);
+                continue;
+            }
+        };
+
+        let (schema, name) = match table.rsplit_once('.') {
+            Some((s, t)) => (Some(s), t),
+            None => (None, table),
+        };
+        let columns = match conn.list_columns(schema, name).await {
+            Ok(cols) if !cols.is_empty() => cols,
+            Ok(_) => {
+                for n in members {
+                    issues.push(issue("ERROR", &n.node_key, "missing_table", format!("表 {} 在数据源 {} 中不存在", table, source_id)));
+                }
+                continue;
+            }
+            Err(e) => {
+                for n in members {
+                    issues.push(issue("WARNING", &n.node_key, "source_unreachable", format!("读取表 {} 的列失败: {}", table, e)));
+                }
+                continue;
+            }
+        };
+        let exact: HashSet<&str> = columns.iter().map(String::as_str).collect();
+        let folded: HashSet<String> = columns.iter().map(|c| c.to_lowercase()).collect();
+
+        for n in members {
+            let mut missing = BTreeSet::new();
+            let refs = column_refs(&n.sql_expression)
+                .into_iter()
+                .chain(n.default_constraints.0.iter().flat_map(|c| column_refs(&c.column)));
+            for (col, quoted) in refs {
+                let found = if quoted { exact.contains(col.as_str()) } else { folded.contains(&col.to_lowercase()) };
+                if !found {
+                    missing.insert(col);
+                }
+            }
+            if !missing.is_empty() {
+                issues.push(issue(
+                    "ERROR",
+                    &n.node_key,
+                    "missing_column",
+                    format!("表 {} 中不存在列: {}", table, missing.into_iter().collect::<Vec<_>>().join(", ")),
+                ));
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// 从 SQL 表达式中提取列引用 (列名, 是否带引号)：
+/// 跳过字符串字面量、数字、函数名、限定前缀 (t.col 中的 t)、`::` 后的类型名与关键字
+fn column_refs(expr: &str) -> Vec<(String, bool)> {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    let re = TOKEN.get_or_init(|| {
+        Regex::new(r#"'(?:[^']|'')*'|(::\s*)?(?:"((?:[^"]|"")+)"|`([^`]+)`|([A-Za-z_][A-Za-z0-9_$]*)|\d[\w.]*)(\s*[.(])?"#)
+            .unwrap()
+    });
+    let mut refs = Vec::new();
+    for caps in re.captures_iter(expr) {
+        if caps.get(1).is_some() || caps.get(5).is_some() {
+            continue;
+        }
+        if let Some(q) = caps.get(2).or(caps.get(3)) {
+            refs.push((q.as_str().replace("\"\"", "\""), true));
+        } else if let Some(w) = caps.get(4) {
+            if !SQL_KEYWORDS.contains(&w.as_str().to_uppercase().as_str()) {
+                refs.push((w.as_str().to_string(), false));
+            }
+        }
+    }
+    refs
+}
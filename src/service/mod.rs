@@ -0,0 +1,15 @@
+pub mod answer;
+pub mod bootstrap;
+pub mod bundle;
+pub mod chart;
+pub mod lint;
+pub mod query_log;
+pub mod rdf;
+pub mod rdf_io;
+pub mod relations;
+pub mod revision;
+pub mod security;
+pub mod shadow;
+pub mod sparql;
+pub mod sync;
+pub mod validation;
\ No newline at end of file
@@ -0,0 +1,80 @@
+use crate::ax_state::AppState;
+use crate::models::schema::QueryLogicalPlan;
+use std::time::Instant;
+use tracing::warn;
+use uuid::Uuid;
+
+/// 一次问答的审计记录（在流水线中逐步填充，结束时一次性落库）
+pub struct QueryLogEntry {
+    pub question: String,
+    pub session_id: Option<String>,
+    pub user_id: Option<String>,
+    pub domain_key: Option<String>, // 提问限定的业务域
+    pub plan: Option<QueryLogicalPlan>,
+    pub inference_mode: Option<String>,
+    pub compiled_sql: Option<String>,
+    pub source_id: Option<String>,
+    pub row_count: Option<i32>,
+    pub status: String, // success / fail (未理解) / error (执行失败)
+    pub error: Option<String>,
+    pub replay_of: Option<Uuid>,
+    started: Instant,
+}
+
+impl QueryLogEntry {
+    pub fn new(question: &str, session_id: Option<String>) -> Self {
+        Self {
+            question: question.to_string(),
+            session_id,
+            user_id: None,
+            domain_key: None,
+            plan: None,
+            inference_mode: None,
+            compiled_sql: None,
+            source_id: None,
+            row_count: None,
+            status: "fail".to_string(),
+            error: None,
+            replay_of: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn fail(&mut self, status: &str, error: String) {
+        self.status = status.to_string();
+        self.error = Some(error);
+    }
+}
+
+/// 记录一次问答，返回日志 ID
+/// 日志写入失败只告警，不影响问答主流程
+pub async fn record(state: &AppState, entry: &QueryLogEntry) -> Option<Uuid> {
+    let plan_json = entry.plan.as_ref().and_then(|p| serde_json::to_value(p).ok());
+    let latency_ms = entry.started.elapsed().as_millis() as i64;
+    match sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO query_log (question, session_id, plan, inference_mode, compiled_sql, source_id, latency_ms, row_count, status, error, replay_of, user_id, domain_key)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+    )
+    .bind(&entry.question)
+    .bind(&entry.session_id)
+    .bind(plan_json)
+    .bind(&entry.inference_mode)
+    .bind(&entry.compiled_sql)
+    .bind(&entry.source_id)
+    .bind(latency_ms)
+    .bind(entry.row_count)
+    .bind(&entry.status)
+    .bind(&entry.error)
+    .bind(entry.replay_of)
+    .bind(&entry.user_id)
+    .bind(&entry.domain_key)
+    .fetch_one(&state.db)
+    .await
+    {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("问答日志写入失败: {}", e);
+            None
+        }
+    }
+}
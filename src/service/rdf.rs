@@ -0,0 +1,517 @@
+use crate::models::bundle::{
+    BundleDataSource, BundleDataset, BundleDomain, BundleLabelSource, BundleNode, BundleRelation, BundleValue,
+    OntologyBundle, BUNDLE_FORMAT_VERSION,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+
+pub const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+pub const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+pub const SKOS: &str = "http://www.w3.org/2004/02/skos/core#";
+pub const DCTERMS: &str = "http://purl.org/dc/terms/";
+
+/// 未配置 SSE_RDF_BASE_IRI 时的默认基础 IRI
+const DEFAULT_BASE_IRI: &str = "http://localhost/sse/";
+
+/// RDF 项：IRI、空白节点或字面量（datatype 为空表示 xsd:string）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    Iri(String),
+    Blank(String),
+    Literal { value: String, datatype: Option<String>, lang: Option<String> },
+}
+
+impl Term {
+    pub fn literal(value: impl Into<String>) -> Self {
+        Term::Literal { value: value.into(), datatype: None, lang: None }
+    }
+
+    pub fn typed(value: impl Into<String>, datatype: impl Into<String>) -> Self {
+        Term::Literal { value: value.into(), datatype: Some(datatype.into()), lang: None }
+    }
+
+    fn as_iri(&self) -> Option<&str> {
+        match self {
+            Term::Iri(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    fn as_literal(&self) -> Option<&str> {
+        match self {
+            Term::Literal { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triple {
+    pub subject: Term,
+    pub predicate: String,
+    pub object: Term,
+}
+
+/// 本体 IRI 命名：词汇表位于 {base}vocab#，各类实例位于 {base}<类别>/<业务 Key>
+pub struct Namespaces {
+    pub base: String,
+    pub vocab: String,
+}
+
+impl Namespaces {
+    /// 基础 IRI：请求参数优先，其次 SSE_RDF_BASE_IRI，末尾自动补 "/"
+    pub fn new(base_override: Option<&str>) -> Self {
+        let mut base = base_override
+            .map(String::from)
+            .or_else(|| env::var("SSE_RDF_BASE_IRI").ok())
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_BASE_IRI.to_string());
+        if !base.ends_with('/') && !base.ends_with('#') {
+            base.push('/');
+        }
+        let vocab = format!("{}vocab#", base);
+        Self { base, vocab }
+    }
+
+    pub fn term(&self, name: &str) -> String {
+        format!("{}{}", self.vocab, name)
+    }
+
+    /// 实例命名空间，如 {base}node/
+    pub fn ns(&self, kind: &str) -> String {
+        format!("{}{}/", self.base, kind)
+    }
+
+    fn instance(&self, kind: &str, key: &str) -> String {
+        format!("{}{}", self.ns(kind), encode_segment(key))
+    }
+
+    /// 序列化时使用的前缀表
+    pub fn prefixes(&self) -> Vec<(String, String)> {
+        let mut p: Vec<(String, String)> = [("rdf", RDF), ("rdfs", RDFS), ("xsd", XSD), ("skos", SKOS), ("dcterms", DCTERMS)]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        p.push(("sse".into(), self.vocab.clone()));
+        for kind in ["source", "domain", "dataset", "node", "member", "join"] {
+            p.push((kind.into(), self.ns(kind)));
+        }
+        p
+    }
+}
+
+/// IRI 路径段编码：保留非 ASCII 字符（IRI 允许），其余保留字符按 UTF-8 百分号编码
+fn encode_segment(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() || "-._~".contains(c) || !c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut buf = [0u8; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    out
+}
+
+fn decode_segment(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(h), Some(l)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((h * 16 + l) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// --- 1. 本体包 -> RDF 图 ---
+
+struct GraphBuilder<'a> {
+    ns: &'a Namespaces,
+    triples: Vec<Triple>,
+}
+
+impl GraphBuilder<'_> {
+    fn add(&mut self, s: &str, p: String, o: Term) {
+        self.triples.push(Triple { subject: Term::Iri(s.to_string()), predicate: p, object: o });
+    }
+
+    fn add_type(&mut self, s: &str, class: String) {
+        self.add(s, format!("{}type", RDF), Term::Iri(class));
+    }
+
+    fn add_lit(&mut self, s: &str, p: String, v: &str) {
+        self.add(s, p, Term::literal(v));
+    }
+
+    fn add_opt(&mut self, s: &str, p: String, v: &Option<String>) {
+        if let Some(v) = v {
+            self.add_lit(s, p, v);
+        }
+    }
+
+    fn add_bool(&mut self, s: &str, p: String, v: bool) {
+        self.add(s, p, Term::typed(v.to_string(), format!("{}boolean", XSD)));
+    }
+
+    fn add_json(&mut self, s: &str, p: String, v: &serde_json::Value) {
+        self.add(s, p, Term::typed(v.to_string(), format!("{}JSON", RDF)));
+    }
+
+    fn sse(&self, name: &str) -> String {
+        self.ns.term(name)
+    }
+}
+
+/// 将本体包转换为 RDF 三元组
+/// 维度同时是 skos:ConceptScheme，其码值导出为 skos:Concept 个体（notation 为码值，altLabel 为同义词）
+pub fn bundle_to_graph(bundle: &OntologyBundle, ns: &Namespaces) -> Vec<Triple> {
+    let mut g = GraphBuilder { ns, triples: Vec::new() };
+    let id = format!("{}identifier", DCTERMS);
+    let label = format!("{}label", RDFS);
+    let alt_label = format!("{}altLabel", SKOS);
+
+    for s in &bundle.data_sources {
+        let iri = ns.instance("source", &s.id);
+        g.add_type(&iri, g.sse("DataSource"));
+        g.add_lit(&iri, id.clone(), &s.id);
+        g.add_lit(&iri, g.sse("dbType"), &s.db_type);
+        g.add_opt(&iri, label.clone(), &s.display_name);
+    }
+
+    for d in &bundle.domains {
+        let iri = ns.instance("domain", &d.domain_key);
+        g.add_type(&iri, g.sse("Domain"));
+        g.add_lit(&iri, id.clone(), &d.domain_key);
+        g.add_lit(&iri, label.clone(), &d.label);
+        g.add_opt(&iri, format!("{}comment", RDFS), &d.description);
+    }
+
+    for d in &bundle.datasets {
+        let iri = ns.instance("dataset", &d.dataset_key);
+        g.add_type(&iri, g.sse("Dataset"));
+        g.add_lit(&iri, id.clone(), &d.dataset_key);
+        g.add_lit(&iri, label.clone(), &d.label);
+        if let Some(k) = &d.domain_key {
+            g.add(&iri, g.sse("domain"), Term::Iri(ns.instance("domain", k)));
+        }
+        g.add_json(&iri, g.sse("joinConfig"), &d.join_config);
+    }
+
+    for n in &bundle.nodes {
+        let iri = ns.instance("node", &n.node_key);
+        let is_dimension = n.node_role == "DIMENSION";
+        g.add_type(&iri, g.sse(if is_dimension { "Dimension" } else { "Metric" }));
+        if is_dimension {
+            g.add_type(&iri, format!("{}ConceptScheme", SKOS));
+        }
+        g.add_lit(&iri, id.clone(), &n.node_key);
+        g.add_lit(&iri, label.clone(), &n.label);
+        for a in &n.alias_names {
+            g.add_lit(&iri, alt_label.clone(), a);
+        }
+        g.add_lit(&iri, g.sse("semanticType"), &n.semantic_type);
+        if let Some(k) = &n.dataset_key {
+            g.add(&iri, g.sse("dataset"), Term::Iri(ns.instance("dataset", k)));
+        }
+        g.add(&iri, g.sse("source"), Term::Iri(ns.instance("source", &n.source_id)));
+        g.add_lit(&iri, g.sse("physicalTable"), &n.target_table);
+        g.add_lit(&iri, g.sse("sqlExpression"), &n.sql_expression);
+        g.add_lit(&iri, g.sse("defaultAgg"), &n.default_agg);
+        g.add_opt(&iri, g.sse("valueFormat"), &n.value_format);
+        g.add_opt(&iri, g.sse("unit"), &n.unit);
+        g.add_opt(&iri, g.sse("numberFormat"), &n.number_format);
+        if !n.default_constraints.is_empty() {
+            let v = serde_json::to_value(&n.default_constraints).unwrap_or_default();
+            g.add_json(&iri, g.sse("defaultConstraints"), &v);
+        }
+        for k in &n.supported_dimensions {
+            g.add(&iri, g.sse("hasDimension"), Term::Iri(ns.instance("node", k)));
+        }
+        if let Some(ls) = &n.label_source {
+            g.add_lit(&iri, g.sse("labelExpression"), &ls.label_expression);
+            g.add_opt(&iri, g.sse("lookupTable"), &ls.lookup_table);
+            g.add_opt(&iri, g.sse("lookupCodeColumn"), &ls.lookup_code_column);
+        }
+
+        for v in &n.values {
+            let m = format!("{}/{}", ns.instance("member", &n.node_key), encode_segment(&v.code));
+            g.add_type(&m, format!("{}Concept", SKOS));
+            g.add(&m, format!("{}inScheme", SKOS), Term::Iri(iri.clone()));
+            g.add_lit(&m, format!("{}notation", SKOS), &v.code);
+            g.add_lit(&m, format!("{}prefLabel", SKOS), &v.label);
+            for s in &v.synonyms {
+                g.add_lit(&m, alt_label.clone(), s);
+            }
+            g.add_bool(&m, g.sse("active"), v.is_active);
+            g.add_bool(&m, g.sse("labelLocked"), v.label_locked);
+        }
+    }
+
+    for r in &bundle.relations {
+        let key = format!("{}\u{1f}{}\u{1f}{}\u{1f}{}", r.source_id, r.from_table, r.to_table, r.join_logic);
+        let iri = ns.instance("join", &hex::encode(&Sha256::digest(key.as_bytes())[..8]));
+        g.add_type(&iri, g.sse("Join"));
+        g.add(&iri, g.sse("source"), Term::Iri(ns.instance("source", &r.source_id)));
+        g.add_lit(&iri, g.sse("fromTable"), &r.from_table);
+        g.add_lit(&iri, g.sse("toTable"), &r.to_table);
+        g.add_lit(&iri, g.sse("joinLogic"), &r.join_logic);
+        g.add_lit(&iri, g.sse("origin"), &r.origin);
+        g.add_lit(&iri, g.sse("status"), &r.status);
+    }
+
+    g.triples
+}
+
+// --- 2. RDF 图 -> 本体包 ---
+
+/// 按主语聚合的图视图
+struct GraphIndex<'a> {
+    ns: &'a Namespaces,
+    subjects: Vec<&'a Term>,
+    props: HashMap<&'a Term, Vec<(&'a str, &'a Term)>>,
+}
+
+impl<'a> GraphIndex<'a> {
+    fn new(triples: &'a [Triple], ns: &'a Namespaces) -> Self {
+        let mut subjects = Vec::new();
+        let mut props: HashMap<&Term, Vec<(&str, &Term)>> = HashMap::new();
+        for t in triples {
+            let entry = props.entry(&t.subject).or_default();
+            if entry.is_empty() {
+                subjects.push(&t.subject);
+            }
+            entry.push((t.predicate.as_str(), &t.object));
+        }
+        Self { ns, subjects, props }
+    }
+
+    fn objects(&self, s: &Term, p: &str) -> impl Iterator<Item = &'a Term> + '_ {
+        let p = p.to_string();
+        self.props.get(s).into_iter().flatten().filter(move |(pred, _)| *pred == p).map(|(_, o)| *o)
+    }
+
+    fn of_type(&self, class: &str) -> Vec<&'a Term> {
+        let rdf_type = format!("{}type", RDF);
+        self.subjects
+            .iter()
+            .copied()
+            .filter(|s| self.objects(s, &rdf_type).any(|o| o.as_iri() == Some(class)))
+            .collect()
+    }
+
+    fn literal(&self, s: &Term, p: &str) -> Option<String> {
+        self.objects(s, p).find_map(|o| o.as_literal().map(String::from))
+    }
+
+    fn literals(&self, s: &Term, p: &str) -> Vec<String> {
+        self.objects(s, p).filter_map(|o| o.as_literal().map(String::from)).collect()
+    }
+
+    fn required(&self, s: &Term, p: &str, errors: &mut Vec<String>) -> String {
+        self.literal(s, p).unwrap_or_else(|| {
+            errors.push(format!("{} 缺少属性 <{}>", describe(s), p));
+            String::new()
+        })
+    }
+
+    fn boolean(&self, s: &Term, p: &str) -> Option<bool> {
+        self.literal(s, p).map(|v| v == "true" || v == "1")
+    }
+
+    fn json(&self, s: &Term, p: &str, errors: &mut Vec<String>) -> Option<serde_json::Value> {
+        let raw = self.literal(s, p)?;
+        serde_json::from_str(&raw)
+            .map_err(|e| errors.push(format!("{} 的 <{}> 不是合法 JSON: {}", describe(s), p, e)))
+            .ok()
+    }
+
+    fn iri(&self, s: &Term, p: &str) -> Option<&'a str> {
+        self.objects(s, p).find_map(|o| o.as_iri())
+    }
+
+    /// 业务 Key：优先取 dcterms:identifier，其次由实例 IRI 反解
+    fn key(&self, s: &Term, kind: &str) -> Option<String> {
+        self.literal(s, &format!("{}identifier", DCTERMS))
+            .or_else(|| s.as_iri().and_then(|i| self.key_from_iri(i, kind)))
+    }
+
+    fn key_from_iri(&self, iri: &str, kind: &str) -> Option<String> {
+        iri.strip_prefix(&self.ns.ns(kind)).map(decode_segment)
+    }
+
+    /// 引用解析：图中声明了该实例时取其 identifier，否则按命名规则反解 IRI
+    fn reference(&self, iri: &str, kind: &str, errors: &mut Vec<String>) -> String {
+        let term = Term::Iri(iri.to_string());
+        if self.props.contains_key(&term) {
+            if let Some(k) = self.key(&term, kind) {
+                return k;
+            }
+        }
+        self.key_from_iri(iri, kind).unwrap_or_else(|| {
+            errors.push(format!("无法解析 {} 引用: <{}>", kind, iri));
+            String::new()
+        })
+    }
+}
+
+fn describe(s: &Term) -> String {
+    match s {
+        Term::Iri(i) => format!("<{}>", i),
+        Term::Blank(b) => format!("_:{}", b),
+        Term::Literal { value, .. } => format!("\"{}\"", value),
+    }
+}
+
+/// 将 RDF 三元组还原为本体包（之后复用本体包的校验、差异与导入流程）
+pub fn graph_to_bundle(triples: &[Triple], ns: &Namespaces) -> Result<OntologyBundle, Vec<String>> {
+    let g = GraphIndex::new(triples, ns);
+    let mut errors = Vec::new();
+    let label = format!("{}label", RDFS);
+    let alt_label = format!("{}altLabel", SKOS);
+    let sse = |name: &str| ns.term(name);
+
+    let data_sources: Vec<BundleDataSource> = g
+        .of_type(&sse("DataSource"))
+        .into_iter()
+        .map(|s| BundleDataSource {
+            id: g.key(s, "source").unwrap_or_default(),
+            db_type: g.required(s, &sse("dbType"), &mut errors),
+            display_name: g.literal(s, &label),
+        })
+        .collect();
+
+    let domains: Vec<BundleDomain> = g
+        .of_type(&sse("Domain"))
+        .into_iter()
+        .map(|s| BundleDomain {
+            domain_key: g.key(s, "domain").unwrap_or_default(),
+            label: g.required(s, &label, &mut errors),
+            description: g.literal(s, &format!("{}comment", RDFS)),
+        })
+        .collect();
+
+    let datasets: Vec<BundleDataset> = g
+        .of_type(&sse("Dataset"))
+        .into_iter()
+        .map(|s| BundleDataset {
+            dataset_key: g.key(s, "dataset").unwrap_or_default(),
+            label: g.required(s, &label, &mut errors),
+            domain_key: g.iri(s, &sse("domain")).map(|i| g.reference(i, "domain", &mut errors)),
+            join_config: g.json(s, &sse("joinConfig"), &mut errors).unwrap_or_else(|| serde_json::json!({})),
+        })
+        .collect();
+
+    // 码值个体按所属维度归组
+    let mut values: HashMap<String, Vec<BundleValue>> = HashMap::new();
+    for m in g.of_type(&format!("{}Concept", SKOS)) {
+        let Some(scheme) = g.iri(m, &format!("{}inScheme", SKOS)) else {
+            errors.push(format!("{} 缺少 skos:inScheme", describe(m)));
+            continue;
+        };
+        let dim_key = g.reference(scheme, "node", &mut errors);
+        let code = g.required(m, &format!("{}notation", SKOS), &mut errors);
+        values.entry(dim_key).or_default().push(BundleValue {
+            label: g.literal(m, &format!("{}prefLabel", SKOS)).unwrap_or_else(|| code.clone()),
+            code,
+            label_locked: g.boolean(m, &sse("labelLocked")).unwrap_or(false),
+            is_active: g.boolean(m, &sse("active")).unwrap_or(true),
+            synonyms: g.literals(m, &alt_label),
+        });
+    }
+
+    let mut nodes = Vec::new();
+    for (class, role) in [("Dimension", "DIMENSION"), ("Metric", "METRIC")] {
+        for s in g.of_type(&sse(class)) {
+            let node_key = g.key(s, "node").unwrap_or_default();
+            let label_source = g.literal(s, &sse("labelExpression")).map(|label_expression| BundleLabelSource {
+                label_expression,
+                lookup_table: g.literal(s, &sse("lookupTable")),
+                lookup_code_column: g.literal(s, &sse("lookupCodeColumn")),
+            });
+            let default_constraints = match g.json(s, &sse("defaultConstraints"), &mut errors) {
+                Some(v) => serde_json::from_value(v).unwrap_or_else(|e| {
+                    errors.push(format!("{} 的默认约束格式错误: {}", describe(s), e));
+                    Vec::new()
+                }),
+                None => Vec::new(),
+            };
+            let mut supported_dimensions: Vec<String> = g
+                .objects(s, &sse("hasDimension"))
+                .filter_map(|o| o.as_iri())
+                .map(|i| g.reference(i, "node", &mut errors))
+                .collect();
+            supported_dimensions.sort();
+            nodes.push(BundleNode {
+                label: g.required(s, &label, &mut errors),
+                node_role: role.to_string(),
+                semantic_type: g.literal(s, &sse("semanticType")).unwrap_or_else(|| "STRING".into()),
+                dataset_key: g.iri(s, &sse("dataset")).map(|i| g.reference(i, "dataset", &mut errors)),
+                source_id: match g.iri(s, &sse("source")) {
+                    Some(i) => g.reference(i, "source", &mut errors),
+                    None => g.required(s, &sse("source"), &mut errors),
+                },
+                target_table: g.required(s, &sse("physicalTable"), &mut errors),
+                sql_expression: g.required(s, &sse("sqlExpression"), &mut errors),
+                alias_names: g.literals(s, &alt_label),
+                default_constraints,
+                default_agg: g.literal(s, &sse("defaultAgg")).unwrap_or_else(|| "SUM".into()),
+                value_format: g.literal(s, &sse("valueFormat")),
+                unit: g.literal(s, &sse("unit")),
+                number_format: g.literal(s, &sse("numberFormat")),
+                supported_dimensions,
+                label_source,
+                values: values.remove(&node_key).unwrap_or_default(),
+                node_key,
+            });
+        }
+    }
+    for key in values.keys() {
+        errors.push(format!("码值所属的维度未在图中声明: {}", key));
+    }
+
+    let relations = g
+        .of_type(&sse("Join"))
+        .into_iter()
+        .map(|s| BundleRelation {
+            source_id: match g.iri(s, &sse("source")) {
+                Some(i) => g.reference(i, "source", &mut errors),
+                None => g.required(s, &sse("source"), &mut errors),
+            },
+            from_table: g.required(s, &sse("fromTable"), &mut errors),
+            to_table: g.required(s, &sse("toTable"), &mut errors),
+            join_logic: g.required(s, &sse("joinLogic"), &mut errors),
+            origin: g.literal(s, &sse("origin")).unwrap_or_else(|| "MANUAL".into()),
+            status: g.literal(s, &sse("status")).unwrap_or_else(|| "ACCEPTED".into()),
+        })
+        .collect();
+
+    if !triples.is_empty() && data_sources.is_empty() && domains.is_empty() && datasets.is_empty() && nodes.is_empty() {
+        errors.push(format!("未识别到任何本体对象，请确认基础 IRI 与导出时一致（当前词汇表: {}）", ns.vocab));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(OntologyBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: None,
+        data_sources,
+        domains,
+        datasets,
+        nodes,
+        relations,
+    })
+}
@@ -0,0 +1,883 @@
+use crate::service::rdf::{Term, Triple, RDF, XSD};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// 支持的 RDF 序列化格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RdfFormat {
+    Turtle,
+    NTriples,
+    JsonLd,
+}
+
+impl RdfFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "turtle" | "ttl" => Some(Self::Turtle),
+            "ntriples" | "n-triples" | "nt" => Some(Self::NTriples),
+            "jsonld" | "json-ld" => Some(Self::JsonLd),
+            _ => None,
+        }
+    }
+
+    /// 按 Content-Type 识别格式
+    pub fn from_content_type(ct: &str) -> Option<Self> {
+        let ct = ct.to_ascii_lowercase();
+        if ct.contains("turtle") {
+            Some(Self::Turtle)
+        } else if ct.contains("n-triples") {
+            Some(Self::NTriples)
+        } else if ct.contains("ld+json") {
+            Some(Self::JsonLd)
+        } else {
+            None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Turtle => "text/turtle; charset=utf-8",
+            Self::NTriples => "application/n-triples",
+            Self::JsonLd => "application/ld+json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Turtle => "ttl",
+            Self::NTriples => "nt",
+            Self::JsonLd => "jsonld",
+        }
+    }
+}
+
+pub fn serialize(format: RdfFormat, triples: &[Triple], prefixes: &[(String, String)]) -> String {
+    match format {
+        RdfFormat::Turtle => write_turtle(triples, prefixes),
+        RdfFormat::NTriples => write_ntriples(triples),
+        RdfFormat::JsonLd => write_jsonld(triples, prefixes),
+    }
+}
+
+pub fn parse(format: RdfFormat, text: &str) -> Result<Vec<Triple>, String> {
+    match format {
+        // N-Triples 是 Turtle 的子集
+        RdfFormat::Turtle | RdfFormat::NTriples => TurtleParser::new(text).parse(),
+        RdfFormat::JsonLd => parse_jsonld(text),
+    }
+}
+
+// --- 1. 序列化 ---
+
+/// 字符串字面量转义（Turtle / N-Triples 通用）
+fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// IRI 转义：IRIREF 中不允许出现的字符用 \u 转义
+fn escape_iri(iri: &str) -> String {
+    let mut out = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        if c <= ' ' || "<>\"{}|^`\\".contains(c) {
+            let _ = write!(out, "\\u{:04X}", c as u32);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn nt_term(t: &Term) -> String {
+    match t {
+        Term::Iri(i) => format!("<{}>", escape_iri(i)),
+        Term::Blank(b) => format!("_:{}", b),
+        Term::Literal { value, datatype, lang } => {
+            let mut s = format!("\"{}\"", escape_string(value));
+            if let Some(l) = lang {
+                s.push('@');
+                s.push_str(l);
+            } else if let Some(dt) = datatype.as_ref().filter(|dt| dt.as_str() != format!("{}string", XSD)) {
+                let _ = write!(s, "^^<{}>", escape_iri(dt));
+            }
+            s
+        }
+    }
+}
+
+pub fn write_ntriples(triples: &[Triple]) -> String {
+    let mut out = String::new();
+    for t in triples {
+        let _ = writeln!(out, "{} <{}> {} .", nt_term(&t.subject), escape_iri(&t.predicate), nt_term(&t.object));
+    }
+    out
+}
+
+/// 前缀名压缩：本地名仅含安全字符时输出 prefix:local，否则输出完整 IRI
+fn compact(iri: &str, prefixes: &[(String, String)]) -> Option<String> {
+    prefixes
+        .iter()
+        .filter(|(_, ns)| iri.starts_with(ns.as_str()))
+        .max_by_key(|(_, ns)| ns.len())
+        .and_then(|(p, ns)| {
+            let local = &iri[ns.len()..];
+            let safe = !local.is_empty()
+                && local.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                && !local.starts_with('-');
+            safe.then(|| format!("{}:{}", p, local))
+        })
+}
+
+fn ttl_iri(iri: &str, prefixes: &[(String, String)]) -> String {
+    compact(iri, prefixes).unwrap_or_else(|| format!("<{}>", escape_iri(iri)))
+}
+
+fn ttl_term(t: &Term, prefixes: &[(String, String)]) -> String {
+    match t {
+        Term::Iri(i) => ttl_iri(i, prefixes),
+        Term::Literal { value, datatype: Some(dt), lang: None } if *dt == format!("{}boolean", XSD) => value.clone(),
+        Term::Literal { value, datatype: Some(dt), lang: None } if *dt != format!("{}string", XSD) => {
+            format!("\"{}\"^^{}", escape_string(value), ttl_iri(dt, prefixes))
+        }
+        other => nt_term(other),
+    }
+}
+
+/// 按主语出现顺序聚合
+fn group_by_subject(triples: &[Triple]) -> Vec<(&Term, Vec<&Triple>)> {
+    let mut order: Vec<&Term> = Vec::new();
+    let mut groups: HashMap<&Term, Vec<&Triple>> = HashMap::new();
+    for t in triples {
+        groups
+            .entry(&t.subject)
+            .or_insert_with(|| {
+                order.push(&t.subject);
+                Vec::new()
+            })
+            .push(t);
+    }
+    order.into_iter().map(|s| (s, groups.remove(s).unwrap_or_default())).collect()
+}
+
+pub fn write_turtle(triples: &[Triple], prefixes: &[(String, String)]) -> String {
+    let mut out = String::new();
+    for (p, ns) in prefixes {
+        let _ = writeln!(out, "@prefix {}: <{}> .", p, escape_iri(ns));
+    }
+    let rdf_type = format!("{}type", RDF);
+    for (subject, group) in group_by_subject(triples) {
+        let _ = write!(out, "\n{}", ttl_term(subject, prefixes));
+        let mut last_pred: Option<&str> = None;
+        for (i, t) in group.iter().enumerate() {
+            let obj = ttl_term(&t.object, prefixes);
+            if last_pred == Some(t.predicate.as_str()) {
+                let _ = write!(out, ", {}", obj);
+            } else {
+                let pred = if t.predicate == rdf_type { "a".to_string() } else { ttl_iri(&t.predicate, prefixes) };
+                let sep = if i == 0 { " " } else { " ;\n    " };
+                let _ = write!(out, "{}{} {}", sep, pred, obj);
+            }
+            last_pred = Some(&t.predicate);
+        }
+        out.push_str(" .\n");
+    }
+    out
+}
+
+/// JSON-LD：@context 声明前缀，属性名使用前缀名，值对象采用展开形式
+pub fn write_jsonld(triples: &[Triple], prefixes: &[(String, String)]) -> String {
+    let context: Map<String, Value> = prefixes.iter().map(|(p, ns)| (p.clone(), json!(ns))).collect();
+    let rdf_type = format!("{}type", RDF);
+    let key = |iri: &str| compact(iri, prefixes).unwrap_or_else(|| iri.to_string());
+
+    let graph: Vec<Value> = group_by_subject(triples)
+        .into_iter()
+        .map(|(subject, group)| {
+            let mut node = Map::new();
+            node.insert(
+                "@id".into(),
+                match subject {
+                    Term::Blank(b) => json!(format!("_:{}", b)),
+                    other => json!(other_iri(other)),
+                },
+            );
+            for t in group {
+                if t.predicate == rdf_type {
+                    if let Term::Iri(class) = &t.object {
+                        push_value(&mut node, "@type", json!(key(class)));
+                        continue;
+                    }
+                }
+                let value = match &t.object {
+                    Term::Iri(i) => json!({ "@id": i }),
+                    Term::Blank(b) => json!({ "@id": format!("_:{}", b) }),
+                    Term::Literal { value, lang: Some(l), .. } => json!({ "@value": value, "@language": l }),
+                    Term::Literal { value, datatype: None, .. } => json!({ "@value": value }),
+                    Term::Literal { value, datatype: Some(dt), .. } => {
+                        if *dt == format!("{}boolean", XSD) {
+                            json!({ "@value": value == "true" })
+                        } else if *dt == format!("{}JSON", RDF) {
+                            match serde_json::from_str::<Value>(value) {
+                                Ok(v) => json!({ "@value": v, "@type": "@json" }),
+                                Err(_) => json!({ "@value": value }),
+                            }
+                        } else if *dt == format!("{}string", XSD) {
+                            json!({ "@value": value })
+                        } else {
+                            json!({ "@value": value, "@type": key(dt) })
+                        }
+                    }
+                };
+                push_value(&mut node, &key(&t.predicate), value);
+            }
+            Value::Object(node)
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({ "@context": context, "@graph": graph })).unwrap_or_default()
+}
+
+fn other_iri(t: &Term) -> String {
+    match t {
+        Term::Iri(i) => i.clone(),
+        Term::Blank(b) => format!("_:{}", b),
+        Term::Literal { value, .. } => value.clone(),
+    }
+}
+
+fn push_value(node: &mut Map<String, Value>, key: &str, v: Value) {
+    match node.get_mut(key) {
+        Some(Value::Array(arr)) => arr.push(v),
+        _ => {
+            node.insert(key.to_string(), json!([v]));
+        }
+    }
+}
+
+// --- 2. Turtle / N-Triples 解析 ---
+
+/// Turtle 子集解析器：支持 @prefix/@base、谓词列表 (;)、对象列表 (,)、a、
+/// 长短字符串字面量、语言标签、^^datatype、数值与布尔；不支持 [] 匿名节点与 () 集合
+struct TurtleParser {
+    chars: Vec<char>,
+    pos: usize,
+    base: Option<String>,
+    prefixes: HashMap<String, String>,
+    triples: Vec<Triple>,
+}
+
+impl TurtleParser {
+    fn new(text: &str) -> Self {
+        Self { chars: text.chars().collect(), pos: 0, base: None, prefixes: HashMap::new(), triples: Vec::new() }
+    }
+
+    fn parse(mut self) -> Result<Vec<Triple>, String> {
+        loop {
+            self.skip_ws();
+            if self.eof() {
+                return Ok(self.triples);
+            }
+            if self.eat_keyword("@prefix") {
+                self.parse_prefix(true)?;
+            } else if self.eat_keyword_ci("PREFIX") {
+                self.parse_prefix(false)?;
+            } else if self.eat_keyword("@base") {
+                self.parse_base(true)?;
+            } else if self.eat_keyword_ci("BASE") {
+                self.parse_base(false)?;
+            } else {
+                self.parse_statement()?;
+            }
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|c| **c == '\n').count() + 1;
+        format!("第 {} 行: {}", line, msg)
+    }
+
+    fn eof(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("期望 '{}'", c)))
+        }
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        if self.starts_with(kw) && self.peek_at(kw.chars().count()).is_some_and(|c| c.is_whitespace()) {
+            self.pos += kw.chars().count();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword_ci(&mut self, kw: &str) -> bool {
+        let n = kw.chars().count();
+        let matches = kw.chars().enumerate().all(|(i, c)| self.peek_at(i).is_some_and(|p| p.eq_ignore_ascii_case(&c)));
+        if matches && self.peek_at(n).is_some_and(|c| c.is_whitespace()) {
+            self.pos += n;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_prefix(&mut self, dotted: bool) -> Result<(), String> {
+        self.skip_ws();
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c == ':' {
+                break;
+            }
+            if c.is_whitespace() {
+                return Err(self.error("前缀声明缺少 ':'"));
+            }
+            name.push(c);
+            self.pos += 1;
+        }
+        self.expect(':')?;
+        self.skip_ws();
+        let iri = self.parse_iriref()?;
+        self.prefixes.insert(name, iri);
+        if dotted {
+            self.expect('.')?;
+        }
+        Ok(())
+    }
+
+    fn parse_base(&mut self, dotted: bool) -> Result<(), String> {
+        self.skip_ws();
+        self.base = Some(self.parse_iriref()?);
+        if dotted {
+            self.expect('.')?;
+        }
+        Ok(())
+    }
+
+    fn parse_statement(&mut self) -> Result<(), String> {
+        let subject = self.parse_subject()?;
+        loop {
+            self.skip_ws();
+            let predicate = self.parse_predicate()?;
+            loop {
+                self.skip_ws();
+                let object = self.parse_object()?;
+                self.triples.push(Triple { subject: subject.clone(), predicate: predicate.clone(), object });
+                self.skip_ws();
+                if self.peek() == Some(',') {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            if self.peek() == Some(';') {
+                // 允许多余的 ";"
+                while self.peek() == Some(';') {
+                    self.pos += 1;
+                    self.skip_ws();
+                }
+                if self.peek() == Some('.') {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+        self.expect('.')
+    }
+
+    fn parse_subject(&mut self) -> Result<Term, String> {
+        match self.peek() {
+            Some('<') => Ok(Term::Iri(self.parse_iriref()?)),
+            Some('_') if self.peek_at(1) == Some(':') => Ok(self.parse_blank()),
+            Some('[') | Some('(') => Err(self.error("暂不支持匿名节点与集合")),
+            _ => Ok(Term::Iri(self.parse_pname()?)),
+        }
+    }
+
+    fn parse_predicate(&mut self) -> Result<String, String> {
+        if self.peek() == Some('a') && self.peek_at(1).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+            return Ok(format!("{}type", RDF));
+        }
+        match self.peek() {
+            Some('<') => self.parse_iriref(),
+            _ => self.parse_pname(),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Term, String> {
+        match self.peek() {
+            Some('<') => Ok(Term::Iri(self.parse_iriref()?)),
+            Some('"') | Some('\'') => self.parse_literal(),
+            Some('_') if self.peek_at(1) == Some(':') => Ok(self.parse_blank()),
+            Some('[') | Some('(') => Err(self.error("暂不支持匿名节点与集合")),
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => self.parse_number(),
+            _ => {
+                for kw in ["true", "false"] {
+                    let n = kw.len();
+                    if self.starts_with(kw) && !self.peek_at(n).is_some_and(|c| c.is_alphanumeric() || c == ':') {
+                        self.pos += n;
+                        return Ok(Term::typed(kw, format!("{}boolean", XSD)));
+                    }
+                }
+                Ok(Term::Iri(self.parse_pname()?))
+            }
+        }
+    }
+
+    fn parse_iriref(&mut self) -> Result<String, String> {
+        if self.peek() != Some('<') {
+            return Err(self.error("期望 IRI"));
+        }
+        self.pos += 1;
+        let mut iri = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("IRI 未闭合")),
+                Some('>') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    iri.push(self.parse_unicode_escape()?);
+                }
+                Some(c) => {
+                    iri.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        // 相对 IRI 按 @base 拼接
+        match &self.base {
+            Some(base) if !iri.contains(':') => Ok(format!("{}{}", base, iri)),
+            _ => Ok(iri),
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let len = match self.peek() {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("非法转义")),
+        };
+        self.pos += 1;
+        let hex: String = (0..len).filter_map(|i| self.peek_at(i)).collect();
+        self.pos += len;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("非法 Unicode 转义"))
+    }
+
+    fn parse_pname(&mut self) -> Result<String, String> {
+        let mut prefix = String::new();
+        while let Some(c) = self.peek() {
+            if c == ':' {
+                break;
+            }
+            if !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.') {
+                return Err(self.error(&format!("无法识别的符号 '{}'", c)));
+            }
+            prefix.push(c);
+            self.pos += 1;
+        }
+        if self.peek() != Some(':') {
+            return Err(self.error("期望前缀名"));
+        }
+        self.pos += 1;
+        let mut local = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == ':' || c == '%' {
+                local.push(c);
+                self.pos += 1;
+            } else if c == '.' && self.peek_at(1).is_some_and(|n| n.is_alphanumeric() || n == '_' || n == '-') {
+                // 本地名不能以 "." 结尾（句点是语句结束符）
+                local.push(c);
+                self.pos += 1;
+            } else if c == '\\' {
+                self.pos += 1;
+                if let Some(e) = self.peek() {
+                    local.push(e);
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let ns = self.prefixes.get(&prefix).ok_or_else(|| self.error(&format!("未声明的前缀: {}", prefix)))?;
+        Ok(format!("{}{}", ns, local))
+    }
+
+    fn parse_blank(&mut self) -> Term {
+        self.pos += 2;
+        let mut label = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                label.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        Term::Blank(label)
+    }
+
+    fn parse_literal(&mut self) -> Result<Term, String> {
+        let quote = self.peek().unwrap_or('"');
+        let long = self.peek_at(1) == Some(quote) && self.peek_at(2) == Some(quote);
+        self.pos += if long { 3 } else { 1 };
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("字符串未闭合")),
+                Some(c) if c == quote => {
+                    if !long {
+                        self.pos += 1;
+                        break;
+                    }
+                    if self.peek_at(1) == Some(quote) && self.peek_at(2) == Some(quote) {
+                        self.pos += 3;
+                        break;
+                    }
+                    value.push(c);
+                    self.pos += 1;
+                }
+                Some('\n') | Some('\r') if !long => return Err(self.error("短字符串中不允许换行")),
+                Some('\\') => {
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('f') => '\u{c}',
+                        Some('"') => '"',
+                        Some('\'') => '\'',
+                        Some('\\') => '\\',
+                        Some('u') | Some('U') => {
+                            value.push(self.parse_unicode_escape()?);
+                            continue;
+                        }
+                        _ => return Err(self.error("非法转义")),
+                    };
+                    value.push(c);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        if self.peek() == Some('@') {
+            self.pos += 1;
+            let mut lang = String::new();
+            while let Some(c) = self.peek() {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    lang.push(c);
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            return Ok(Term::Literal { value, datatype: None, lang: Some(lang) });
+        }
+        if self.starts_with("^^") {
+            self.pos += 2;
+            let dt = match self.peek() {
+                Some('<') => self.parse_iriref()?,
+                _ => self.parse_pname()?,
+            };
+            let datatype = (dt != format!("{}string", XSD)).then_some(dt);
+            return Ok(Term::Literal { value, datatype, lang: None });
+        }
+        Ok(Term::literal(value))
+    }
+
+    fn parse_number(&mut self) -> Result<Term, String> {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            let follows_exp = s.ends_with(['e', 'E']);
+            if c.is_ascii_digit()
+                || ((c == '+' || c == '-') && (s.is_empty() || follows_exp))
+                || c == 'e'
+                || c == 'E'
+                || (c == '.' && self.peek_at(1).is_some_and(|n| n.is_ascii_digit()))
+            {
+                s.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let dt = if s.contains(['e', 'E']) {
+            "double"
+        } else if s.contains('.') {
+            "decimal"
+        } else {
+            "integer"
+        };
+        if s.parse::<f64>().is_err() {
+            return Err(self.error(&format!("非法数值: {}", s)));
+        }
+        Ok(Term::typed(s, format!("{}{}", XSD, dt)))
+    }
+}
+
+// --- 3. JSON-LD 解析 ---
+
+/// JSON-LD 解析：支持 @context 中的前缀定义（字符串映射）、@graph、顶层数组或单个节点对象；
+/// 节点值支持字符串、数值、布尔、{"@id"} 与 {"@value", "@language" | "@type"}（含 @json）
+fn parse_jsonld(text: &str) -> Result<Vec<Triple>, String> {
+    let doc: Value = serde_json::from_str(text).map_err(|e| format!("JSON-LD 解析失败: {}", e))?;
+    let mut prefixes: HashMap<String, String> = HashMap::new();
+    let nodes: Vec<Value> = match &doc {
+        Value::Array(arr) => arr.clone(),
+        Value::Object(obj) => {
+            if let Some(Value::Object(ctx)) = obj.get("@context") {
+                for (k, v) in ctx {
+                    if let Value::String(ns) = v {
+                        prefixes.insert(k.clone(), ns.clone());
+                    }
+                }
+            }
+            match obj.get("@graph") {
+                Some(Value::Array(g)) => g.clone(),
+                _ => vec![doc.clone()],
+            }
+        }
+        _ => return Err("JSON-LD 文档必须是对象或数组".into()),
+    };
+
+    let expand = |term: &str| -> String {
+        if let Some((p, local)) = term.split_once(':') {
+            if !local.starts_with("//") {
+                if let Some(ns) = prefixes.get(p) {
+                    return format!("{}{}", ns, local);
+                }
+            }
+        }
+        term.to_string()
+    };
+    let subject_term = |id: &str| match id.strip_prefix("_:") {
+        Some(b) => Term::Blank(b.to_string()),
+        None => Term::Iri(expand(id)),
+    };
+
+    let mut triples = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let Value::Object(obj) = node else {
+            return Err(format!("@graph[{}] 不是对象", i));
+        };
+        let subject = match obj.get("@id") {
+            Some(Value::String(id)) => subject_term(id),
+            _ => Term::Blank(format!("b{}", i)),
+        };
+        for (k, v) in obj {
+            if k == "@id" || k == "@context" {
+                continue;
+            }
+            let values = match v {
+                Value::Array(arr) => arr.clone(),
+                other => vec![other.clone()],
+            };
+            if k == "@type" {
+                for t in values {
+                    let Value::String(t) = t else {
+                        return Err(format!("{} 的 @type 必须是字符串", k));
+                    };
+                    triples.push(Triple {
+                        subject: subject.clone(),
+                        predicate: format!("{}type", RDF),
+                        object: Term::Iri(expand(&t)),
+                    });
+                }
+                continue;
+            }
+            if k.starts_with('@') {
+                continue;
+            }
+            let predicate = expand(k);
+            for v in values {
+                let object = match v {
+                    Value::String(s) => Term::literal(s),
+                    Value::Bool(b) => Term::typed(b.to_string(), format!("{}boolean", XSD)),
+                    Value::Number(n) => {
+                        let dt = if n.is_f64() { "double" } else { "integer" };
+                        Term::typed(n.to_string(), format!("{}{}", XSD, dt))
+                    }
+                    Value::Object(o) => {
+                        if let Some(Value::String(id)) = o.get("@id") {
+                            subject_term(id)
+                        } else {
+                            let raw = o.get("@value").ok_or_else(|| format!("属性 {} 的值对象缺少 @value", k))?;
+                            let ty = o.get("@type").and_then(|t| t.as_str());
+                            let lang = o.get("@language").and_then(|l| l.as_str()).map(String::from);
+                            match (raw, ty) {
+                                (raw, Some("@json")) => Term::typed(raw.to_string(), format!("{}JSON", RDF)),
+                                (Value::String(s), ty) => Term::Literal {
+                                    value: s.clone(),
+                                    datatype: ty.map(&expand).filter(|dt| *dt != format!("{}string", XSD)),
+                                    lang,
+                                },
+                                (Value::Bool(b), _) => Term::typed(b.to_string(), format!("{}boolean", XSD)),
+                                (other, ty) => Term::typed(
+                                    other.to_string(),
+                                    ty.map(&expand).unwrap_or_else(|| format!("{}integer", XSD)),
+                                ),
+                            }
+                        }
+                    }
+                    _ => return Err(format!("属性 {} 的值类型不受支持", k)),
+                };
+                triples.push(Triple { subject: subject.clone(), predicate: predicate.clone(), object });
+            }
+        }
+    }
+    Ok(triples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bundle::fixtures::sample_bundle;
+    use crate::models::bundle::{BundleLabelSource, BundleValue, OntologyBundle};
+    use crate::models::schema::BusinessConstraint;
+    use crate::service::rdf::{bundle_to_graph, graph_to_bundle, Namespaces};
+
+    /// 图还原的节点按角色分组、码值按出现顺序，比较前统一按 Key 排序
+    fn normalized(mut b: OntologyBundle) -> OntologyBundle {
+        b.nodes.sort_by(|x, y| x.node_key.cmp(&y.node_key));
+        for n in &mut b.nodes {
+            n.supported_dimensions.sort();
+        }
+        b
+    }
+
+    fn round_trip(bundle: &OntologyBundle, format: RdfFormat) -> OntologyBundle {
+        let ns = Namespaces::new(Some("http://test/sse/"));
+        let text = serialize(format, &bundle_to_graph(bundle, &ns), &ns.prefixes());
+        let triples = parse(format, &text).unwrap_or_else(|e| panic!("{:?} 解析失败: {}\n{}", format, e, text));
+        graph_to_bundle(&triples, &ns).unwrap_or_else(|e| panic!("{:?} 还原失败: {:?}", format, e))
+    }
+
+    /// 含引号、换行、反斜杠、制表符与保留字符的本体包
+    fn tricky_bundle() -> OntologyBundle {
+        let mut b = sample_bundle();
+        let revenue = b.nodes.iter_mut().find(|n| n.node_key == "revenue").unwrap();
+        revenue.label = "收益 \"含税\"\n(口径 A)".to_string();
+        revenue.sql_expression = "CASE WHEN note LIKE '%\\\\%' THEN \"amount\"\tELSE 0 END".to_string();
+        revenue.alias_names = vec!["营收".to_string(), "revenue \"gross\"".to_string()];
+        revenue.default_constraints = vec![BusinessConstraint {
+            column: "status".to_string(),
+            operator: "=".to_string(),
+            value: "it's \"paid\"".to_string(),
+        }];
+        revenue.number_format = Some("#,##0.00".to_string());
+        let region = b.nodes.iter_mut().find(|n| n.node_key == "region").unwrap();
+        region.label_source = Some(BundleLabelSource {
+            label_expression: "concat(name, ' \\\\ ', code)".to_string(),
+            lookup_table: Some("demo.dim_region".to_string()),
+            lookup_code_column: Some("code".to_string()),
+        });
+        region.values[0].synonyms = vec!["东区".to_string(), "east \"zone\"".to_string()];
+        region.values[1].label_locked = true;
+        region.values[1].is_active = false;
+        region.values.push(BundleValue {
+            code: "N/A #1".to_string(),
+            label: "未知\\其他".to_string(),
+            label_locked: false,
+            is_active: true,
+            synonyms: Vec::new(),
+        });
+        b.domains[0].description = Some("多行\n描述".to_string());
+        b.datasets[0].join_config = serde_json::json!({"joins": [{"on": "a.id = \"b\".id"}]});
+        b
+    }
+
+    #[test]
+    fn sample_bundle_round_trips_in_every_format() {
+        let bundle = sample_bundle();
+        for format in [RdfFormat::Turtle, RdfFormat::NTriples, RdfFormat::JsonLd] {
+            assert_eq!(normalized(round_trip(&bundle, format)), normalized(bundle.clone()), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn escaping_aliases_and_members_round_trip() {
+        let bundle = tricky_bundle();
+        for format in [RdfFormat::Turtle, RdfFormat::NTriples, RdfFormat::JsonLd] {
+            let back = normalized(round_trip(&bundle, format));
+            assert_eq!(back, normalized(bundle.clone()), "{:?}", format);
+            let region = back.nodes.iter().find(|n| n.node_key == "region").unwrap();
+            assert_eq!(region.values.len(), 3, "{:?}", format);
+            assert_eq!(region.values[0].synonyms, ["东区", "east \"zone\""], "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn literal_escaping_in_ntriples() {
+        let t = Triple {
+            subject: Term::Iri("http://ex/a b".to_string()),
+            predicate: "http://ex/p".to_string(),
+            object: Term::literal("say \"hi\"\nC:\\tmp"),
+        };
+        let text = write_ntriples(std::slice::from_ref(&t));
+        assert_eq!(text, "<http://ex/a\\u0020b> <http://ex/p> \"say \\\"hi\\\"\\nC:\\\\tmp\" .\n");
+        assert_eq!(parse(RdfFormat::NTriples, &text).unwrap(), vec![t]);
+    }
+}
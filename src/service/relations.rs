@@ -0,0 +1,73 @@
+use crate::models::schema::{RelationProposal, TableMetadata};
+
+/// 从表元数据发现表间关联
+/// 1. 外键：直接采用目录中的约束
+/// 2. 启发式（可选）：未声明外键的 xxx_id 列，匹配名为 xxx / xxxs / dim_xxx / d_xxx / t_xxx / xxx_dim 且有单列主键的表
+///
+/// schema 非空时表名带 schema 前缀（与本体节点的 target_table 保持一致）
+pub fn discover(tables: &[TableMetadata], schema: Option<&str>, heuristics: bool) -> Vec<RelationProposal> {
+    let qualify = |s: Option<&str>, t: &str| match schema {
+        Some(default) => format!("{}.{}", s.unwrap_or(default), t),
+        None => t.to_string(),
+    };
+
+    let mut out: Vec<RelationProposal> = Vec::new();
+    for t in tables {
+        let from_table = qualify(None, &t.table_name);
+        for col in &t.columns {
+            if let Some(fk) = &col.foreign_key {
+                let to_table = qualify(fk.schema.as_deref(), &fk.table);
+                // SQLite 外键可省略引用列，此时取对方主键
+                let to_column = fk.column.clone().or_else(|| {
+                    tables
+                        .iter()
+                        .find(|u| u.table_name == fk.table)
+                        .and_then(single_primary_key)
+                        .map(String::from)
+                });
+                let Some(to_column) = to_column else { continue };
+                push(&mut out, &from_table, &col.name, &to_table, &to_column, "FK");
+            } else if heuristics && !col.is_primary_key {
+                let Some(stem) = col.name.to_lowercase().strip_suffix("_id").map(String::from) else {
+                    continue;
+                };
+                let names = [
+                    stem.clone(),
+                    format!("{}s", stem),
+                    format!("dim_{}", stem),
+                    format!("d_{}", stem),
+                    format!("t_{}", stem),
+                    format!("{}_dim", stem),
+                ];
+                for u in tables.iter().filter(|u| u.table_name != t.table_name) {
+                    if !names.contains(&u.table_name.to_lowercase()) {
+                        continue;
+                    }
+                    if let Some(pk) = single_primary_key(u) {
+                        push(&mut out, &from_table, &col.name, &qualify(None, &u.table_name), pk, "HEURISTIC");
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn single_primary_key(t: &TableMetadata) -> Option<&str> {
+    match t.primary_key.as_slice() {
+        [pk] => Some(pk.as_str()),
+        _ => None,
+    }
+}
+
+fn push(out: &mut Vec<RelationProposal>, from_table: &str, from_col: &str, to_table: &str, to_col: &str, origin: &str) {
+    let proposal = RelationProposal {
+        from_table: from_table.to_string(),
+        to_table: to_table.to_string(),
+        join_logic: format!("{}.{} = {}.{}", from_table, from_col, to_table, to_col),
+        origin: origin.to_string(),
+    };
+    if !out.iter().any(|p| p.join_logic == proposal.join_logic) {
+        out.push(proposal);
+    }
+}
@@ -0,0 +1,459 @@
+use crate::api::mapping::{load_all_nodes, mark_deleted, write_node};
+use crate::models::revision::{Author, FieldChange, NodeChange, OntologyPublication};
+use crate::models::schema::{CreateNodeRequest, FullSemanticNode, OntologyRelation};
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::info;
+use uuid::Uuid;
+
+// --- 1. 修订记录 ---
+
+/// 节点定义的规范快照：维度 ID 排序，非指标节点不带维度关联
+pub fn canonical(node: &FullSemanticNode) -> Value {
+    let mut req = CreateNodeRequest::from(node);
+    if req.node_role == "METRIC" {
+        req.supported_dimension_ids.sort();
+    } else {
+        req.supported_dimension_ids.clear();
+    }
+    serde_json::to_value(req).unwrap_or(Value::Null)
+}
+
+/// 快照还原为内存节点（发布快照加载与回滚共用）
+pub fn snapshot_node(node_id: Uuid, snapshot: &Value) -> Option<FullSemanticNode> {
+    let req: CreateNodeRequest = serde_json::from_value(snapshot.clone()).ok()?;
+    Some(FullSemanticNode {
+        id: node_id,
+        node_key: req.node_key,
+        label: req.label,
+        node_role: req.node_role,
+        semantic_type: req.semantic_type,
+        source_id: req.source_id,
+        target_table: req.target_table,
+        sql_expression: req.sql_expression,
+        default_constraints: sqlx::types::Json(req.default_constraints),
+        alias_names: req.alias_names,
+        default_agg: req.default_agg,
+        supported_dimension_ids: req.supported_dimension_ids,
+        dataset_id: req.dataset_id,
+        value_format: req.value_format,
+        unit: req.unit,
+        number_format: req.number_format,
+    })
+}
+
+/// 字段级差异（对象字段取并集，按字段名排序）
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let b = before.and_then(Value::as_object).unwrap_or(&empty);
+    let a = after.and_then(Value::as_object).unwrap_or(&empty);
+    let fields: BTreeSet<&String> = b.keys().chain(a.keys()).collect();
+    fields
+        .into_iter()
+        .filter_map(|f| {
+            let (x, y) = (b.get(f).cloned().unwrap_or(Value::Null), a.get(f).cloned().unwrap_or(Value::Null));
+            (x != y).then(|| FieldChange { field: f.clone(), before: x, after: y })
+        })
+        .collect()
+}
+
+async fn current_nodes(conn: &mut PgConnection, node_key: Option<&str>) -> sqlx::Result<Vec<FullSemanticNode>> {
+    sqlx::query_as::<Postgres, FullSemanticNode>(
+        r#"
+        SELECT n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression,
+               d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format, d.unit, d.number_format,
+               COALESCE(array_agg(r.dimension_node_id) FILTER (WHERE r.dimension_node_id IS NOT NULL), '{}') as supported_dimension_ids
+        FROM ontology_nodes n
+        JOIN semantic_definitions d ON n.id = d.node_id
+        LEFT JOIN metric_dimension_rels r ON n.id = r.metric_node_id
+        WHERE n.deleted_at IS NULL AND ($1::text IS NULL OR n.node_key = $1)
+        GROUP BY n.id, n.node_key, n.label, n.node_role, n.semantic_type, d.source_id, d.target_table, d.sql_expression, d.default_constraints, d.alias_names, d.default_agg, n.dataset_id, d.value_format, d.unit, d.number_format
+        "#,
+    )
+    .bind(node_key)
+    .fetch_all(conn)
+    .await
+}
+
+const RELATION_KEY_PREFIX: &str = "relation:";
+
+/// 关联的修订 key（关联没有 node_key，以自增 id 标识）
+pub fn relation_key(id: i32) -> String {
+    format!("{}{}", RELATION_KEY_PREFIX, id)
+}
+
+pub fn parse_relation_key(key: &str) -> Option<i32> {
+    key.strip_prefix(RELATION_KEY_PREFIX)?.parse().ok()
+}
+
+/// 关联的规范快照
+fn relation_snapshot(r: &OntologyRelation) -> Value {
+    json!({
+        "source_id": r.source_id,
+        "from_table": r.from_table,
+        "to_table": r.to_table,
+        "join_logic": r.join_logic,
+        "origin": r.origin,
+        "status": r.status
+    })
+}
+
+async fn current_relations(conn: &mut PgConnection) -> sqlx::Result<BTreeMap<String, (Option<Uuid>, Value)>> {
+    Ok(sqlx::query_as::<Postgres, OntologyRelation>("SELECT * FROM ontology_relations")
+        .fetch_all(conn)
+        .await?
+        .iter()
+        .map(|r| (relation_key(r.id), (None, relation_snapshot(r))))
+        .collect())
+}
+
+/// 各节点（或关联）最新修订：key -> (node_id, 快照)，已删除的快照为空
+async fn latest_revisions(
+    conn: &mut PgConnection,
+    kind: &str,
+    node_key: Option<&str>,
+    version: Option<i64>,
+) -> sqlx::Result<HashMap<String, (Option<Uuid>, Option<Value>)>> {
+    let rows = sqlx::query(
+        "SELECT DISTINCT ON (node_key) node_key, node_id, snapshot FROM ontology_revisions
+         WHERE kind = $1 AND ($2::text IS NULL OR node_key = $2) AND ($3::bigint IS NULL OR id <= $3)
+         ORDER BY node_key, id DESC",
+    )
+    .bind(kind)
+    .bind(node_key)
+    .bind(version)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.get(0), (r.get(1), r.get(2)))).collect())
+}
+
+/// 比对节点当前状态与最新修订，有变化则写入新修订；返回新增的版本号
+/// node_key 为空时比对全部节点（级联删除、启动基线）
+async fn record_changes(
+    tx: &mut Transaction<'_, Postgres>,
+    node_key: Option<&str>,
+    author: &Author,
+) -> sqlx::Result<Vec<i64>> {
+    let current: BTreeMap<String, (Option<Uuid>, Value)> = current_nodes(tx, node_key)
+        .await?
+        .iter()
+        .map(|n| (n.node_key.clone(), (Some(n.id), canonical(n))))
+        .collect();
+    write_revisions(tx, "NODE", current, node_key, author).await
+}
+
+/// 比对全部关联与最新修订并记录变更（在修改关联的同一事务内调用）
+pub async fn record_relations(tx: &mut Transaction<'_, Postgres>, author: &Author) -> sqlx::Result<Vec<i64>> {
+    let current = current_relations(tx).await?;
+    write_revisions(tx, "RELATION", current, None, author).await
+}
+
+async fn write_revisions(
+    tx: &mut Transaction<'_, Postgres>,
+    kind: &str,
+    current: BTreeMap<String, (Option<Uuid>, Value)>,
+    node_key: Option<&str>,
+    author: &Author,
+) -> sqlx::Result<Vec<i64>> {
+    let latest = latest_revisions(tx, kind, node_key, None).await?;
+
+    let keys: BTreeSet<&String> = current.keys().chain(latest.keys()).collect();
+    let mut versions = Vec::new();
+    for key in keys {
+        let (prev_id, prev) = latest.get(key).cloned().unwrap_or((None, None));
+        let cur = current.get(key);
+        let action = match (&prev, cur) {
+            (None, None) => continue,
+            (Some(p), Some((_, c))) if p == c => continue,
+            (None, Some(_)) => "CREATE",
+            (Some(_), Some(_)) => "UPDATE",
+            (Some(_), None) => "DELETE",
+        };
+        let after = cur.map(|(_, v)| v);
+        let changes = serde_json::to_value(diff(prev.as_ref(), after)).unwrap_or_default();
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO ontology_revisions (node_key, node_id, action, snapshot, diff, changed_by, via, kind)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+        )
+        .bind(key)
+        .bind(cur.and_then(|(id, _)| *id).or(prev_id))
+        .bind(action)
+        .bind(after)
+        .bind(changes)
+        .bind(&author.by)
+        .bind(&author.via)
+        .bind(kind)
+        .fetch_one(&mut **tx)
+        .await?;
+        versions.push(id);
+    }
+    Ok(versions)
+}
+
+/// 记录单个节点的变更（在写入节点的同一事务内调用）
+pub async fn record_node(tx: &mut Transaction<'_, Postgres>, node_key: &str, author: &Author) -> sqlx::Result<Vec<i64>> {
+    record_changes(tx, Some(node_key), author).await
+}
+
+/// 比对全部节点并记录变更（删除会级联影响其他节点的 T-Box 关联）
+pub async fn record_all(tx: &mut Transaction<'_, Postgres>, author: &Author) -> sqlx::Result<Vec<i64>> {
+    record_changes(tx, None, author).await
+}
+
+/// 启动时补齐基线修订：尚无历史的节点与关联（或库外直接修改过的）记为一个修订
+pub async fn ensure_baseline(db: &PgPool) -> anyhow::Result<usize> {
+    let mut tx = db.begin().await?;
+    let author = Author::new("system", "baseline");
+    let mut versions = record_all(&mut tx, &author).await?;
+    versions.extend(record_relations(&mut tx, &author).await?);
+    tx.commit().await?;
+    if !versions.is_empty() {
+        info!("本体修订基线已补齐: {} 条", versions.len());
+    }
+    Ok(versions.len())
+}
+
+// --- 2. 版本快照与差异 ---
+
+pub async fn latest_version(db: &PgPool) -> sqlx::Result<i64> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM ontology_revisions").fetch_one(db).await
+}
+
+/// 指定版本时的本体：各节点在该版本及之前的最新修订（已删除节点不含）
+pub async fn snapshot_at(db: &PgPool, version: i64) -> sqlx::Result<BTreeMap<String, (Uuid, Value)>> {
+    let mut conn = db.acquire().await?;
+    Ok(latest_revisions(&mut conn, "NODE", None, Some(version))
+        .await?
+        .into_iter()
+        .filter_map(|(key, (id, snap))| Some((key, (id?, snap?))))
+        .collect())
+}
+
+/// 指定版本时的关联（已删除关联不含）
+/// 关联纳入修订晚于节点：首条修订来自基线的关联视为此前一直存在，早于基线的版本也以它为准
+pub async fn relations_at(db: &PgPool, version: i64) -> sqlx::Result<BTreeMap<String, Value>> {
+    let rows = sqlx::query(
+        "WITH r AS (
+             SELECT node_key, id, snapshot,
+                    id = MIN(id) OVER (PARTITION BY node_key) AND via = 'baseline' AS seed
+             FROM ontology_revisions WHERE kind = 'RELATION'
+         )
+         SELECT DISTINCT ON (node_key) node_key, snapshot FROM r
+         WHERE id <= $1 OR seed
+         ORDER BY node_key, (id <= $1) DESC, id DESC",
+    )
+    .bind(version)
+    .fetch_all(db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| Some((r.get::<String, _>(0), r.get::<Option<Value>, _>(1)?)))
+        .collect())
+}
+
+/// 比较两个版本的本体（节点与关联）
+pub async fn diff_versions(db: &PgPool, from: i64, to: i64, node_key: Option<&str>) -> sqlx::Result<Vec<NodeChange>> {
+    let versioned = |nodes: BTreeMap<String, (Uuid, Value)>, relations: BTreeMap<String, Value>| {
+        nodes.into_iter().map(|(k, (_, v))| (k, v)).chain(relations).collect::<BTreeMap<String, Value>>()
+    };
+    let before = versioned(snapshot_at(db, from).await?, relations_at(db, from).await?);
+    let after = versioned(snapshot_at(db, to).await?, relations_at(db, to).await?);
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|k| node_key.is_none_or(|n| n == k.as_str()))
+        .filter_map(|k| {
+            let (b, a) = (before.get(k), after.get(k));
+            let action = match (b, a) {
+                (None, Some(_)) => "ADDED",
+                (Some(_), None) => "REMOVED",
+                (Some(x), Some(y)) if x != y => "CHANGED",
+                _ => return None,
+            };
+            Some(NodeChange { node_key: k.clone(), action: action.to_string(), changes: diff(b, a) })
+        })
+        .collect())
+}
+
+// --- 3. 发布快照 ---
+
+/// 当前生效的发布：最新一条未撤回的发布（全部撤回时为空，即服务实时本体）
+pub async fn current_publication(db: &PgPool) -> sqlx::Result<Option<OntologyPublication>> {
+    sqlx::query_as::<Postgres, OntologyPublication>(
+        "SELECT * FROM ontology_publications WHERE withdrawn_at IS NULL ORDER BY id DESC LIMIT 1",
+    )
+    .fetch_optional(db)
+    .await
+}
+
+/// 启动时确保线上服务发布快照：从未发布过时把当前本体作为基线发布
+/// 此后建模修改只进入草稿（实时本体），经校验并显式发布后才影响问数
+pub async fn ensure_published(db: &PgPool) -> anyhow::Result<()> {
+    let published: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ontology_publications").fetch_one(db).await?;
+    let latest = latest_version(db).await?;
+    if published == 0 && latest > 0 {
+        sqlx::query("INSERT INTO ontology_publications (version, note, published_by) VALUES ($1, '初始发布（基线）', 'system')")
+            .bind(latest)
+            .execute(db)
+            .await?;
+        info!("本体基线已发布: 版本 {}", latest);
+    }
+    Ok(())
+}
+
+/// 指定版本的节点（修订快照还原）
+pub async fn nodes_at(db: &PgPool, version: i64) -> sqlx::Result<Vec<FullSemanticNode>> {
+    Ok(snapshot_at(db, version)
+        .await?
+        .values()
+        .filter_map(|(id, snap)| snapshot_node(*id, snap))
+        .collect())
+}
+
+/// 推理引擎加载的节点：存在生效发布时取发布快照，否则取实时本体
+pub async fn load_serving_nodes(db: &PgPool) -> anyhow::Result<Vec<FullSemanticNode>> {
+    match current_publication(db).await? {
+        Some(p) => Ok(nodes_at(db, p.version).await?),
+        None => Ok(load_all_nodes(db).await?),
+    }
+}
+
+/// 当前发布中使用指定数据源的节点
+pub async fn serving_nodes_on_source(db: &PgPool, source_id: &str) -> sqlx::Result<Vec<String>> {
+    let Some(p) = current_publication(db).await? else {
+        return Ok(Vec::new());
+    };
+    Ok(nodes_at(db, p.version).await?.into_iter().filter(|n| n.source_id == source_id).map(|n| n.node_key).collect())
+}
+
+/// 物理删除已打删除标记、且不再被任何未撤回发布引用的节点（撤回时可能回退到较早的发布，因此一并保留）
+/// 此时才级联清理节点的安全策略、A-Box 码值与同义词、标签来源与同步调度
+pub async fn purge_deleted(db: &PgPool) -> sqlx::Result<Vec<String>> {
+    let purged: Vec<String> = sqlx::query_scalar(
+        "DELETE FROM ontology_nodes n WHERE n.deleted_at IS NOT NULL AND NOT EXISTS (
+             SELECT 1 FROM ontology_publications p
+             CROSS JOIN LATERAL (
+                 SELECT DISTINCT ON (r.node_key) r.node_id, r.snapshot FROM ontology_revisions r
+                 WHERE r.kind = 'NODE' AND r.id <= p.version ORDER BY r.node_key, r.id DESC
+             ) s
+             WHERE p.withdrawn_at IS NULL AND s.snapshot IS NOT NULL AND s.node_id = n.id
+         )
+         RETURNING n.node_key",
+    )
+    .fetch_all(db)
+    .await?;
+    if !purged.is_empty() {
+        info!("已物理删除 {} 个不再被发布引用的节点: {:?}", purged.len(), purged);
+    }
+    Ok(purged)
+}
+
+/// 丢弃草稿：把实时本体重置为指定版本（通常为当前发布），返回被重置的节点与关联
+/// 先（草稿）删除该版本之后新增的节点，再按先维度后指标写回：草稿中删除的节点只打了删除标记，
+/// 按 node_key 恢复后 id 不变，安全策略与 A-Box 码值等仍然有效；已被物理删除（删除已发布）的节点
+/// 沿用快照中的 id 重建，但其码值、同义词、标签来源、同步调度与策略已随物理删除清理，需重新同步与配置。
+/// 已不存在的数据集归属置空、数据源已删除的关联跳过。重置本身记为新的修订
+pub async fn reset_to(db: &PgPool, version: i64, author: &Author) -> anyhow::Result<Vec<String>> {
+    let latest = latest_version(db).await?;
+    let changed: Vec<String> = diff_versions(db, version, latest, None).await?.into_iter().map(|c| c.node_key).collect();
+    if changed.is_empty() {
+        return Ok(changed);
+    }
+    let target = snapshot_at(db, version).await?;
+    let target_relations = relations_at(db, version).await?;
+    let datasets: BTreeSet<Uuid> =
+        sqlx::query_scalar("SELECT id FROM semantic_datasets").fetch_all(db).await?.into_iter().collect();
+    let sources: BTreeSet<String> =
+        sqlx::query_scalar("SELECT id FROM data_sources").fetch_all(db).await?.into_iter().collect();
+
+    let mut tx = db.begin().await?;
+    let keys: Vec<&String> = target.keys().collect();
+    let extra: Vec<Uuid> =
+        sqlx::query_scalar("SELECT id FROM ontology_nodes WHERE deleted_at IS NULL AND NOT (node_key = ANY($1))")
+            .bind(&keys)
+            .fetch_all(&mut *tx)
+            .await?;
+    mark_deleted(&mut tx, &extra).await?;
+
+    let mut reqs: Vec<(Uuid, CreateNodeRequest)> = target
+        .values()
+        .filter_map(|(id, snap)| Some((*id, serde_json::from_value::<CreateNodeRequest>(snap.clone()).ok()?)))
+        .collect();
+    reqs.sort_by_key(|(_, r)| r.node_role == "METRIC");
+
+    let mut id_map: HashMap<Uuid, Uuid> = HashMap::new();
+    for (old_id, mut req) in reqs {
+        req.supported_dimension_ids = req.supported_dimension_ids.iter().filter_map(|d| id_map.get(d).copied()).collect();
+        req.dataset_id = req.dataset_id.filter(|ds| datasets.contains(ds));
+        // 已物理删除的节点按快照中的原 id 重建，使发布快照中的节点 id 与实时本体一致
+        sqlx::query(
+            "INSERT INTO ontology_nodes (id, node_key, label, node_role, semantic_type, dataset_id)
+             VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+        )
+        .bind(old_id)
+        .bind(&req.node_key)
+        .bind(&req.label)
+        .bind(&req.node_role)
+        .bind(&req.semantic_type)
+        .bind(req.dataset_id)
+        .execute(&mut *tx)
+        .await?;
+        let new_id = write_node(&mut tx, &req, author).await.map_err(anyhow::Error::msg)?;
+        id_map.insert(old_id, new_id);
+    }
+    record_all(&mut tx, author).await?;
+
+    // 关联：删除目标版本中没有的，按原 id 写回目标版本的定义
+    let relation_ids: Vec<i32> = target_relations.keys().filter_map(|k| parse_relation_key(k)).collect();
+    sqlx::query("DELETE FROM ontology_relations WHERE NOT (id = ANY($1))")
+        .bind(&relation_ids)
+        .execute(&mut *tx)
+        .await?;
+    for (key, snap) in &target_relations {
+        let (Some(id), Some(source_id)) = (parse_relation_key(key), snap["source_id"].as_str()) else {
+            continue;
+        };
+        if !sources.contains(source_id) {
+            continue;
+        }
+        write_relation(&mut tx, id, snap).await?;
+    }
+    record_relations(&mut tx, author).await?;
+
+    tx.commit().await?;
+    info!("草稿已重置为版本 {}: {} 个节点 / 关联", version, changed.len());
+    Ok(changed)
+}
+
+/// 按 id 写回关联快照（同一关联以新 id 重新登记过时先移除，避免唯一约束冲突）
+pub async fn write_relation(tx: &mut Transaction<'_, Postgres>, id: i32, snap: &Value) -> sqlx::Result<()> {
+    let text = |f: &str| snap[f].as_str().map(str::to_string);
+    sqlx::query(
+        "DELETE FROM ontology_relations WHERE id <> $1 AND source_id IS NOT DISTINCT FROM $2
+           AND from_table IS NOT DISTINCT FROM $3 AND to_table IS NOT DISTINCT FROM $4 AND join_logic = $5",
+    )
+    .bind(id)
+    .bind(text("source_id"))
+    .bind(text("from_table"))
+    .bind(text("to_table"))
+    .bind(text("join_logic").unwrap_or_default())
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO ontology_relations (id, source_id, from_table, to_table, join_logic, origin, status)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (id) DO UPDATE SET source_id = EXCLUDED.source_id, from_table = EXCLUDED.from_table,
+             to_table = EXCLUDED.to_table, join_logic = EXCLUDED.join_logic, origin = EXCLUDED.origin, status = EXCLUDED.status",
+    )
+    .bind(id)
+    .bind(text("source_id"))
+    .bind(text("from_table"))
+    .bind(text("to_table"))
+    .bind(text("join_logic").unwrap_or_default())
+    .bind(text("origin").unwrap_or_else(|| "MANUAL".to_string()))
+    .bind(text("status").unwrap_or_else(|| "ACCEPTED".to_string()))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
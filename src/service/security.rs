@@ -0,0 +1,281 @@
+use crate::ax_state::AppState;
+use crate::infra::connector::SqlDialect;
+use crate::models::auth::{Principal, Role, SecurityPolicy};
+use crate::models::schema::{FullSemanticNode, QueryLogicalPlan};
+use regex::{Captures, Regex};
+use sqlx::Postgres;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tracing::debug;
+use uuid::Uuid;
+
+/// 单次请求的安全上下文：调用方不可见的节点与需注入的行级谓词
+pub struct SecurityContext {
+    policies: Vec<SecurityPolicy>,
+    attributes: HashMap<String, Vec<String>>,
+    /// 行级策略所挂节点的物理位置：node_id -> (source_id, target_table)
+    policy_tables: HashMap<Uuid, (String, String)>,
+    pub hidden_nodes: HashSet<Uuid>,
+}
+
+impl SecurityContext {
+    /// 加载调用方角色生效的策略（管理员不受约束）
+    pub async fn load(state: &AppState, principal: &Principal) -> anyhow::Result<Self> {
+        if principal.role == Role::Admin {
+            return Ok(Self {
+                policies: Vec::new(),
+                attributes: HashMap::new(),
+                policy_tables: HashMap::new(),
+                hidden_nodes: HashSet::new(),
+            });
+        }
+
+        let policies = sqlx::query_as::<Postgres, SecurityPolicy>(
+            "SELECT * FROM security_policies WHERE enabled AND (role IS NULL OR role = $1)",
+        )
+        .bind(principal.role.as_str())
+        .fetch_all(&state.db)
+        .await?;
+
+        // HIDE 策略：节点级直接隐藏，数据源级隐藏其下全部节点
+        let hide = policies.iter().filter(|p| p.effect == "HIDE");
+        let hidden_ids: HashSet<Uuid> = hide.clone().filter_map(|p| p.node_id).collect();
+        let hidden_sources: HashSet<&str> = hide.filter_map(|p| p.source_id.as_deref()).collect();
+        let filter_ids: HashSet<Uuid> =
+            policies.iter().filter(|p| p.effect == "ROW_FILTER").filter_map(|p| p.node_id).collect();
+        let fst = state.fst.read().await;
+        let mut hidden_nodes = HashSet::new();
+        let mut policy_tables = HashMap::new();
+        for e in fst.node_cache.iter() {
+            let n = e.value();
+            if hidden_ids.contains(&n.id) || hidden_sources.contains(n.source_id.as_str()) {
+                hidden_nodes.insert(n.id);
+            }
+            if filter_ids.contains(&n.id) {
+                policy_tables.insert(n.id, (n.source_id.clone(), n.target_table.clone()));
+            }
+        }
+        drop(fst);
+
+        debug!(
+            "安全上下文: subject={}, 策略 {} 条, 隐藏节点 {} 个",
+            principal.subject,
+            policies.len(),
+            hidden_nodes.len()
+        );
+        Ok(Self {
+            policies,
+            attributes: principal.attributes.clone(),
+            policy_tables,
+            hidden_nodes,
+        })
+    }
+
+    pub fn is_visible(&self, node: &FullSemanticNode) -> bool {
+        !self.hidden_nodes.contains(&node.id)
+    }
+
+    /// 校验计划中的节点均可见，并注入命中的行级谓词
+    /// 命中条件：策略挂在计划引用的节点、指标支持的维度、与指标同一物理表的节点上，或挂在指标所属数据源上
+    /// 因此只问指标总量、未提及受保护维度的问题同样会注入谓词
+    /// 谓词中的属性值按目标数据源方言转义为字面量
+    pub fn apply(&self, plan: &mut QueryLogicalPlan, dialect: SqlDialect) -> Result<(), String> {
+        let nodes: Vec<&FullSemanticNode> = std::iter::once(&plan.metric)
+            .chain(plan.dimensions.iter().map(|(d, _)| d))
+            .chain(plan.group_by.iter())
+            .collect();
+        if let Some(n) = nodes.iter().find(|n| !self.is_visible(n)) {
+            return Err(format!("无权访问语义节点: {}", n.label));
+        }
+
+        let metric = &plan.metric;
+        let node_ids: HashSet<Uuid> = nodes
+            .iter()
+            .map(|n| n.id)
+            .chain(metric.supported_dimension_ids.iter().copied())
+            .collect();
+        let same_table = |id: &Uuid| {
+            self.policy_tables
+                .get(id)
+                .is_some_and(|(src, table)| *src == metric.source_id && *table == metric.target_table)
+        };
+        for p in self.policies.iter().filter(|p| p.effect == "ROW_FILTER") {
+            let hit = p.node_id.is_some_and(|id| node_ids.contains(&id) || same_table(&id))
+                || p.source_id.as_deref() == Some(metric.source_id.as_str());
+            let Some(predicate) = p.predicate.as_deref().filter(|_| hit) else {
+                continue;
+            };
+            let rendered = render_predicate(predicate, &self.attributes, dialect);
+            if !plan.security_filters.contains(&rendered) {
+                debug!("注入行级安全谓词 [{}]: {}", p.name, rendered);
+                plan.security_filters.push(rendered);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 展开谓词中的 user.<属性> 为 SQL 字面量列表（可直接用于 IN）
+/// 仅匹配独立的 user.（不含 superuser.x、t.user.x），引号内的字面量与标识符原样保留
+/// 任一属性缺失或为空时整条谓词退化为 1 = 0，即默认拒绝
+pub fn render_predicate(predicate: &str, attributes: &HashMap<String, Vec<String>>, dialect: SqlDialect) -> String {
+    static USER_ATTR: OnceLock<Regex> = OnceLock::new();
+    let re = USER_ATTR.get_or_init(|| {
+        Regex::new(r#"'(?:[^']|'')*'|"(?:[^"]|"")*"|`[^`]*`|(\.?)\buser\.([A-Za-z_][A-Za-z0-9_]*)"#).unwrap()
+    });
+    let mut missing = false;
+    let rendered = re.replace_all(predicate, |caps: &Captures| {
+        let (Some(attr), Some("")) = (caps.get(2), caps.get(1).map(|m| m.as_str())) else {
+            return caps[0].to_string();
+        };
+        match attributes.get(attr.as_str()).filter(|v| !v.is_empty()) {
+            Some(values) => format!(
+                "({})",
+                values
+                    .iter()
+                    .map(|v| dialect.quote_literal(v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None => {
+                missing = true;
+                String::new()
+            }
+        }
+    });
+    if missing {
+        "1 = 0".to_string()
+    } else {
+        format!("({})", rendered)
+    }
+}
+
+/// 策略谓词只允许单个布尔表达式（禁止语句分隔与注释）
+pub fn validate_predicate(predicate: &str) -> Result<(), String> {
+    let p = predicate.trim();
+    if p.is_empty() {
+        return Err("predicate 不能为空".to_string());
+    }
+    if [";", "--", "/*", "*/"].iter().any(|t| p.contains(t)) {
+        return Err("predicate 不允许包含语句分隔符或注释".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::inference::InferenceResult;
+    use crate::core::planner;
+
+    fn node(key: &str, role: &str, table: &str) -> FullSemanticNode {
+        FullSemanticNode { target_table: table.to_string(), ..FullSemanticNode::stub(key, key, role, "STRING") }
+    }
+
+    fn row_filter(node: &FullSemanticNode) -> SecurityPolicy {
+        SecurityPolicy {
+            id: Uuid::new_v4(),
+            name: "platform_scope".to_string(),
+            role: None,
+            node_id: Some(node.id),
+            source_id: None,
+            effect: "ROW_FILTER".to_string(),
+            predicate: Some("platform_name IN user.platforms".to_string()),
+            enabled: true,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn context(policies: Vec<SecurityPolicy>, nodes: &[&FullSemanticNode]) -> SecurityContext {
+        SecurityContext {
+            policies,
+            attributes: HashMap::from([("platforms".to_string(), vec!["A".to_string()])]),
+            policy_tables: nodes.iter().map(|n| (n.id, (n.source_id.clone(), n.target_table.clone()))).collect(),
+            hidden_nodes: HashSet::new(),
+        }
+    }
+
+    fn total_plan(metric: &FullSemanticNode) -> QueryLogicalPlan {
+        planner::build_plan(
+            "总收益",
+            InferenceResult { metric: metric.clone(), filters: Vec::new(), group_by: Vec::new() },
+        )
+    }
+
+    #[test]
+    fn metric_total_gets_filter_of_supported_dimension() {
+        let platform = node("platform", "DIMENSION", "t_revenue");
+        let mut revenue = node("revenue", "METRIC", "t_other");
+        revenue.supported_dimension_ids = vec![platform.id];
+        let ctx = context(vec![row_filter(&platform)], &[&platform]);
+
+        let mut plan = total_plan(&revenue);
+        ctx.apply(&mut plan, SqlDialect::Postgres).unwrap();
+        assert_eq!(plan.security_filters, vec!["(platform_name IN ('A'))".to_string()]);
+    }
+
+    #[test]
+    fn metric_total_gets_filter_of_same_table_node() {
+        let platform = node("platform", "DIMENSION", "t_revenue");
+        let revenue = node("revenue", "METRIC", "t_revenue");
+        let ctx = context(vec![row_filter(&platform)], &[&platform]);
+
+        let mut plan = total_plan(&revenue);
+        ctx.apply(&mut plan, SqlDialect::Postgres).unwrap();
+        assert!(!plan.security_filters.is_empty());
+    }
+
+    #[test]
+    fn unrelated_table_is_not_filtered() {
+        let platform = node("platform", "DIMENSION", "t_revenue");
+        let sales = node("sales", "METRIC", "t_sales");
+        let ctx = context(vec![row_filter(&platform)], &[&platform]);
+
+        let mut plan = total_plan(&sales);
+        ctx.apply(&mut plan, SqlDialect::Postgres).unwrap();
+        assert!(plan.security_filters.is_empty());
+    }
+
+    #[test]
+    fn render_predicate_escapes_attributes_per_dialect() {
+        let attrs = HashMap::from([("platforms".to_string(), vec!["A".to_string(), "\\' OR 1=1 -- ".to_string()])]);
+        assert_eq!(
+            render_predicate("platform_name IN user.platforms", &attrs, SqlDialect::MySql),
+            "(platform_name IN ('A', '\\\\'' OR 1=1 -- '))"
+        );
+        assert_eq!(
+            render_predicate("platform_name IN user.platforms", &attrs, SqlDialect::Postgres),
+            "(platform_name IN ('A', '\\'' OR 1=1 -- '))"
+        );
+    }
+
+    #[test]
+    fn render_predicate_only_expands_standalone_user_attributes() {
+        let attrs = HashMap::from([("name".to_string(), vec!["bob".to_string()])]);
+        let render = |p: &str| render_predicate(p, &attrs, SqlDialect::Postgres);
+        assert_eq!(render("owner IN user.name"), "(owner IN ('bob'))");
+        assert_eq!(render("(owner IN user.name)"), "((owner IN ('bob')))");
+        // 标识符内部、限定名与引号内的 user. 原样保留
+        assert_eq!(render("superuser.name = 1"), "(superuser.name = 1)");
+        assert_eq!(render("t.user.name = 1"), "(t.user.name = 1)");
+        assert_eq!(render("note <> 'user.name' AND owner IN user.name"), "(note <> 'user.name' AND owner IN ('bob'))");
+        assert_eq!(render("note <> 'it''s user.name'"), "(note <> 'it''s user.name')");
+        assert_eq!(render(r#""user.name" IN user.name"#), r#"("user.name" IN ('bob'))"#);
+        // 未知属性仍默认拒绝，引号内的未知属性不触发拒绝
+        assert_eq!(render("owner IN user.team"), "1 = 0");
+        assert_eq!(render("note = 'user.team'"), "(note = 'user.team')");
+    }
+
+    #[test]
+    fn render_predicate_denies_on_missing_attribute() {
+        assert_eq!(render_predicate("region IN user.regions", &HashMap::new(), SqlDialect::Postgres), "1 = 0");
+    }
+
+    #[test]
+    fn hidden_metric_is_rejected() {
+        let revenue = node("revenue", "METRIC", "t_revenue");
+        let mut ctx = context(Vec::new(), &[]);
+        ctx.hidden_nodes.insert(revenue.id);
+        assert!(ctx.apply(&mut total_plan(&revenue), SqlDialect::Postgres).is_err());
+    }
+}
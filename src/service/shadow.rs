@@ -0,0 +1,106 @@
+use crate::ax_state::AppState;
+use crate::core::llm_fallback;
+use crate::core::planner::{self, CanonicalPlan};
+use crate::models::schema::QueryLogicalPlan;
+use std::env;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// 影子队列容量：队列满时直接丢弃样本，绝不阻塞用户请求
+const SHADOW_QUEUE_CAPACITY: usize = 256;
+
+/// 一次影子执行任务：用户提问 + 规则推理给出的逻辑计划
+pub struct ShadowJob {
+    pub question: String,
+    pub plan: QueryLogicalPlan,
+}
+
+/// 影子执行流水线的投递端（挂载在 AppState 上）
+#[derive(Clone)]
+pub struct ShadowRunner {
+    tx: mpsc::Sender<ShadowJob>,
+}
+
+impl ShadowRunner {
+    /// 按环境变量 SHADOW_RUN_ENABLED 创建投递端与接收端
+    pub fn from_env() -> Option<(Self, mpsc::Receiver<ShadowJob>)> {
+        let enabled = env::var("SHADOW_RUN_ENABLED")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel(SHADOW_QUEUE_CAPACITY);
+        Some((Self { tx }, rx))
+    }
+
+    /// 非阻塞投递
+    pub fn submit(&self, job: ShadowJob) {
+        if self.tx.try_send(job).is_err() {
+            debug!("影子执行队列已满或已关闭，丢弃本次样本");
+        }
+    }
+}
+
+/// 启动后台影子执行任务
+pub fn spawn_worker(state: Arc<AppState>, mut rx: mpsc::Receiver<ShadowJob>) {
+    tokio::spawn(async move {
+        info!("👥 影子执行流水线已启动");
+        while let Some(job) = rx.recv().await {
+            if let Err(e) = run_one(&state, &job).await {
+                warn!("影子执行失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 单次影子执行：LLM 规划 -> 规范化 -> 哈希比对 -> 差异入库
+async fn run_one(state: &AppState, job: &ShadowJob) -> anyhow::Result<()> {
+    let Some(llm) = state.llm.as_ref() else {
+        return Ok(());
+    };
+
+    let nodes = llm_fallback::load_nodes(state).await;
+    let rels = llm_fallback::load_dimension_rels(state).await?;
+    let catalog = llm_fallback::build_catalog(&nodes, &rels);
+
+    let rule_plan = planner::canonicalize(&job.plan);
+    let rule_hash = planner::plan_hash(&rule_plan);
+
+    // 1. LLM 调用失败属于基础设施问题，不计入语义差异
+    let draft = llm.plan(&catalog, &job.question).await?;
+
+    // 2. 校验失败视为一次差异，保留原始草案便于复盘
+    let (llm_plan, llm_hash, llm_error) =
+        match llm_fallback::resolve_draft(&state.db, &nodes, &rels, &job.question, &draft).await {
+            Ok(plan) => {
+                let canonical: CanonicalPlan = planner::canonicalize(&plan);
+                let hash = planner::plan_hash(&canonical);
+                (serde_json::to_value(&canonical)?, Some(hash), None)
+            }
+            Err(e) => (serde_json::to_value(&draft)?, None, Some(e.to_string())),
+        };
+
+    if llm_hash.as_deref() == Some(rule_hash.as_str()) {
+        debug!("影子执行一致: {}", job.question);
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO shadow_runs (question, provider, rule_plan, llm_plan, rule_hash, llm_hash, llm_error)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(&job.question)
+    .bind(llm.name())
+    .bind(serde_json::to_value(&rule_plan)?)
+    .bind(llm_plan)
+    .bind(&rule_hash)
+    .bind(&llm_hash)
+    .bind(&llm_error)
+    .execute(&state.db)
+    .await?;
+
+    info!("👥 影子执行发现语义偏差: {} (rule={}, llm={:?})", job.question, &rule_hash[..12], llm_hash.as_deref().map(|h| &h[..12]));
+    Ok(())
+}
@@ -0,0 +1,1200 @@
+use crate::ax_state::AppState;
+use crate::service::bundle;
+use crate::service::rdf::{bundle_to_graph, Namespaces, Term, Triple, RDF, XSD};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+/// 单次查询最多返回的解数量
+const MAX_SOLUTIONS: usize = 10_000;
+
+// --- 1. 内存三元组存储 ---
+
+/// 本体 T-Box / A-Box 的内存 RDF 视图，随本体热重载整体重建
+#[derive(Default)]
+pub struct TripleStore {
+    triples: Vec<Triple>,
+    by_subject: HashMap<Term, Vec<usize>>,
+    by_predicate: HashMap<String, Vec<usize>>,
+    by_object: HashMap<Term, Vec<usize>>,
+    /// 查询中可直接使用的默认前缀
+    prefixes: Vec<(String, String)>,
+}
+
+impl TripleStore {
+    pub fn new(triples: Vec<Triple>, prefixes: Vec<(String, String)>) -> Self {
+        let mut store = Self { prefixes, ..Default::default() };
+        for (i, t) in triples.iter().enumerate() {
+            store.by_subject.entry(t.subject.clone()).or_default().push(i);
+            store.by_predicate.entry(t.predicate.clone()).or_default().push(i);
+            store.by_object.entry(t.object.clone()).or_default().push(i);
+        }
+        store.triples = triples;
+        store
+    }
+
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    pub fn prefixes(&self) -> &[(String, String)] {
+        &self.prefixes
+    }
+
+    /// 按已绑定位置选择最小的索引扫描
+    fn matching<'a>(
+        &'a self,
+        s: Option<&Term>,
+        p: Option<&str>,
+        o: Option<&Term>,
+    ) -> Box<dyn Iterator<Item = &'a Triple> + 'a> {
+        let mut candidates: Vec<&Vec<usize>> = Vec::new();
+        if let Some(s) = s {
+            candidates.push(self.by_subject.get(s).unwrap_or(&EMPTY));
+        }
+        if let Some(p) = p {
+            candidates.push(self.by_predicate.get(p).unwrap_or(&EMPTY));
+        }
+        if let Some(o) = o {
+            candidates.push(self.by_object.get(o).unwrap_or(&EMPTY));
+        }
+        let (s, p, o) = (s.cloned(), p.map(String::from), o.cloned());
+        let filter = move |t: &&Triple| {
+            s.as_ref().is_none_or(|s| &t.subject == s)
+                && p.as_ref().is_none_or(|p| &t.predicate == p)
+                && o.as_ref().is_none_or(|o| &t.object == o)
+        };
+        match candidates.into_iter().min_by_key(|c| c.len()) {
+            Some(idx) => Box::new(idx.iter().map(|i| &self.triples[*i]).filter(filter)),
+            None => Box::new(self.triples.iter().filter(filter)),
+        }
+    }
+}
+
+static EMPTY: Vec<usize> = Vec::new();
+
+/// 从内部库重建三元组存储（含码值 A-Box），与 FST / 分词词典同步热刷新
+pub async fn refresh(state: &AppState) -> anyhow::Result<()> {
+    let bundle = bundle::export(&state.db, true).await?;
+    let ns = Namespaces::new(None);
+    let store = TripleStore::new(bundle_to_graph(&bundle, &ns), ns.prefixes());
+    let count = store.len();
+    *state.rdf.write().await = store;
+    info!("🕸️ 本体三元组存储已刷新: {} 条", count);
+    Ok(())
+}
+
+// --- 2. 查询语法树 ---
+
+/// 支持的 SPARQL 子集：SELECT [DISTINCT] / ASK；基本图模式、OPTIONAL、UNION、FILTER（含 [NOT] EXISTS）；
+/// COUNT 聚合与 GROUP BY；ORDER BY、LIMIT、OFFSET
+#[derive(Debug)]
+pub struct Query {
+    form: QueryForm,
+    pattern: GroupPattern,
+    group_by: Vec<String>,
+    order_by: Vec<(Expr, bool)>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum QueryForm {
+    Select { distinct: bool, projection: Option<Vec<Projection>> },
+    Ask,
+}
+
+#[derive(Debug)]
+enum Projection {
+    Var(String),
+    Count { var: Option<String>, distinct: bool, alias: String },
+}
+
+#[derive(Debug, Clone)]
+enum PatternTerm {
+    Var(String),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+struct TriplePattern {
+    s: PatternTerm,
+    p: PatternTerm,
+    o: PatternTerm,
+}
+
+#[derive(Debug, Clone, Default)]
+struct GroupPattern {
+    elements: Vec<Element>,
+    filters: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+enum Element {
+    Triples(Vec<TriplePattern>),
+    Optional(GroupPattern),
+    Union(Vec<GroupPattern>),
+    Group(GroupPattern),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Box<Expr>, &'static str, Box<Expr>),
+    Var(String),
+    Const(Term),
+    Call(String, Vec<Expr>),
+    Exists(bool, GroupPattern),
+}
+
+type Binding = HashMap<String, Term>;
+
+// --- 3. 词法分析 ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Iri(String),
+    PName(String, String),
+    Var(String),
+    Str(String),
+    LangTag(String),
+    DataType,
+    Number(String),
+    Word(String),
+    Punct(char),
+    Op(&'static str),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // 括号栈：位于 "(" 内（表达式）且紧跟操作数时，"<" 只能是比较运算符
+    let mut brackets: Vec<char> = Vec::new();
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '<' {
+            // 表达式中紧跟操作数时是比较运算符；否则 "<" 后紧跟无空白内容并以 ">" 结束时是 IRI
+            let after_operand = brackets.last() == Some(&'(')
+                && matches!(
+                    tokens.last(),
+                    Some(Token::Var(_) | Token::Str(_) | Token::Number(_) | Token::Iri(_) | Token::PName(..))
+                        | Some(Token::LangTag(_) | Token::Word(_) | Token::Punct(')'))
+                );
+            let end = chars[i + 1..].iter().position(|c| *c == '>' || c.is_whitespace()).map(|p| p + i + 1);
+            match end {
+                Some(e) if !after_operand && chars[e] == '>' => {
+                    tokens.push(Token::Iri(chars[i + 1..e].iter().collect()));
+                    i = e + 1;
+                }
+                _ if chars.get(i + 1) == Some(&'=') => {
+                    tokens.push(Token::Op("<="));
+                    i += 2;
+                }
+                _ => {
+                    tokens.push(Token::Op("<"));
+                    i += 1;
+                }
+            }
+        } else if c == '?' || c == '$' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && is_name(chars[i]) {
+                i += 1;
+            }
+            if i == start {
+                return Err("变量名不能为空".into());
+            }
+            tokens.push(Token::Var(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let long = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+            i += if long { 3 } else { 1 };
+            let mut s = String::new();
+            loop {
+                let Some(&ch) = chars.get(i) else {
+                    return Err("字符串未闭合".into());
+                };
+                if ch == c && (!long || (chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c))) {
+                    i += if long { 3 } else { 1 };
+                    break;
+                }
+                if ch == '\\' {
+                    i += 1;
+                    s.push(match chars.get(i) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(other) => *other,
+                        None => return Err("字符串未闭合".into()),
+                    });
+                } else {
+                    s.push(ch);
+                }
+                i += 1;
+            }
+            tokens.push(Token::Str(s));
+        } else if c == '@' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '-') {
+                i += 1;
+            }
+            tokens.push(Token::LangTag(chars[start..i].iter().collect()));
+        } else if c == '^' && chars.get(i + 1) == Some(&'^') {
+            tokens.push(Token::DataType);
+            i += 2;
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || (chars[i] == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()))) {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' || c == ':' {
+            let start = i;
+            while i < chars.len() && is_name(chars[i]) {
+                i += 1;
+            }
+            let prefix: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&':') {
+                i += 1;
+                let local_start = i;
+                while i < chars.len()
+                    && (is_name(chars[i])
+                        || chars[i] == '%'
+                        || (chars[i] == '.' && chars.get(i + 1).is_some_and(|n| is_name(*n))))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::PName(prefix, chars[local_start..i].iter().collect()));
+            } else {
+                tokens.push(Token::Word(prefix));
+            }
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = match two.as_str() {
+                "!=" => Some("!="),
+                ">=" => Some(">="),
+                "&&" => Some("&&"),
+                "||" => Some("||"),
+                _ => None,
+            };
+            if let Some(op) = op {
+                tokens.push(Token::Op(op));
+                i += 2;
+                continue;
+            }
+            match c {
+                '=' => tokens.push(Token::Op("=")),
+                '>' => tokens.push(Token::Op(">")),
+                '!' => tokens.push(Token::Op("!")),
+                '{' | '(' => {
+                    brackets.push(c);
+                    tokens.push(Token::Punct(c));
+                }
+                '}' | ')' => {
+                    brackets.pop();
+                    tokens.push(Token::Punct(c));
+                }
+                '.' | ';' | ',' | '*' => tokens.push(Token::Punct(c)),
+                other => return Err(format!("无法识别的字符 '{}'", other)),
+            }
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+// --- 4. 语法分析 ---
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    prefixes: HashMap<String, String>,
+}
+
+pub fn parse_query(text: &str, default_prefixes: &[(String, String)]) -> Result<Query, String> {
+    let mut p = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        prefixes: default_prefixes.iter().cloned().collect(),
+    };
+    p.query()
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn is_word(&self, w: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(x)) if x.eq_ignore_ascii_case(w))
+    }
+
+    fn eat_word(&mut self, w: &str) -> bool {
+        let hit = self.is_word(w);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let hit = self.peek() == Some(&Token::Punct(c));
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), String> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            Err(format!("期望 '{}'，实际为 {:?}", c, self.peek()))
+        }
+    }
+
+    fn expect_word(&mut self, w: &str) -> Result<(), String> {
+        if self.eat_word(w) {
+            Ok(())
+        } else {
+            Err(format!("期望 {}，实际为 {:?}", w, self.peek()))
+        }
+    }
+
+    fn var(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Var(v)) => Ok(v),
+            other => Err(format!("期望变量，实际为 {:?}", other)),
+        }
+    }
+
+    fn expand(&self, prefix: &str, local: &str) -> Result<String, String> {
+        self.prefixes
+            .get(prefix)
+            .map(|ns| format!("{}{}", ns, local))
+            .ok_or_else(|| format!("未声明的前缀: {}", prefix))
+    }
+
+    fn query(&mut self) -> Result<Query, String> {
+        loop {
+            if self.eat_word("PREFIX") {
+                let Some(Token::PName(prefix, local)) = self.next() else {
+                    return Err("PREFIX 声明格式错误".into());
+                };
+                if !local.is_empty() {
+                    return Err("PREFIX 声明格式错误".into());
+                }
+                let Some(Token::Iri(iri)) = self.next() else {
+                    return Err("PREFIX 声明缺少 IRI".into());
+                };
+                self.prefixes.insert(prefix, iri);
+            } else if self.eat_word("BASE") {
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        let form = if self.eat_word("SELECT") {
+            let distinct = self.eat_word("DISTINCT");
+            let projection = if self.eat_punct('*') {
+                None
+            } else {
+                let mut items = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(Token::Var(_)) => items.push(Projection::Var(self.var()?)),
+                        Some(Token::Punct('(')) => {
+                            self.pos += 1;
+                            self.expect_word("COUNT")?;
+                            self.expect_punct('(')?;
+                            let distinct = self.eat_word("DISTINCT");
+                            let var = if self.eat_punct('*') { None } else { Some(self.var()?) };
+                            self.expect_punct(')')?;
+                            self.expect_word("AS")?;
+                            let alias = self.var()?;
+                            self.expect_punct(')')?;
+                            items.push(Projection::Count { var, distinct, alias });
+                        }
+                        _ => break,
+                    }
+                }
+                if items.is_empty() {
+                    return Err("SELECT 缺少投影变量".into());
+                }
+                Some(items)
+            };
+            QueryForm::Select { distinct, projection }
+        } else if self.eat_word("ASK") {
+            QueryForm::Ask
+        } else {
+            return Err("仅支持 SELECT 与 ASK 查询".into());
+        };
+
+        self.eat_word("WHERE");
+        let pattern = self.group()?;
+
+        let mut group_by = Vec::new();
+        if self.eat_word("GROUP") {
+            self.expect_word("BY")?;
+            while let Some(Token::Var(_)) = self.peek() {
+                group_by.push(self.var()?);
+            }
+        }
+        let mut order_by = Vec::new();
+        if self.eat_word("ORDER") {
+            self.expect_word("BY")?;
+            loop {
+                if self.is_word("ASC") || self.is_word("DESC") {
+                    let desc = self.is_word("DESC");
+                    self.pos += 1;
+                    self.expect_punct('(')?;
+                    let e = self.expr()?;
+                    self.expect_punct(')')?;
+                    order_by.push((e, desc));
+                } else if let Some(Token::Var(_)) = self.peek() {
+                    order_by.push((Expr::Var(self.var()?), false));
+                } else {
+                    break;
+                }
+            }
+        }
+        let (mut limit, mut offset) = (None, 0);
+        loop {
+            if self.eat_word("LIMIT") {
+                limit = Some(self.number()?);
+            } else if self.eat_word("OFFSET") {
+                offset = self.number()?;
+            } else {
+                break;
+            }
+        }
+        if let Some(t) = self.peek() {
+            return Err(format!("查询末尾存在无法识别的内容: {:?}", t));
+        }
+        Ok(Query { form, pattern, group_by, order_by, limit, offset })
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        match self.next() {
+            Some(Token::Number(n)) => n.parse().map_err(|_| format!("非法数值: {}", n)),
+            other => Err(format!("期望数值，实际为 {:?}", other)),
+        }
+    }
+
+    fn group(&mut self) -> Result<GroupPattern, String> {
+        self.expect_punct('{')?;
+        let mut g = GroupPattern::default();
+        loop {
+            if self.eat_punct('}') {
+                return Ok(g);
+            }
+            if self.eat_punct('.') {
+                continue;
+            }
+            if self.eat_word("OPTIONAL") {
+                g.elements.push(Element::Optional(self.group()?));
+            } else if self.eat_word("FILTER") {
+                g.filters.push(self.filter_expr()?);
+            } else if self.peek() == Some(&Token::Punct('{')) {
+                let first = self.group()?;
+                let mut branches = vec![first];
+                while self.eat_word("UNION") {
+                    branches.push(self.group()?);
+                }
+                if branches.len() == 1 {
+                    g.elements.push(Element::Group(branches.remove(0)));
+                } else {
+                    g.elements.push(Element::Union(branches));
+                }
+            } else if self.peek().is_none() {
+                return Err("图模式未闭合".into());
+            } else {
+                g.elements.push(Element::Triples(self.triples_block()?));
+            }
+        }
+    }
+
+    fn filter_expr(&mut self) -> Result<Expr, String> {
+        if self.is_word("NOT") || self.is_word("EXISTS") {
+            return self.primary();
+        }
+        if self.peek() == Some(&Token::Punct('(')) {
+            self.pos += 1;
+            let e = self.expr()?;
+            self.expect_punct(')')?;
+            return Ok(e);
+        }
+        self.primary()
+    }
+
+    /// 主语 谓词 宾语 (, 宾语)* (; 谓词 宾语 ...)*
+    fn triples_block(&mut self) -> Result<Vec<TriplePattern>, String> {
+        let s = self.pattern_term()?;
+        let mut out = Vec::new();
+        loop {
+            let p = if self.eat_word("a") {
+                PatternTerm::Term(Term::Iri(format!("{}type", RDF)))
+            } else {
+                self.pattern_term()?
+            };
+            loop {
+                let o = self.pattern_term()?;
+                out.push(TriplePattern { s: s.clone(), p: p.clone(), o });
+                if !self.eat_punct(',') {
+                    break;
+                }
+            }
+            if self.eat_punct(';') {
+                while self.eat_punct(';') {}
+                if matches!(self.peek(), Some(Token::Punct('.')) | Some(Token::Punct('}'))) {
+                    break;
+                }
+                continue;
+            }
+            break;
+        }
+        Ok(out)
+    }
+
+    fn pattern_term(&mut self) -> Result<PatternTerm, String> {
+        match self.peek() {
+            Some(Token::Var(_)) => Ok(PatternTerm::Var(self.var()?)),
+            _ => Ok(PatternTerm::Term(self.term()?)),
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.next() {
+            Some(Token::Iri(i)) => Ok(Term::Iri(i)),
+            Some(Token::PName(p, l)) => Ok(Term::Iri(self.expand(&p, &l)?)),
+            Some(Token::Str(s)) => match self.peek() {
+                Some(Token::LangTag(_)) => {
+                    let Some(Token::LangTag(l)) = self.next() else { unreachable!() };
+                    Ok(Term::Literal { value: s, datatype: None, lang: Some(l) })
+                }
+                Some(Token::DataType) => {
+                    self.pos += 1;
+                    let dt = match self.next() {
+                        Some(Token::Iri(i)) => i,
+                        Some(Token::PName(p, l)) => self.expand(&p, &l)?,
+                        other => return Err(format!("期望数据类型，实际为 {:?}", other)),
+                    };
+                    let datatype = (dt != format!("{}string", XSD)).then_some(dt);
+                    Ok(Term::Literal { value: s, datatype, lang: None })
+                }
+                _ => Ok(Term::literal(s)),
+            },
+            Some(Token::Number(n)) => {
+                let dt = if n.contains('.') { "decimal" } else { "integer" };
+                Ok(Term::typed(n, format!("{}{}", XSD, dt)))
+            }
+            Some(Token::Word(w)) if w == "true" || w == "false" => Ok(Term::typed(w, format!("{}boolean", XSD))),
+            other => Err(format!("期望 RDF 项，实际为 {:?}", other)),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.and_expr()?;
+        while self.peek() == Some(&Token::Op("||")) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.compare_expr()?;
+        while self.peek() == Some(&Token::Op("&&")) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.compare_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn compare_expr(&mut self) -> Result<Expr, String> {
+        let left = self.unary()?;
+        if let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if ["=", "!=", "<", ">", "<=", ">="].contains(&op) {
+                self.pos += 1;
+                return Ok(Expr::Compare(Box::new(left), op, Box::new(self.unary()?)));
+            }
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Op("!")) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        if self.eat_word("NOT") {
+            self.expect_word("EXISTS")?;
+            return Ok(Expr::Exists(false, self.group()?));
+        }
+        if self.eat_word("EXISTS") {
+            return Ok(Expr::Exists(true, self.group()?));
+        }
+        match self.peek().cloned() {
+            Some(Token::Punct('(')) => {
+                self.pos += 1;
+                let e = self.expr()?;
+                self.expect_punct(')')?;
+                Ok(e)
+            }
+            Some(Token::Var(v)) => {
+                self.pos += 1;
+                Ok(Expr::Var(v))
+            }
+            Some(Token::Word(w)) if w != "true" && w != "false" => {
+                self.pos += 1;
+                self.expect_punct('(')?;
+                let mut args = Vec::new();
+                if !self.eat_punct(')') {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat_punct(')') {
+                            break;
+                        }
+                        self.expect_punct(',')?;
+                    }
+                }
+                Ok(Expr::Call(w.to_ascii_uppercase(), args))
+            }
+            _ => Ok(Expr::Const(self.term()?)),
+        }
+    }
+}
+
+// --- 5. 求值 ---
+
+struct Evaluator<'a> {
+    store: &'a TripleStore,
+}
+
+impl Evaluator<'_> {
+    fn group(&self, g: &GroupPattern, input: Vec<Binding>) -> Result<Vec<Binding>, String> {
+        let mut solutions = input;
+        for el in &g.elements {
+            solutions = match el {
+                Element::Triples(patterns) => {
+                    let mut acc = solutions;
+                    for tp in patterns {
+                        acc = self.join(tp, acc)?;
+                    }
+                    acc
+                }
+                Element::Optional(inner) => {
+                    let mut out = Vec::new();
+                    for b in solutions {
+                        let ext = self.group(inner, vec![b.clone()])?;
+                        if ext.is_empty() {
+                            out.push(b);
+                        } else {
+                            out.extend(ext);
+                        }
+                    }
+                    out
+                }
+                Element::Union(branches) => {
+                    let mut out = Vec::new();
+                    for branch in branches {
+                        out.extend(self.group(branch, solutions.clone())?);
+                    }
+                    out
+                }
+                Element::Group(inner) => self.group(inner, solutions)?,
+            };
+            if solutions.len() > MAX_SOLUTIONS * 10 {
+                return Err("中间结果过大，请收窄查询条件".into());
+            }
+        }
+        let mut out = Vec::with_capacity(solutions.len());
+        for b in solutions {
+            let mut keep = true;
+            for f in &g.filters {
+                if !self.ebv(f, &b) {
+                    keep = false;
+                    break;
+                }
+            }
+            if keep {
+                out.push(b);
+            }
+        }
+        Ok(out)
+    }
+
+    fn join(&self, tp: &TriplePattern, input: Vec<Binding>) -> Result<Vec<Binding>, String> {
+        let mut out = Vec::new();
+        for b in input {
+            let resolve = |pt: &PatternTerm| -> Option<Term> {
+                match pt {
+                    PatternTerm::Term(t) => Some(t.clone()),
+                    PatternTerm::Var(v) => b.get(v).cloned(),
+                }
+            };
+            let (s, p, o) = (resolve(&tp.s), resolve(&tp.p), resolve(&tp.o));
+            let p_iri = match &p {
+                Some(Term::Iri(i)) => Some(i.as_str()),
+                Some(_) => continue,
+                None => None,
+            };
+            for t in self.store.matching(s.as_ref(), p_iri, o.as_ref()) {
+                let mut nb = b.clone();
+                let mut ok = true;
+                for (pt, val) in [(&tp.s, &t.subject), (&tp.o, &t.object)] {
+                    if let PatternTerm::Var(v) = pt {
+                        match nb.get(v) {
+                            Some(existing) if existing != val => ok = false,
+                            _ => {
+                                nb.insert(v.clone(), val.clone());
+                            }
+                        }
+                    }
+                }
+                if let PatternTerm::Var(v) = &tp.p {
+                    let val = Term::Iri(t.predicate.clone());
+                    match nb.get(v) {
+                        Some(existing) if *existing != val => ok = false,
+                        _ => {
+                            nb.insert(v.clone(), val);
+                        }
+                    }
+                }
+                if ok {
+                    out.push(nb);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// 有效布尔值；求值错误视为 false
+    fn ebv(&self, e: &Expr, b: &Binding) -> bool {
+        match self.eval(e, b) {
+            Some(Term::Literal { value, datatype, .. }) => match datatype.as_deref() {
+                Some(dt) if dt == format!("{}boolean", XSD) => value == "true",
+                Some(dt) if is_numeric(dt) => value.parse::<f64>().is_ok_and(|n| n != 0.0),
+                _ => !value.is_empty(),
+            },
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn eval(&self, e: &Expr, b: &Binding) -> Option<Term> {
+        let boolean = |v: bool| Some(Term::typed(v.to_string(), format!("{}boolean", XSD)));
+        match e {
+            Expr::Var(v) => b.get(v).cloned(),
+            Expr::Const(t) => Some(t.clone()),
+            Expr::Or(l, r) => boolean(self.ebv(l, b) || self.ebv(r, b)),
+            Expr::And(l, r) => boolean(self.ebv(l, b) && self.ebv(r, b)),
+            Expr::Not(x) => boolean(!self.ebv(x, b)),
+            Expr::Exists(positive, g) => {
+                let found = self.group(g, vec![b.clone()]).map(|r| !r.is_empty()).unwrap_or(false);
+                boolean(found == *positive)
+            }
+            Expr::Compare(l, op, r) => {
+                let (l, r) = (self.eval(l, b)?, self.eval(r, b)?);
+                let ord = compare_terms(&l, &r);
+                boolean(match *op {
+                    "=" => l == r || (ord == Ordering::Equal && both_numeric(&l, &r)),
+                    "!=" => !(l == r || (ord == Ordering::Equal && both_numeric(&l, &r))),
+                    "<" => ord == Ordering::Less,
+                    ">" => ord == Ordering::Greater,
+                    "<=" => ord != Ordering::Greater,
+                    ">=" => ord != Ordering::Less,
+                    _ => return None,
+                })
+            }
+            Expr::Call(name, args) => {
+                let arg = |i: usize| args.get(i).and_then(|a| self.eval(a, b));
+                let text = |i: usize| arg(i).map(|t| lexical(&t));
+                match name.as_str() {
+                    "BOUND" => match args.first() {
+                        Some(Expr::Var(v)) => boolean(b.contains_key(v)),
+                        _ => None,
+                    },
+                    "STR" => text(0).map(Term::literal),
+                    "LCASE" => text(0).map(|s| Term::literal(s.to_lowercase())),
+                    "UCASE" => text(0).map(|s| Term::literal(s.to_uppercase())),
+                    "STRLEN" => text(0).map(|s| Term::typed(s.chars().count().to_string(), format!("{}integer", XSD))),
+                    "CONTAINS" => boolean(text(0)?.contains(&text(1)?)),
+                    "STRSTARTS" => boolean(text(0)?.starts_with(&text(1)?)),
+                    "STRENDS" => boolean(text(0)?.ends_with(&text(1)?)),
+                    "LANG" => match arg(0)? {
+                        Term::Literal { lang, .. } => Some(Term::literal(lang.unwrap_or_default())),
+                        _ => None,
+                    },
+                    "ISIRI" | "ISURI" => boolean(matches!(arg(0)?, Term::Iri(_))),
+                    "ISLITERAL" => boolean(matches!(arg(0)?, Term::Literal { .. })),
+                    "ISBLANK" => boolean(matches!(arg(0)?, Term::Blank(_))),
+                    "REGEX" => {
+                        let flags = text(2).unwrap_or_default();
+                        let pattern = if flags.contains('i') { format!("(?i){}", text(1)?) } else { text(1)? };
+                        let re = regex::Regex::new(&pattern).ok()?;
+                        boolean(re.is_match(&text(0)?))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+fn is_numeric(dt: &str) -> bool {
+    ["integer", "decimal", "double", "float", "long", "int"].iter().any(|t| dt == format!("{}{}", XSD, t))
+}
+
+fn numeric_value(t: &Term) -> Option<f64> {
+    match t {
+        Term::Literal { value, datatype: Some(dt), .. } if is_numeric(dt) => value.parse().ok(),
+        _ => None,
+    }
+}
+
+fn both_numeric(l: &Term, r: &Term) -> bool {
+    numeric_value(l).is_some() && numeric_value(r).is_some()
+}
+
+fn lexical(t: &Term) -> String {
+    match t {
+        Term::Iri(i) => i.clone(),
+        Term::Blank(b) => b.clone(),
+        Term::Literal { value, .. } => value.clone(),
+    }
+}
+
+/// 排序与比较：数值按数值，其余按词法形式
+fn compare_terms(l: &Term, r: &Term) -> Ordering {
+    match (numeric_value(l), numeric_value(r)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => lexical(l).cmp(&lexical(r)),
+    }
+}
+
+// --- 6. 执行与 SPARQL JSON 结果 ---
+
+/// 执行查询并返回 SPARQL 1.1 Query Results JSON
+pub fn execute(store: &TripleStore, query: &Query) -> Result<Value, String> {
+    let ev = Evaluator { store };
+    let mut solutions = ev.group(&query.pattern, vec![Binding::new()])?;
+
+    let (distinct, projection) = match &query.form {
+        QueryForm::Ask => return Ok(json!({ "head": {}, "boolean": !solutions.is_empty() })),
+        QueryForm::Select { distinct, projection } => (*distinct, projection),
+    };
+
+    let has_aggregate = projection.iter().flatten().any(|p| matches!(p, Projection::Count { .. }));
+    if has_aggregate || !query.group_by.is_empty() {
+        solutions = aggregate(solutions, &query.group_by, projection.as_deref().unwrap_or(&[]))?;
+    }
+
+    if !query.order_by.is_empty() {
+        solutions.sort_by(|a, b| {
+            for (e, desc) in &query.order_by {
+                let ord = match (ev.eval(e, a), ev.eval(e, b)) {
+                    (Some(x), Some(y)) => compare_terms(&x, &y),
+                    (None, Some(_)) => Ordering::Less,
+                    (Some(_), None) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                let ord = if *desc { ord.reverse() } else { ord };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            Ordering::Equal
+        });
+    }
+
+    let vars: Vec<String> = match projection {
+        Some(items) => items
+            .iter()
+            .map(|p| match p {
+                Projection::Var(v) => v.clone(),
+                Projection::Count { alias, .. } => alias.clone(),
+            })
+            .collect(),
+        None => {
+            let mut seen = HashSet::new();
+            let mut vars = Vec::new();
+            collect_vars(&query.pattern, &mut vars, &mut seen);
+            vars
+        }
+    };
+
+    let mut rows: Vec<Map<String, Value>> = Vec::new();
+    let mut seen_rows = HashSet::new();
+    for b in solutions {
+        let row: Map<String, Value> = vars
+            .iter()
+            .filter_map(|v| b.get(v).map(|t| (v.clone(), term_json(t))))
+            .collect();
+        if distinct && !seen_rows.insert(Value::Object(row.clone()).to_string()) {
+            continue;
+        }
+        rows.push(row);
+    }
+    let limit = query.limit.unwrap_or(MAX_SOLUTIONS).min(MAX_SOLUTIONS);
+    let bindings: Vec<Value> = rows.into_iter().skip(query.offset).take(limit).map(Value::Object).collect();
+    Ok(json!({ "head": { "vars": vars }, "results": { "bindings": bindings } }))
+}
+
+fn aggregate(solutions: Vec<Binding>, group_by: &[String], projection: &[Projection]) -> Result<Vec<Binding>, String> {
+    for p in projection {
+        if let Projection::Var(v) = p {
+            if !group_by.contains(v) {
+                return Err(format!("变量 ?{} 未出现在 GROUP BY 中", v));
+            }
+        }
+    }
+    let mut groups: Vec<(Vec<Option<Term>>, Vec<Binding>)> = Vec::new();
+    for b in solutions {
+        let key: Vec<Option<Term>> = group_by.iter().map(|v| b.get(v).cloned()).collect();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(b),
+            None => groups.push((key, vec![b])),
+        }
+    }
+    // 无 GROUP BY 的聚合：空输入也产出一行 (COUNT = 0)
+    if groups.is_empty() && group_by.is_empty() {
+        groups.push((Vec::new(), Vec::new()));
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(key, members)| {
+            let mut out: Binding = group_by.iter().cloned().zip(key).filter_map(|(v, t)| t.map(|t| (v, t))).collect();
+            for p in projection {
+                if let Projection::Count { var, distinct, alias } = p {
+                    let count = match var {
+                        None => members.len(),
+                        Some(v) => {
+                            let values = members.iter().filter_map(|m| m.get(v));
+                            if *distinct {
+                                values.collect::<HashSet<_>>().len()
+                            } else {
+                                values.count()
+                            }
+                        }
+                    };
+                    out.insert(alias.clone(), Term::typed(count.to_string(), format!("{}integer", XSD)));
+                }
+            }
+            out
+        })
+        .collect())
+}
+
+fn collect_vars(g: &GroupPattern, vars: &mut Vec<String>, seen: &mut HashSet<String>) {
+    for el in &g.elements {
+        match el {
+            Element::Triples(patterns) => {
+                for tp in patterns {
+                    for pt in [&tp.s, &tp.p, &tp.o] {
+                        if let PatternTerm::Var(v) = pt {
+                            if seen.insert(v.clone()) {
+                                vars.push(v.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            Element::Optional(inner) | Element::Group(inner) => collect_vars(inner, vars, seen),
+            Element::Union(branches) => branches.iter().for_each(|b| collect_vars(b, vars, seen)),
+        }
+    }
+}
+
+fn term_json(t: &Term) -> Value {
+    match t {
+        Term::Iri(i) => json!({ "type": "uri", "value": i }),
+        Term::Blank(b) => json!({ "type": "bnode", "value": b }),
+        Term::Literal { value, lang: Some(l), .. } => json!({ "type": "literal", "value": value, "xml:lang": l }),
+        Term::Literal { value, datatype: Some(dt), .. } => json!({ "type": "literal", "value": value, "datatype": dt }),
+        Term::Literal { value, .. } => json!({ "type": "literal", "value": value }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bundle::fixtures::sample_bundle;
+
+    fn sample_store() -> TripleStore {
+        let ns = Namespaces::new(Some("http://test/sse/"));
+        TripleStore::new(bundle_to_graph(&sample_bundle(), &ns), ns.prefixes())
+    }
+
+    /// 三个个体各有一个整数 ex:v（1、5、9）
+    fn numeric_store() -> TripleStore {
+        let triples = [("a", "1"), ("b", "5"), ("c", "9")]
+            .into_iter()
+            .map(|(s, v)| Triple {
+                subject: Term::Iri(format!("http://ex/{}", s)),
+                predicate: "http://ex/v".to_string(),
+                object: Term::typed(v, format!("{}integer", XSD)),
+            })
+            .collect();
+        TripleStore::new(triples, vec![("ex".to_string(), "http://ex/".to_string())])
+    }
+
+    fn run(store: &TripleStore, text: &str) -> Value {
+        let query = parse_query(text, store.prefixes()).unwrap_or_else(|e| panic!("{}: {}", text, e));
+        execute(store, &query).unwrap()
+    }
+
+    /// 结果中某变量的取值（IRI 取末段），排序后比较
+    fn column(result: &Value, var: &str) -> Vec<String> {
+        let mut out: Vec<String> = result["results"]["bindings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| match b.get(var) {
+                Some(t) => t["value"].as_str().unwrap().rsplit('/').next().unwrap().to_string(),
+                None => "-".to_string(),
+            })
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn metrics_supporting_dimension() {
+        let store = sample_store();
+        let r = run(&store, "SELECT ?m WHERE { ?m a sse:Metric ; sse:hasDimension node:region }");
+        assert_eq!(column(&r, "m"), ["order_count", "revenue"]);
+        let r = run(&store, "SELECT ?m WHERE { ?m sse:hasDimension node:channel }");
+        assert_eq!(column(&r, "m"), ["revenue"]);
+    }
+
+    #[test]
+    fn orphaned_dimensions_via_not_exists() {
+        let store = sample_store();
+        let r = run(
+            &store,
+            "SELECT ?d WHERE { ?d a sse:Dimension . FILTER NOT EXISTS { ?m sse:hasDimension ?d } }",
+        );
+        assert_eq!(column(&r, "d"), ["orphan"]);
+        let r = run(&store, "ASK { ?m sse:hasDimension node:orphan }");
+        assert_eq!(r["boolean"], false);
+    }
+
+    #[test]
+    fn optional_keeps_unmatched_solutions() {
+        let store = sample_store();
+        let r = run(&store, "SELECT ?m ?u WHERE { ?m a sse:Metric OPTIONAL { ?m sse:unit ?u } }");
+        assert_eq!(column(&r, "m"), ["order_count", "revenue"]);
+        assert_eq!(column(&r, "u"), ["-", "元"]);
+    }
+
+    #[test]
+    fn union_merges_branches() {
+        let store = sample_store();
+        let r = run(&store, "SELECT ?x WHERE { { ?x a sse:Metric } UNION { ?x a sse:Domain } }");
+        assert_eq!(column(&r, "x"), ["order_count", "revenue", "sales"]);
+    }
+
+    #[test]
+    fn count_with_group_by() {
+        let store = sample_store();
+        let r = run(
+            &store,
+            "SELECT ?d (COUNT(?m) AS ?n) WHERE { ?m sse:hasDimension ?d } GROUP BY ?d ORDER BY ?d",
+        );
+        let rows: Vec<(String, String)> = r["results"]["bindings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| (b["d"]["value"].as_str().unwrap().to_string(), b["n"]["value"].as_str().unwrap().to_string()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("http://test/sse/node/channel".to_string(), "1".to_string()),
+                ("http://test/sse/node/region".to_string(), "2".to_string())
+            ]
+        );
+        // 无 GROUP BY 的计数在空结果上也产出 0
+        let r = run(&store, "SELECT (COUNT(*) AS ?n) WHERE { ?m sse:hasDimension node:orphan }");
+        assert_eq!(r["results"]["bindings"][0]["n"]["value"], "0");
+    }
+
+    #[test]
+    fn member_concepts_and_alt_labels() {
+        let store = sample_store();
+        let r = run(
+            &store,
+            r#"SELECT ?code WHERE { ?c skos:inScheme node:region ; skos:notation ?code ; skos:altLabel "东区" }"#,
+        );
+        assert_eq!(column(&r, "code"), ["EAST"]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let prefixes = sample_store().prefixes().to_vec();
+        let err = parse_query("SELECT ?x WHERE { ?x a foo:Bar }", &prefixes).unwrap_err();
+        assert!(err.contains("未声明的前缀: foo"), "{}", err);
+        let err = parse_query(r#"SELECT ?x WHERE { ?x rdfs:label "unterminated }"#, &prefixes).unwrap_err();
+        assert!(err.contains("字符串未闭合"), "{}", err);
+        let err = parse_query("SELECT ?x WHERE { ?x a sse:Metric", &prefixes).unwrap_err();
+        assert!(err.contains("图模式未闭合"), "{}", err);
+        let err = parse_query("DESCRIBE ?x", &prefixes).unwrap_err();
+        assert!(err.contains("仅支持 SELECT 与 ASK"), "{}", err);
+    }
+
+    #[test]
+    fn less_than_versus_iri() {
+        let store = numeric_store();
+        let pairs = |text: &str| -> Vec<String> {
+            let r = run(&store, text);
+            let mut out: Vec<String> = r["results"]["bindings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|b| {
+                    let last = |v: &str| b[v]["value"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+                    format!("{}{}", last("x"), last("y"))
+                })
+                .collect();
+            out.sort();
+            out
+        };
+        // 无空白的 "<" 在表达式中是运算符
+        assert_eq!(pairs("SELECT ?x ?y WHERE { ?x ex:v ?a . ?y ex:v ?b FILTER(?a<?b) }"), ["ab", "ac", "bc"]);
+        assert_eq!(pairs("SELECT ?x ?y WHERE { ?x ex:v ?a . ?y ex:v ?b FILTER(?a<=?b&&?b<9) }"), ["aa", "ab", "bb"]);
+        // "<3&&?b>" 形似 IRI，但位于表达式中的操作数之后
+        assert_eq!(pairs("SELECT ?x ?y WHERE { ?x ex:v ?a . ?y ex:v ?b FILTER(?a<3&&?b>5) }"), ["ac"]);
+        // 三元组模式中变量之后的 "<...>" 仍是 IRI，表达式中运算符之后亦然
+        assert_eq!(pairs("SELECT ?x ?y WHERE { ?x <http://ex/v> ?a . ?y <http://ex/v> ?b FILTER(?x = <http://ex/a> && ?a < ?b) }"), ["ab", "ac"]);
+    }
+}
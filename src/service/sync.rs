@@ -0,0 +1,350 @@
+use crate::api::mapping::{full_reload_semantic_engine, source_connector};
+use crate::ax_state::AppState;
+use crate::core::normalize::normalize_value;
+use crate::models::schema::SyncRun;
+use futures::StreamExt;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// 单批写入的码值数量
+const BATCH_SIZE: usize = 1000;
+/// 调度器轮询间隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// 单次同步的统计
+#[derive(Debug, Default)]
+struct SyncCounts {
+    scanned: i32,
+    inserted: i32,
+    reactivated: i32,
+    deactivated: i32,
+    relabeled: i32,
+    label_conflicts: i32,
+}
+
+/// 同步单个维度的码值，并记录到 sync_runs
+/// 失败时同样返回运行记录（status = FAILED，error 记录原因）；维度不存在返回 None
+pub async fn sync_dimension(state: &AppState, node_id: Uuid, trigger: &str) -> anyhow::Result<Option<SyncRun>> {
+    let run_id: Uuid = match sqlx::query_scalar(
+        "INSERT INTO sync_runs (dimension_node_id, trigger, status)
+         SELECT id, $2, 'RUNNING' FROM ontology_nodes WHERE id = $1 AND node_role = 'DIMENSION' RETURNING id",
+    )
+    .bind(node_id)
+    .bind(trigger)
+    .fetch_optional(&state.db)
+    .await?
+    {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+
+    let result = run_sync(state, node_id).await;
+    let (status, counts, error) = match result {
+        Ok(c) => ("SUCCESS", c, None),
+        Err(e) => ("FAILED", SyncCounts::default(), Some(e.to_string())),
+    };
+
+    let run = sqlx::query_as::<Postgres, SyncRun>(
+        "UPDATE sync_runs SET status = $2, scanned_count = $3, inserted_count = $4, reactivated_count = $5,
+             deactivated_count = $6, relabeled_count = $7, label_conflict_count = $8, error = $9, finished_at = NOW()
+         WHERE id = $1 RETURNING *",
+    )
+    .bind(run_id)
+    .bind(status)
+    .bind(counts.scanned)
+    .bind(counts.inserted)
+    .bind(counts.reactivated)
+    .bind(counts.deactivated)
+    .bind(counts.relabeled)
+    .bind(counts.label_conflicts)
+    .bind(&error)
+    .fetch_one(&state.db)
+    .await?;
+
+    match &error {
+        None => info!(
+            "✅ A-Box 同步完成: node={}, 扫描 {}, 新增 {}, 恢复 {}, 失效 {}, 更新标签 {}, 标签冲突 {}",
+            node_id,
+            counts.scanned,
+            counts.inserted,
+            counts.reactivated,
+            counts.deactivated,
+            counts.relabeled,
+            counts.label_conflicts
+        ),
+        Some(e) => warn!("A-Box 同步失败: node={}, {}", node_id, e),
+    }
+    if counts.inserted + counts.reactivated + counts.deactivated + counts.relabeled > 0 {
+        let _ = full_reload_semantic_engine(state).await;
+    }
+    Ok(Some(run))
+}
+
+/// 流式读取物理库去重码值，分批写入；整个同步在一个事务内完成
+async fn run_sync(state: &AppState, node_id: Uuid) -> anyhow::Result<SyncCounts> {
+    let def = sqlx::query("SELECT source_id, target_table, sql_expression FROM semantic_definitions WHERE node_id = $1")
+        .bind(node_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("维度定义不存在"))?;
+    let source_id: String = def.get("source_id");
+    let target_table: String = def.get("target_table");
+    let sql_expression: String = def.get("sql_expression");
+
+    // 标签来源：码表 (码值列 + 标签列) 或维度所在表的标签列；未配置时标签即码值
+    let label_source = sqlx::query(
+        "SELECT label_expression, lookup_table, lookup_code_column FROM dimension_label_sources WHERE dimension_node_id = $1",
+    )
+    .bind(node_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let (code_expr, table, label_expr) = match label_source {
+        Some(r) => match (r.get::<Option<String>, _>(1), r.get::<Option<String>, _>(2)) {
+            (Some(lookup_table), Some(code_column)) => (code_column, lookup_table, Some(r.get::<String, _>(0))),
+            _ => (sql_expression, target_table, Some(r.get::<String, _>(0))),
+        },
+        None => (sql_expression, target_table, None),
+    };
+
+    let conn = source_connector(state, &source_id)
+        .await
+        .map_err(|_| anyhow::anyhow!("无法连接数据源: {}", source_id))?;
+
+    let mut tx = state.db.begin().await?;
+    // 同一维度同一时刻只允许一个同步（手动与调度可能并发触发）
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock(hashtext($1::text))")
+        .bind(node_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !locked {
+        anyhow::bail!("该维度正在同步中");
+    }
+
+    let sql = conn.dialect().distinct_values_sql(&code_expr, label_expr.as_deref(), &table);
+    info!("开始 A-Box 同步，物理查询: {}", sql);
+    let mut counts = SyncCounts::default();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    // 同一码值只取首个标签；标签已被其他码值占用（库中已有或本次先出现）时以码值本身作标签，
+    // 计入标签冲突，码值照常写入。库中已有的标签优先，避免标签随读取顺序在码值间漂移
+    let holders: HashMap<String, String> =
+        sqlx::query_as("SELECT value_label, value_code FROM dimension_values WHERE dimension_node_id = $1")
+            .bind(node_id)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+    let (mut seen_codes, mut seen_labels) = (HashSet::new(), HashSet::new());
+    let mut rows = conn.stream_rows(&sql);
+    while let Some(row) = rows.next().await {
+        let row = row?;
+        let Some(code) = row["val"].as_str().map(String::from) else {
+            continue;
+        };
+        if !seen_codes.insert(code.clone()) {
+            continue;
+        }
+        let label = match row["label"].as_str().map(str::trim).filter(|l| !l.is_empty()) {
+            Some(l) if holders.get(l).is_some_and(|c| *c != code) || seen_labels.contains(l) => {
+                counts.label_conflicts += 1;
+                code.clone()
+            }
+            Some(l) => l.to_string(),
+            None => code.clone(),
+        };
+        seen_labels.insert(label.clone());
+        batch.push((code, label));
+        if batch.len() >= BATCH_SIZE {
+            flush_batch(&mut tx, node_id, &mut batch, &mut counts).await?;
+        }
+    }
+    flush_batch(&mut tx, node_id, &mut batch, &mut counts).await?;
+
+    // 本事务内 NOW() 恒为事务开始时间：本次未被触达的码值 last_seen_at 必然更早
+    counts.deactivated = sqlx::query(
+        "UPDATE dimension_values SET is_active = FALSE
+         WHERE dimension_node_id = $1 AND is_active AND last_seen_at < NOW()",
+    )
+    .bind(node_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected() as i32;
+
+    tx.commit().await?;
+    Ok(counts)
+}
+
+/// 写入一批 (码值, 标签)：已有码值刷新活跃状态与标签（人工锁定的标签除外），新码值插入
+async fn flush_batch(
+    tx: &mut Transaction<'_, Postgres>,
+    node_id: Uuid,
+    batch: &mut Vec<(String, String)>,
+    counts: &mut SyncCounts,
+) -> anyhow::Result<()> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    counts.scanned += batch.len() as i32;
+    let (codes, labels): (Vec<String>, Vec<String>) = batch.drain(..).unzip();
+    let norm_codes: Vec<String> = codes.iter().map(|c| normalize_value(c)).collect();
+    let norm_labels: Vec<String> = labels.iter().map(|l| normalize_value(l)).collect();
+
+    counts.reactivated += sqlx::query(
+        "UPDATE dimension_values SET is_active = TRUE
+         WHERE dimension_node_id = $1 AND NOT is_active AND value_code = ANY($2)",
+    )
+    .bind(node_id)
+    .bind(&codes)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected() as i32;
+
+    sqlx::query("UPDATE dimension_values SET last_seen_at = NOW() WHERE dimension_node_id = $1 AND value_code = ANY($2)")
+        .bind(node_id)
+        .bind(&codes)
+        .execute(&mut **tx)
+        .await?;
+
+    // 标签变化：目标标签已被其他码值占用时保持原标签，未能更新的计入标签冲突
+    counts.relabeled += sqlx::query(
+        "UPDATE dimension_values d SET value_label = b.label, normalized_label = b.norm
+         FROM UNNEST($2::text[], $3::text[], $4::text[]) AS b(code, label, norm)
+         WHERE d.dimension_node_id = $1 AND d.value_code = b.code AND NOT d.label_locked AND d.value_label <> b.label
+           AND NOT EXISTS (SELECT 1 FROM dimension_values x WHERE x.dimension_node_id = $1 AND x.value_label = b.label)",
+    )
+    .bind(node_id)
+    .bind(&codes)
+    .bind(&labels)
+    .bind(&norm_labels)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected() as i32;
+
+    let blocked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM dimension_values d JOIN UNNEST($2::text[], $3::text[]) AS b(code, label) ON d.value_code = b.code
+         WHERE d.dimension_node_id = $1 AND NOT d.label_locked AND d.value_label <> b.label",
+    )
+    .bind(node_id)
+    .bind(&codes)
+    .bind(&labels)
+    .fetch_one(&mut **tx)
+    .await?;
+    counts.label_conflicts += blocked as i32;
+
+    // 新码值：标签已被其他码值占用时以码值作标签，码值本身不会因标签冲突被丢弃
+    let inserted: Vec<(String, String)> = sqlx::query_as(
+        "INSERT INTO dimension_values (dimension_node_id, value_label, value_code, normalized_label)
+         SELECT $1, CASE WHEN taken THEN b.code ELSE b.label END, b.code, CASE WHEN taken THEN b.code_norm ELSE b.norm END
+         FROM UNNEST($2::text[], $3::text[], $4::text[], $5::text[]) AS b(code, label, norm, code_norm)
+         CROSS JOIN LATERAL (SELECT EXISTS (
+             SELECT 1 FROM dimension_values x WHERE x.dimension_node_id = $1 AND x.value_label = b.label
+         ) AS taken) t
+         ON CONFLICT (dimension_node_id, value_code) DO NOTHING
+         RETURNING value_code, value_label",
+    )
+    .bind(node_id)
+    .bind(&codes)
+    .bind(&labels)
+    .bind(&norm_labels)
+    .bind(&norm_codes)
+    .fetch_all(&mut **tx)
+    .await?;
+    let wanted: HashMap<&str, &str> = codes.iter().map(String::as_str).zip(labels.iter().map(String::as_str)).collect();
+    counts.inserted += inserted.len() as i32;
+    counts.label_conflicts += inserted
+        .iter()
+        .filter(|(code, label)| wanted.get(code.as_str()).is_some_and(|w| *w != label))
+        .count() as i32;
+
+    Ok(())
+}
+
+/// 补齐缺失的归一化形式（历史数据或手工导入的码值/同义词）
+/// 修改 SSE_VALUE_STRIP_SUFFIXES 后，可将两列置空后重启以按新规则重新计算
+pub async fn backfill_normalized(db: &PgPool) -> anyhow::Result<u64> {
+    let values: Vec<(i32, String)> =
+        sqlx::query_as("SELECT id, value_label FROM dimension_values WHERE normalized_label IS NULL")
+            .fetch_all(db)
+            .await?;
+    let synonyms: Vec<(i32, String)> =
+        sqlx::query_as("SELECT id, synonym FROM dimension_value_synonyms WHERE normalized IS NULL")
+            .fetch_all(db)
+            .await?;
+    if values.is_empty() && synonyms.is_empty() {
+        return Ok(0);
+    }
+
+    let mut tx = db.begin().await?;
+    let mut filled = 0;
+    for (table, column, rows) in [
+        ("dimension_values", "normalized_label", values),
+        ("dimension_value_synonyms", "normalized", synonyms),
+    ] {
+        for chunk in rows.chunks(BATCH_SIZE) {
+            let ids: Vec<i32> = chunk.iter().map(|(id, _)| *id).collect();
+            let norms: Vec<String> = chunk.iter().map(|(_, text)| normalize_value(text)).collect();
+            filled += sqlx::query(&format!(
+                "UPDATE {table} t SET {column} = b.norm FROM UNNEST($1::int[], $2::text[]) AS b(id, norm) WHERE t.id = b.id"
+            ))
+            .bind(&ids)
+            .bind(&norms)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+    }
+    tx.commit().await?;
+    info!("🔤 已补齐 {} 条码值归一化形式", filled);
+    Ok(filled)
+}
+
+/// 将 "30m" / "6h" / "1d" / "90"（秒）解析为秒数
+pub fn parse_interval(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let (num, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_lowercase()),
+        _ => (s, 's'),
+    };
+    let n: i32 = num.trim().parse().ok()?;
+    let factor = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        _ => return None,
+    };
+    n.checked_mul(factor)
+}
+
+/// 启动后台同步调度：周期性认领到期的维度调度并依次执行
+/// 认领与推进 next_run_at 在同一条 UPDATE 中完成，多实例部署时不会重复执行
+pub fn spawn_scheduler(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        info!("⏰ A-Box 同步调度器已启动");
+        let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+        loop {
+            ticker.tick().await;
+            let due: Vec<Uuid> = match sqlx::query_scalar(
+                "UPDATE dimension_sync_schedules SET next_run_at = NOW() + interval_secs * INTERVAL '1 second'
+                 WHERE enabled AND next_run_at <= NOW() RETURNING dimension_node_id",
+            )
+            .fetch_all(&state.db)
+            .await
+            {
+                Ok(ids) => ids,
+                Err(e) => {
+                    warn!("同步调度查询失败: {}", e);
+                    continue;
+                }
+            };
+            for node_id in due {
+                if let Err(e) = sync_dimension(&state, node_id, "SCHEDULED").await {
+                    warn!("调度同步记录失败: node={}, {}", node_id, e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,188 @@
+use crate::ax_state::AppState;
+use crate::core::inference::InferenceResult;
+use crate::core::planner;
+use crate::infra::connector::DataSourceConnector;
+use crate::models::revision::{ValidationIssue, ValidationReport};
+use crate::models::schema::{DataSource, FullSemanticNode};
+use sqlx::{PgPool, Postgres, Row};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+/// 单条 EXPLAIN 的超时，避免不可达的数据源拖住发布
+const EXPLAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) fn issue(severity: &str, node_key: &str, check: &str, message: String) -> ValidationIssue {
+    ValidationIssue { severity: severity.to_string(), node_key: node_key.to_string(), check: check.to_string(), message }
+}
+
+/// 发布前校验：维度引用完整、别名不冲突、SQL 表达式可在数据源上编译
+pub async fn validate(state: &AppState, version: i64, nodes: &[FullSemanticNode]) -> anyhow::Result<ValidationReport> {
+    let mut issues = check_dimension_refs(nodes);
+    issues.extend(check_alias_collisions(nodes, &node_domains(&state.db, nodes).await?));
+    issues.extend(check_sql(state, nodes).await?);
+
+    let passed = issues.iter().all(|i| i.severity != "ERROR");
+    info!("🔎 本体校验完成: 版本 {}, {} 个节点, {} 个问题", version, nodes.len(), issues.len());
+    Ok(ValidationReport { version, checked_nodes: nodes.len(), passed, issues })
+}
+
+// --- 1. T-Box 引用 ---
+
+/// 指标引用的维度必须存在于同一版本中，且为维度节点
+pub fn check_dimension_refs(nodes: &[FullSemanticNode]) -> Vec<ValidationIssue> {
+    let by_id: HashMap<Uuid, &FullSemanticNode> = nodes.iter().map(|n| (n.id, n)).collect();
+    let mut issues = Vec::new();
+    for m in nodes.iter().filter(|n| n.node_role == "METRIC") {
+        for dim_id in &m.supported_dimension_ids {
+            match by_id.get(dim_id) {
+                None => issues.push(issue("ERROR", &m.node_key, "dimension_ref", format!("引用的维度 {} 不存在", dim_id))),
+                Some(d) if d.node_role != "DIMENSION" => issues.push(issue(
+                    "ERROR",
+                    &m.node_key,
+                    "dimension_ref",
+                    format!("引用的节点 {} 不是维度 (role={})", d.node_key, d.node_role),
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+    issues
+}
+
+// --- 2. 别名冲突 ---
+
+/// 节点所属业务域（未归属数据集或数据集未挂业务域的节点为共享节点）
+pub async fn node_domains(db: &PgPool, nodes: &[FullSemanticNode]) -> sqlx::Result<HashMap<Uuid, Option<Uuid>>> {
+    let datasets: HashMap<Uuid, Option<Uuid>> = sqlx::query("SELECT id, domain_id FROM semantic_datasets")
+        .fetch_all(db)
+        .await?
+        .iter()
+        .map(|r| (r.get(0), r.get(1)))
+        .collect();
+    Ok(nodes
+        .iter()
+        .map(|n| (n.id, n.dataset_id.and_then(|ds| datasets.get(&ds).copied().flatten())))
+        .collect())
+}
+
+/// 标签与别名（与 FST 索引一致按小写比较）在可同时可见的节点间不得重复
+/// 不同业务域下的同名节点由提问时的业务域区分，不算冲突；共享节点与任何业务域可见
+pub fn check_alias_collisions(
+    nodes: &[FullSemanticNode],
+    domains: &HashMap<Uuid, Option<Uuid>>,
+) -> Vec<ValidationIssue> {
+    let mut terms: BTreeMap<String, Vec<&FullSemanticNode>> = BTreeMap::new();
+    for n in nodes {
+        let mut own: Vec<String> = std::iter::once(&n.label)
+            .chain(n.alias_names.iter())
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        own.sort();
+        own.dedup();
+        for t in own {
+            terms.entry(t).or_default().push(n);
+        }
+    }
+
+    let domain_of = |n: &FullSemanticNode| domains.get(&n.id).copied().flatten();
+    let mut issues = Vec::new();
+    for (term, owners) in terms.iter().filter(|(_, o)| o.len() > 1) {
+        for (i, a) in owners.iter().enumerate() {
+            for b in &owners[i + 1..] {
+                let overlap = match (domain_of(a), domain_of(b)) {
+                    (Some(x), Some(y)) => x == y,
+                    _ => true,
+                };
+                if overlap {
+                    issues.push(issue(
+                        "ERROR",
+                        &b.node_key,
+                        "alias_collision",
+                        format!("词条 '{}' 同时指向 {} 与 {}", term, a.node_key, b.node_key),
+                    ));
+                }
+            }
+        }
+    }
+    issues
+}
+
+// --- 3. SQL 可编译性 ---
+
+/// 按数据源分组，用 EXPLAIN 编译节点生成的 SQL：
+/// 指标编译其默认聚合查询（含隐含约束），再逐个带上支持的维度分组编译；维度编译其码值查询
+async fn check_sql(state: &AppState, nodes: &[FullSemanticNode]) -> anyhow::Result<Vec<ValidationIssue>> {
+    let by_id: HashMap<Uuid, &FullSemanticNode> = nodes.iter().map(|n| (n.id, n)).collect();
+    let mut by_source: BTreeMap<&str, Vec<&FullSemanticNode>> = BTreeMap::new();
+    for n in nodes.iter().filter(|n| !n.target_table.is_empty()) {
+        by_source.entry(n.source_id.as_str()).or_default().push(n);
+    }
+
+    let mut issues = Vec::new();
+    for (source_id, members) in by_source {
+        let conn = match connect(state, source_id).await? {
+            Ok(c) => c,
+            Err(msg) => {
+                for n in members {
+                    issues.push(issue("ERROR", &n.node_key, "sql_compile", format!("数据源 {} 不可用: {}", source_id, msg)));
+                }
+                continue;
+            }
+        };
+        let dialect = conn.dialect();
+
+        for n in members {
+            if n.node_role != "METRIC" {
+                let sql = dialect.distinct_values_sql(&n.sql_expression, None, &n.target_table);
+                if let Err(e) = explain(conn.as_ref(), &sql).await {
+                    issues.push(issue("ERROR", &n.node_key, "sql_compile", e));
+                }
+                continue;
+            }
+
+            let plan = |group_by: Vec<FullSemanticNode>| {
+                planner::build_plan("", InferenceResult { metric: n.clone(), filters: Vec::new(), group_by })
+            };
+            if let Err(e) = explain(conn.as_ref(), &planner::compile_sql(&plan(Vec::new()), dialect)).await {
+                issues.push(issue("ERROR", &n.node_key, "sql_compile", e));
+                continue;
+            }
+            for dim in n.supported_dimension_ids.iter().filter_map(|id| by_id.get(id)) {
+                let sql = planner::compile_sql(&plan(vec![(*dim).clone()]), dialect);
+                if let Err(e) = explain(conn.as_ref(), &sql).await {
+                    issues.push(issue(
+                        "ERROR",
+                        &dim.node_key,
+                        "sql_compile",
+                        format!("在指标 {} 的表 {} 上无法编译: {}", n.node_key, n.target_table, e),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// 数据源配置缺失或建连失败作为校验问题返回（外层错误仅为内部库访问失败）
+pub(crate) async fn connect(state: &AppState, source_id: &str) -> sqlx::Result<Result<Arc<dyn DataSourceConnector>, String>> {
+    let Some(source) = sqlx::query_as::<Postgres, DataSource>("SELECT * FROM data_sources WHERE id = $1")
+        .bind(source_id)
+        .fetch_optional(&state.db)
+        .await?
+    else {
+        return Ok(Err("数据源配置不存在".to_string()));
+    };
+    Ok(state.pool_manager.get_or_create_pool(&source).await.map_err(|e| e.to_string()))
+}
+
+async fn explain(conn: &dyn DataSourceConnector, sql: &str) -> Result<(), String> {
+    match tokio::time::timeout(EXPLAIN_TIMEOUT, conn.explain(sql)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("EXPLAIN 超时 ({}s)", EXPLAIN_TIMEOUT.as_secs())),
+    }
+}